@@ -15,7 +15,7 @@ pub fn bench_gen(c: &mut Criterion) {
                 alpha: thread_rng().gen(),
                 beta: thread_rng().gen(),
             };
-            dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+            dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
         })
     });
 }
@@ -29,7 +29,10 @@ pub fn bench_eval(c: &mut Criterion) {
         alpha: thread_rng().gen(),
         beta: thread_rng().gen(),
     };
-    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+    let [k, _] = dcf
+        .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+        .unwrap()
+        .split();
 
     c.bench_function("eval", |b| {
         b.iter(|| {
@@ -37,10 +40,92 @@ pub fn bench_eval(c: &mut Criterion) {
             let dcf = DcfImpl::<16, 16, _>::new(prg);
             let x: [u8; 16] = thread_rng().gen();
             let mut y = [0; 16];
-            dcf.eval(false, &k, &[&x], &mut [&mut y]);
+            dcf.eval(false, &k, &[&x], &mut [&mut y]).unwrap();
         })
     });
 }
 
-criterion_group!(benches, bench_gen, bench_eval);
+pub fn bench_eval_all(c: &mut Criterion) {
+    let keys: [[u8; 32]; 2] = thread_rng().gen();
+    let prg = Aes256HirosePrg::<16, 2>::new(std::array::from_fn(|i| &keys[i]));
+    let dcf = DcfImpl::<2, 16, _>::new(prg);
+    let s0s: [[u8; 16]; 2] = thread_rng().gen();
+    let f = CmpFn {
+        alpha: thread_rng().gen(),
+        beta: thread_rng().gen(),
+    };
+    let [k, _] = dcf
+        .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+        .unwrap()
+        .split();
+    let xs: Vec<[u8; 2]> = (0..=u16::MAX).map(|x| x.to_be_bytes()).collect();
+
+    c.bench_function("eval_all_n2", |b| {
+        b.iter(|| {
+            let mut ys = vec![[0; 16]; 1 << 16];
+            dcf.eval_all(false, &k, &mut ys).unwrap();
+        })
+    });
+    c.bench_function("eval_domain_n2", |b| {
+        b.iter(|| {
+            let mut ys = vec![[0; 16]; 1 << 16];
+            dcf.eval(
+                false,
+                &k,
+                &xs.iter().collect::<Vec<_>>(),
+                &mut ys.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+        })
+    });
+}
+
+/// Batch eval expands the root once for all of the inputs, while [`Dcf::eval_at`] on each input expands it for each
+pub fn bench_eval_batch_shared_root(c: &mut Criterion) {
+    let keys: [[u8; 32]; 2] = thread_rng().gen();
+    let prg = Aes256HirosePrg::<16, 2>::new(std::array::from_fn(|i| &keys[i]));
+    let mut dcf = DcfImpl::<2, 16, _>::new(prg);
+    // Serial for both, so that only the root expansion differs
+    dcf.set_parallel_threshold(usize::MAX);
+    let s0s: [[u8; 16]; 2] = thread_rng().gen();
+    let f = CmpFn {
+        alpha: thread_rng().gen(),
+        beta: thread_rng().gen(),
+    };
+    let [k, _] = dcf
+        .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+        .unwrap()
+        .split();
+    let xs: Vec<[u8; 2]> = (0..1024).map(|_| thread_rng().gen()).collect();
+
+    c.bench_function("eval_batch_1k_n2", |b| {
+        b.iter(|| {
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval(
+                false,
+                &k,
+                &xs.iter().collect::<Vec<_>>(),
+                &mut ys.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+        })
+    });
+    c.bench_function("eval_at_each_1k_n2", |b| {
+        b.iter(|| {
+            let ys: Vec<[u8; 16]> = xs
+                .iter()
+                .map(|x| dcf.eval_at(false, &k, x).unwrap())
+                .collect();
+            ys
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_gen,
+    bench_eval,
+    bench_eval_all,
+    bench_eval_batch_shared_root
+);
 criterion_main!(benches);
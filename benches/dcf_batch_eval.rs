@@ -13,7 +13,10 @@ pub fn bench(c: &mut Criterion) {
         alpha: thread_rng().gen(),
         beta: thread_rng().gen(),
     };
-    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+    let [k, _] = dcf
+        .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+        .unwrap()
+        .split();
     const N: usize = 100_000;
     let xs: [[u8; 16]; N] = std::array::from_fn(|_| thread_rng().gen());
 
@@ -27,7 +30,8 @@ pub fn bench(c: &mut Criterion) {
                 &k,
                 &xs.iter().collect::<Vec<_>>(),
                 &mut ys.iter_mut().collect::<Vec<_>>(),
-            );
+            )
+            .unwrap();
         })
     });
 }
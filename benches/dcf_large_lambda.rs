@@ -17,7 +17,10 @@ pub fn bench(c: &mut Criterion) {
         beta: [0; 16384],
     });
     thread_rng().fill_bytes(&mut f.beta);
-    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+    let [k, _] = dcf
+        .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+        .unwrap()
+        .split();
     const N: usize = 10_000;
     let xs: Vec<[u8; 16]> = (0..N).map(|_| thread_rng().gen()).collect();
 
@@ -31,7 +34,8 @@ pub fn bench(c: &mut Criterion) {
                 &k,
                 &xs.iter().collect::<Vec<_>>(),
                 &mut ys.iter_mut().collect::<Vec<_>>(),
-            );
+            )
+            .unwrap();
         })
     });
 }
@@ -0,0 +1,225 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Output buffers with a guaranteed alignment, e.g., for DMA to accelerators, and the output layouts of them
+
+use crate::group::Group;
+use crate::{Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// `len` outputs of `LAMBDA` bytes stored contiguously from an address aligned to `align`.
+///
+/// The alignment is obtained by over-allocating and skipping the unaligned head,
+/// and the allocation is never resized, so the address is stable for the lifetime of the buffer.
+/// Page-pinning, e.g., `mlock`, is OS-specific and left to the caller,
+/// which can use [`AlignedBuf::as_bytes`] with the page size as `align`.
+pub struct AlignedBuf<const LAMBDA: usize> {
+    buf: Vec<u8>,
+    /// Index of the first aligned byte in `buf`
+    offset: usize,
+    len: usize,
+    align: usize,
+}
+
+impl<const LAMBDA: usize> AlignedBuf<LAMBDA> {
+    /// Zeroed buffer of `len` outputs.
+    ///
+    /// Fails with [`DcfError::InvalidAlignment`] if `align` is not a power of 2.
+    pub fn new(len: usize, align: usize) -> Result<Self, DcfError> {
+        if !align.is_power_of_two() {
+            return Err(DcfError::InvalidAlignment { align });
+        }
+        let buf = vec![0; len * LAMBDA + align - 1];
+        let offset = buf.as_ptr().align_offset(align);
+        assert!(offset < align);
+        Ok(Self {
+            buf,
+            offset,
+            len,
+            align,
+        })
+    }
+
+    /// Number of outputs
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// The `len * LAMBDA` bytes of the outputs, starting at the aligned address
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[self.offset..self.offset + self.len * LAMBDA]
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[self.offset..self.offset + self.len * LAMBDA]
+    }
+
+    /// The `i`-th output, which starts at `i * LAMBDA` bytes from the aligned address
+    pub fn get(&self, i: usize) -> &[u8; LAMBDA] {
+        self.as_bytes()[i * LAMBDA..(i + 1) * LAMBDA]
+            .try_into()
+            .unwrap()
+    }
+
+    /// All outputs in order
+    pub fn outputs_mut(&mut self) -> Vec<&mut [u8; LAMBDA]> {
+        self.as_bytes_mut()
+            .chunks_exact_mut(LAMBDA)
+            .map(|y| y.try_into().unwrap())
+            .collect()
+    }
+}
+
+/// Converts `count` outputs of `lambda` bytes from the lane-major layout, i.e., `lambda` planes of `count` bytes
+/// where byte `j` of output `i` is at `j * count + i`, to the point-major layout of `count * lambda` bytes,
+/// where it is at `i * lambda + j`.
+///
+/// The lane-major layout is common in outputs of GPUs.
+pub fn transpose_to_point_major(flat: &[u8], count: usize, lambda: usize) -> Vec<u8> {
+    assert_eq!(flat.len(), count * lambda);
+    let mut out = vec![0; count * lambda];
+    flat.chunks_exact(count.max(1))
+        .enumerate()
+        .for_each(|(j, plane)| {
+            plane
+                .iter()
+                .enumerate()
+                .for_each(|(i, byte)| out[i * lambda + j] = *byte)
+        });
+    out
+}
+
+/// Reconstructs the outputs of party 0 and party 1, both in the lane-major layout,
+/// into the point-major layout. See [`transpose_to_point_major`] for the layouts.
+///
+/// Reconstructs by XOR, i.e., only for [`crate::XorGroup`].
+/// For other groups, transpose the outputs of each party and combine them by [`crate::Combine`].
+pub fn reconstruct_transposed(
+    share0_flat: &[u8],
+    share1_flat: &[u8],
+    count: usize,
+    lambda: usize,
+) -> Vec<u8> {
+    assert_eq!(share0_flat.len(), share1_flat.len());
+    // XOR is bytewise, so it can be done before transposing
+    let flat: Vec<u8> = share0_flat
+        .iter()
+        .zip(share1_flat.iter())
+        .map(|(y0, y1)| y0 ^ y1)
+        .collect();
+    transpose_to_point_major(&flat, count, lambda)
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but writes the outputs contiguously into `out`,
+    /// which avoids a copy into an aligned staging buffer.
+    ///
+    /// Fails the same as [`Dcf::eval`],
+    /// e.g., with [`DcfError::MismatchedOutputBuffer`] if `out` does not have exactly `xs.len()` outputs.
+    pub fn eval_aligned(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        out: &mut AlignedBuf<LAMBDA>,
+    ) -> Result<(), DcfError> {
+        self.eval(b, k, xs, &mut out.outputs_mut())
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::utils::xor;
+    use crate::{BoundState, CmpFn};
+
+    #[test]
+    fn test_dcf_eval_aligned_same_as_eval() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 4]> = (0..50).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let mut ys = vec![[0; 16]; xs.len()];
+        dcf.eval(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        [1, 64, 4096].iter().for_each(|&align| {
+            let mut out = AlignedBuf::<16>::new(xs.len(), align).unwrap();
+            dcf.eval_aligned(false, &k, &xs, &mut out).unwrap();
+            let base = out.as_bytes().as_ptr() as usize;
+            assert_eq!(base % align, 0);
+            ys.iter().enumerate().for_each(|(i, y)| {
+                assert_eq!(out.get(i).as_ptr() as usize, base + i * 16);
+                assert_eq!(out.get(i), y);
+            });
+        });
+        assert_eq!(
+            AlignedBuf::<16>::new(1, 48).err(),
+            Some(DcfError::InvalidAlignment { align: 48 })
+        );
+    }
+
+    #[test]
+    fn test_reconstruct_transposed_same_as_point_major() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<[u8; 4]> = (0..7).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let ks = k.split();
+        let [ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        // What a GPU would give: plane `j` has byte `j` of all of the outputs
+        let [flat0, flat1] = [&ys0, &ys1].map(|ys| {
+            (0..16)
+                .flat_map(|j| ys.iter().map(move |y| y[j]))
+                .collect::<Vec<u8>>()
+        });
+        let ys = reconstruct_transposed(&flat0, &flat1, xs.len(), 16);
+        let ys_expected: Vec<u8> = ys0
+            .iter()
+            .zip(ys1.iter())
+            .flat_map(|(y0, y1)| xor(&[y0, y1]))
+            .collect();
+        assert_eq!(ys, ys_expected);
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                ys[i * 16..(i + 1) * 16],
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+        assert_eq!(transpose_to_point_major(&flat0, xs.len(), 16), ys0.concat());
+    }
+}
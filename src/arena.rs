@@ -0,0 +1,75 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Eval with the outputs allocated in an arena of the caller with the `bumpalo` feature, e.g., for FFI or real-time use
+
+use bumpalo::Bump;
+
+use crate::group::Group;
+use crate::{Dcf, DcfError, DcfImpl, Prg, Share};
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but returns the outputs in a slice allocated in `arena`,
+    /// which lives as long as the arena until it is reset.
+    ///
+    /// Only the output slice is in the arena.
+    /// The working memory of eval, e.g., the path of each input, still uses the global allocator.
+    ///
+    /// Fails the same as [`Dcf::eval`].
+    pub fn eval_in_arena<'a>(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        arena: &'a Bump,
+    ) -> Result<&'a [[u8; LAMBDA]], DcfError> {
+        let ys = arena.alloc_slice_fill_copy(xs.len(), [0; LAMBDA]);
+        self.eval(b, k, xs, &mut ys.iter_mut().collect::<Vec<_>>())?;
+        Ok(ys)
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{BoundState, CmpFn};
+
+    #[test]
+    fn test_dcf_eval_in_arena_same_as_eval() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 4]> = (0..50).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let mut ys = vec![[0; 16]; xs.len()];
+        dcf.eval(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        let arena = Bump::new();
+        let ys_arena = dcf.eval_in_arena(false, &k, &xs, &arena).unwrap();
+        // A second batch in the same arena does not move the first one
+        let ys_arena2 = dcf.eval_in_arena(false, &k, &xs[..10], &arena).unwrap();
+        assert_eq!(ys_arena, &ys[..]);
+        assert_eq!(ys_arena2, &ys[..10]);
+        assert!(arena.allocated_bytes() >= 60 * 16);
+        assert!(dcf
+            .eval_in_arena(false, &k, &[], &arena)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Eval cooperating with async runtimes, e.g., for simulations co-located with async I/O
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::group::Group;
+use crate::{check_eval_inputs, Dcf, DcfError, DcfImpl, Prg, Share};
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but evaluates `chunk_size` inputs at a time
+    /// and yields to the executor between the chunks,
+    /// so that a huge batch does not starve the other tasks on the same thread.
+    ///
+    /// It does not depend on any runtime.
+    /// Each chunk still blocks the thread, so `chunk_size` bounds the time between yields.
+    ///
+    /// Fails the same as [`Dcf::eval`] before any chunk is evaluated.
+    pub async fn eval_chunked_yielding(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+        chunk_size: usize,
+    ) -> Result<(), DcfError> {
+        assert!(chunk_size > 0);
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), ys.len())?;
+        let mut chunks = xs.chunks(chunk_size).zip(ys.chunks_mut(chunk_size));
+        if let Some((xs_chunk, ys_chunk)) = chunks.next() {
+            self.eval(b, k, xs_chunk, ys_chunk)?;
+        }
+        for (xs_chunk, ys_chunk) in chunks {
+            YieldNow(false).await;
+            self.eval(b, k, xs_chunk, ys_chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns [`Poll::Pending`] once and wakes itself immediately,
+/// which puts the task back to the end of the queue of the executor
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use std::task::Waker;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{BoundState, CmpFn};
+
+    #[test]
+    fn test_dcf_eval_chunked_yielding_same_as_eval() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 4]> = (0..100).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let mut ys_expected = vec![[0; 16]; xs.len()];
+        dcf.eval(
+            false,
+            &k,
+            &xs,
+            &mut ys_expected.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let mut ys = vec![[0; 16]; xs.len()];
+        let mut ys_refs = ys.iter_mut().collect::<Vec<_>>();
+        let mut fut = Box::pin(dcf.eval_chunked_yielding(false, &k, &xs, &mut ys_refs, 30));
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut pending = 0;
+        let res = loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(res) => break res,
+                Poll::Pending => pending += 1,
+            }
+        };
+        drop(fut);
+        assert_eq!(res, Ok(()));
+        // Yields between the 4 chunks
+        assert_eq!(pending, 3);
+        assert_eq!(ys, ys_expected);
+    }
+}
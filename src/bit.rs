@@ -0,0 +1,184 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Single-bit outputs, e.g., shared bits of `$x < \alpha$` over a batch.
+//!
+//! The bit is the least significant bit of the output.
+//! Reducing modulo 2 keeps the reconstruction in both [`crate::XorGroup`] and [`crate::ArithGroup`],
+//! so the bits of the 2 parties are reconstructed by XOR with [`bit_reconstruct`]
+//! or with `^` on the whole [`BitVec`]s.
+//!
+//! [`DcfImpl::eval_parity`] instead takes the parity of all bits of the output,
+//! which is only linear, i.e., reconstructed by XOR, in [`XorGroup`].
+
+use bitvec::prelude::*;
+
+use crate::group::{Group, XorGroup};
+use crate::{check_eval_inputs, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// Inputs evaluated at a time by [`DcfImpl::bit_eval`] to bound the scratch memory
+const BIT_EVAL_CHUNK: usize = 1024;
+
+impl<const N: usize, const LAMBDA: usize> CmpFn<N, LAMBDA> {
+    /// Comparison function whose `$\beta$` is 1, for [`DcfImpl::bit_eval`]
+    pub fn new_bit(alpha: [u8; N]) -> Self {
+        let mut beta = [0; LAMBDA];
+        beta[LAMBDA - 1] = 1;
+        Self { alpha, beta }
+    }
+}
+
+/// Reconstructs a bit of [`DcfImpl::bit_eval`] from the 2 parties
+pub fn bit_reconstruct(b0: bool, b1: bool) -> bool {
+    b0 ^ b1
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but only returns the shared bit of each output.
+    ///
+    /// `k` should be generated from [`CmpFn::new_bit`] or any `$\beta$` with the last bit set.
+    /// Only a fixed-size scratch buffer is allocated besides the returned bits.
+    ///
+    /// Fails the same as [`Dcf::eval`].
+    pub fn bit_eval(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+    ) -> Result<BitVec, DcfError> {
+        let mut bits = BitVec::with_capacity(xs.len());
+        let mut ys = vec![[0; LAMBDA]; BIT_EVAL_CHUNK.min(xs.len())];
+        for xs_chunk in xs.chunks(BIT_EVAL_CHUNK) {
+            let ys_chunk = &mut ys[..xs_chunk.len()];
+            self.eval(b, k, xs_chunk, &mut ys_chunk.iter_mut().collect::<Vec<_>>())?;
+            bits.extend(ys_chunk.iter().map(|y| y[LAMBDA - 1] & 1 == 1));
+        }
+        Ok(bits)
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT> DcfImpl<N, LAMBDA, PrgT, XorGroup>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but only appends the party's share of the parity of each output to `out`.
+    ///
+    /// The parity is the XOR of all bits of the output.
+    /// The bits of the 2 parties are reconstructed by XOR to the parity of `$\beta$` where `$f(x) = \beta$`
+    /// and 0 elsewhere.
+    /// Only a fixed-size scratch buffer is allocated besides the appended bits.
+    ///
+    /// Fails the same as [`Dcf::eval`] before anything is appended.
+    pub fn eval_parity(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        out: &mut BitVec,
+    ) -> Result<(), DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), xs.len())?;
+        out.reserve(xs.len());
+        let mut ys = vec![[0; LAMBDA]; BIT_EVAL_CHUNK.min(xs.len())];
+        for xs_chunk in xs.chunks(BIT_EVAL_CHUNK) {
+            let ys_chunk = &mut ys[..xs_chunk.len()];
+            self.eval(b, k, xs_chunk, &mut ys_chunk.iter_mut().collect::<Vec<_>>())?;
+            out.extend(
+                ys_chunk
+                    .iter()
+                    .map(|y| y.iter().fold(0, |acc, byte| acc ^ byte).count_ones() & 1 == 1),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, BoundState};
+
+    fn check_bit_eval<G: Group<16>>(dcf: &DcfImpl<4, 16, Aes256HirosePrg<16, 2>, G>) {
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn::new_bit(thread_rng().gen());
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.split();
+        // More than a chunk
+        let xs: Vec<[u8; 4]> = (0..BIT_EVAL_CHUNK + 100)
+            .map(|_| thread_rng().gen())
+            .collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let bits0 = dcf.bit_eval(false, &k0, &xs).unwrap();
+        let bits1 = dcf.bit_eval(true, &k1, &xs).unwrap();
+        assert_eq!(bits0.len(), xs.len());
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(bit_reconstruct(bits0[i], bits1[i]), *x < &f.alpha);
+        });
+        let bits = bits0 ^ bits1;
+        assert_eq!(
+            bits.count_ones(),
+            xs.iter().filter(|x| **x < &f.alpha).count()
+        );
+    }
+
+    #[test]
+    fn test_bit_eval_then_reconstruct_ok() {
+        check_bit_eval(&DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS)));
+    }
+
+    #[test]
+    fn test_bit_eval_arith_then_reconstruct_ok() {
+        check_bit_eval(&DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(
+            KEYS,
+        )));
+    }
+
+    #[test]
+    fn test_bit_cws_hide_alpha() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        crate::test_fixtures::assert_cws_hide_alpha(|alpha, s0s| {
+            dcf.gen(&CmpFn::new_bit(*alpha), s0s, BoundState::LtBeta)
+                .unwrap()
+        });
+    }
+
+    #[test]
+    fn test_eval_parity_then_reconstruct_ok() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let mut beta: [u8; 16] = thread_rng().gen();
+        // Odd parity so that the target points are distinguishable from the others
+        if beta.iter().map(|byte| byte.count_ones()).sum::<u32>() & 1 == 0 {
+            beta[0] ^= 1;
+        }
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta,
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<[u8; 4]> = (0..BIT_EVAL_CHUNK + 100)
+            .map(|_| thread_rng().gen())
+            .collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let ks = k.split();
+        let [parities0, parities1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut out = BitVec::new();
+            dcf.eval_parity(b, kb, &xs, &mut out).unwrap();
+            out
+        });
+        assert_eq!(parities0.len(), xs.len());
+        let parities = parities0 ^ parities1;
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(parities[i], *x < &f.alpha);
+        });
+    }
+}
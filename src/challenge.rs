@@ -0,0 +1,105 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dealer-assisted check of a key pair at a random point before trusting it
+
+use crate::group::Group;
+use crate::{BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// A point `x` and what the 2 parties' outputs at it should be reconstructed to, made by the dealer.
+///
+/// `alpha` is secret, so the parties can not tell the expected output themselves.
+/// `expected` reveals whether `x` satisfies the comparison, and `beta` if it does,
+/// so it should only go to whoever combines the outputs, and `x` should be randomly sampled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge<const N: usize, const LAMBDA: usize> {
+    pub x: [u8; N],
+    pub expected: [u8; LAMBDA],
+}
+
+impl<const N: usize, const LAMBDA: usize> Challenge<N, LAMBDA> {
+    /// The dealer, who knows `f` and `bound` of gen
+    pub fn new(f: &CmpFn<N, LAMBDA>, bound: BoundState, x: [u8; N]) -> Self {
+        Self {
+            x,
+            expected: f.eval_plain(&x, bound),
+        }
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// The share of the party `b` at the point of `challenge`, which the party sends for [`DcfImpl::verify_at_challenge`].
+    ///
+    /// Fails the same as [`Dcf::eval_at`].
+    pub fn eval_challenge(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        challenge: &Challenge<N, LAMBDA>,
+    ) -> Result<[u8; LAMBDA], DcfError> {
+        self.eval_at(b, k, &challenge.x)
+    }
+
+    /// Whether the shares `y0` of party 0 and `y1` of party 1 from [`DcfImpl::eval_challenge`]
+    /// reconstruct by [`DcfImpl::reconstruct`] to the expected output of `challenge`.
+    ///
+    /// A pass at 1 point does not prove the keys are correct at all points,
+    /// but a tampered or mismatched key fails at a random point with high probability.
+    pub fn verify_at_challenge(
+        &self,
+        challenge: &Challenge<N, LAMBDA>,
+        y0: &[u8; LAMBDA],
+        y1: &[u8; LAMBDA],
+    ) -> bool {
+        self.reconstruct(y0, y1) == challenge.expected
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::ArithGroup;
+
+    #[test]
+    fn test_dcf_verify_at_challenge_good_ok_tampered_fails() {
+        let dcf = DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let alpha: u32 = thread_rng().gen_range(1..u32::MAX);
+        let f = CmpFn {
+            alpha: alpha.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let [k0, k1] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        // On both sides of `alpha`
+        [alpha - 1, alpha, thread_rng().gen()].iter().for_each(|x| {
+            let challenge = Challenge::new(&f, BoundState::LtBeta, x.to_be_bytes());
+            let y0 = dcf.eval_challenge(false, &k0, &challenge).unwrap();
+            let y1 = dcf.eval_challenge(true, &k1, &challenge).unwrap();
+            assert!(dcf.verify_at_challenge(&challenge, &y0, &y1));
+
+            // Party 1 always takes `$V_{CW}$` of the first level since its `t` starts at 1
+            let mut k1_tampered = k1.clone();
+            k1_tampered.cws[0].v[0] ^= 1;
+            let y1 = dcf.eval_challenge(true, &k1_tampered, &challenge).unwrap();
+            assert!(!dcf.verify_at_challenge(&challenge, &y0, &y1));
+
+            let mut k0_tampered = k0.clone();
+            k0_tampered.s0s[0] = thread_rng().gen();
+            let y0 = dcf.eval_challenge(false, &k0_tampered, &challenge).unwrap();
+            let y1 = dcf.eval_challenge(true, &k1, &challenge).unwrap();
+            assert!(!dcf.verify_at_challenge(&challenge, &y0, &y1));
+        });
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed `$\beta$`, e.g., fixed-point numbers or pairs of integers, encoded into the `[u8; LAMBDA]` outputs
+
+use crate::group::Group;
+use crate::{BoundState, CmpFn, DcfImpl, Prg, Share};
+
+/// Encoding of typed values into the outputs and back.
+///
+/// The outputs reconstruct to the encoding of `$\beta$` or to all zeros,
+/// so `decode` of all zeros is what the inputs out of the support reconstruct to,
+/// which should be the zero of the type.
+/// Sums of the outputs of multiple keys, e.g., [`DcfImpl::eval_rank`], only decode correctly
+/// if the encoding maps the addition of `G` to the one of the type.
+pub trait LeafCodec<const LAMBDA: usize> {
+    type Value;
+
+    fn encode(value: &Self::Value) -> [u8; LAMBDA];
+
+    fn decode(bytes: &[u8; LAMBDA]) -> Self::Value;
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`crate::Dcf::gen`] but with `$\beta$` encoded by the codec `C`.
+    ///
+    /// The outputs are reconstructed back to the typed value by [`DcfImpl::reconstruct_typed`] with the same codec.
+    pub fn gen_typed<C>(
+        &self,
+        alpha: &[u8; N],
+        beta: &C::Value,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> Share<LAMBDA>
+    where
+        C: LeafCodec<LAMBDA>,
+    {
+        let f = CmpFn {
+            alpha: *alpha,
+            beta: C::encode(beta),
+        };
+        self.gen_parts(&f, s0s, &bound, 0)
+    }
+
+    /// Reconstructs the output `y0` of party 0 and `y1` of party 1 at the same input by [`DcfImpl::reconstruct`],
+    /// and decodes the result by the codec `C`
+    pub fn reconstruct_typed<C>(&self, y0: &[u8; LAMBDA], y1: &[u8; LAMBDA]) -> C::Value
+    where
+        C: LeafCodec<LAMBDA>,
+    {
+        C::decode(&self.reconstruct(y0, y1))
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, Dcf};
+
+    /// Fixed-point number with 16 fractional bits as a big-endian `i128`,
+    /// which keeps the addition of [`ArithGroup`]
+    struct Fixed16;
+
+    impl LeafCodec<16> for Fixed16 {
+        type Value = f64;
+
+        fn encode(value: &f64) -> [u8; 16] {
+            ((value * 65536.0).round() as i128).to_be_bytes()
+        }
+
+        fn decode(bytes: &[u8; 16]) -> f64 {
+            i128::from_be_bytes(*bytes) as f64 / 65536.0
+        }
+    }
+
+    #[test]
+    fn test_dcf_gen_typed_then_reconstruct_typed_ok() {
+        let dcf = DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let alpha = 1000u32.to_be_bytes();
+        let beta = -3.140625;
+        let k = dcf.gen_typed::<Fixed16>(&alpha, &beta, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+        let xs = [999u32, 1000].map(|x| x.to_be_bytes());
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let ks = k.split();
+        let [ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        assert_eq!(dcf.reconstruct_typed::<Fixed16>(&ys0[0], &ys1[0]), beta);
+        assert_eq!(dcf.reconstruct_typed::<Fixed16>(&ys0[1], &ys1[1]), 0.0);
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compare-and-swap on secret-shared values for oblivious sorting networks.
+//!
+//! The 2 parties hold shares of `u` and `v` in a [`Ring`],
+//! plus a DCF key and a public input `x` whose output is reconstructed to 1 iff `u < v`.
+//! A common way is to have the dealer mask the difference of the values
+//! and gen the key for the mask, but how `x` is obtained is up to the caller.
+//! The parties end up with shares of `min(u, v)` and `max(u, v)`
+//! without learning which one is smaller.
+//!
+//! With the comparison bit `c`, `min = v + c * (u - v)` and `max = u + v - min`.
+//! `c * (u - v)` is a product of 2 shared values,
+//! which requires a [`BeaverTriple`] from the dealer and 1 round of communication:
+//!
+//! 1. Each party calls [`DcfImpl::compare_swap_share`] and sends [`CompareSwap::msg`] to the other party.
+//! 2. Each party calls [`CompareSwap::finish`] with the message received.
+//!
+//! A triple must only be used once.
+
+use std::marker::PhantomData;
+
+use crate::group::Ring;
+use crate::{Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// A party's shares of the Beaver triple `(a, b, a * b)` with `a` and `b` uniformly random
+#[derive(Clone)]
+pub struct BeaverTriple<const LAMBDA: usize> {
+    pub a: [u8; LAMBDA],
+    pub b: [u8; LAMBDA],
+    pub ab: [u8; LAMBDA],
+}
+
+impl<const LAMBDA: usize> BeaverTriple<LAMBDA> {
+    /// Dealer side. Shares the triple of `a` and `b`.
+    ///
+    /// `a`, `b`, and `masks` should be randomly sampled.
+    /// `masks` are the shares of party 0, and the ones of party 1 are derived from them.
+    pub fn gen<G: Ring<LAMBDA>>(
+        a: &[u8; LAMBDA],
+        b: &[u8; LAMBDA],
+        masks: [&[u8; LAMBDA]; 3],
+    ) -> [Self; 2] {
+        let mut ab = *a;
+        G::mul(&mut ab, b);
+        let share1 = |x: &[u8; LAMBDA], mask: &[u8; LAMBDA]| {
+            let mut x = *x;
+            G::sub(&mut x, mask);
+            x
+        };
+        [
+            Self {
+                a: *masks[0],
+                b: *masks[1],
+                ab: *masks[2],
+            },
+            Self {
+                a: share1(a, masks[0]),
+                b: share1(b, masks[1]),
+                ab: share1(&ab, masks[2]),
+            },
+        ]
+    }
+}
+
+impl<const LAMBDA: usize> BeaverTriple<LAMBDA> {
+    /// The party `b`'s share of the product of `x` and `y` from the opened `d = x - a` and `e = y - b`.
+    ///
+    /// `msg` is the party's shares of `d` and `e`, and `peer_msg` is the ones of the other party.
+    pub(crate) fn product_share<G: Ring<LAMBDA>>(
+        &self,
+        b: bool,
+        msg: &[[u8; LAMBDA]; 2],
+        peer_msg: &[[u8; LAMBDA]; 2],
+    ) -> [u8; LAMBDA] {
+        let [mut d, mut e] = *msg;
+        G::add(&mut d, &peer_msg[0]);
+        G::add(&mut e, &peer_msg[1]);
+        // `$[x y] = [ab] + d [b] + e [a] + d e$` where only party 0 adds `$d e$`
+        let mut prod = self.ab;
+        let mut d_b = d;
+        G::mul(&mut d_b, &self.b);
+        G::add(&mut prod, &d_b);
+        let mut e_a = e;
+        G::mul(&mut e_a, &self.a);
+        G::add(&mut prod, &e_a);
+        if !b {
+            G::mul(&mut d, &e);
+            G::add(&mut prod, &d);
+        }
+        prod
+    }
+}
+
+/// State of a party between the 2 steps of compare-and-swap
+pub struct CompareSwap<const LAMBDA: usize, G> {
+    b: bool,
+    u: [u8; LAMBDA],
+    v: [u8; LAMBDA],
+    triple: BeaverTriple<LAMBDA>,
+    /// The party's shares of `c - a` and `(u - v) - b` to send to the other party
+    pub msg: [[u8; LAMBDA]; 2],
+    group: PhantomData<fn() -> G>,
+}
+
+impl<const LAMBDA: usize, G> CompareSwap<LAMBDA, G>
+where
+    G: Ring<LAMBDA>,
+{
+    /// Returns the party's shares of `(min(u, v), max(u, v))`.
+    ///
+    /// `peer_msg` is [`CompareSwap::msg`] of the other party.
+    pub fn finish(&self, peer_msg: &[[u8; LAMBDA]; 2]) -> ([u8; LAMBDA], [u8; LAMBDA]) {
+        // `$[c (u - v)]$`
+        let prod = self.triple.product_share::<G>(self.b, &self.msg, peer_msg);
+        let mut min = self.v;
+        G::add(&mut min, &prod);
+        let mut max = self.u;
+        G::add(&mut max, &self.v);
+        G::sub(&mut max, &min);
+        (min, max)
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Ring<LAMBDA>,
+{
+    /// First step of compare-and-swap. See [`crate::compare_swap`] for the protocol.
+    ///
+    /// `k` evaluated at `x` must be reconstructed to [`Ring::one`] iff `u < v` and 0 otherwise.
+    /// `u` and `v` are the party's shares of the values to swap.
+    ///
+    /// Fails the same as [`Dcf::eval_at`].
+    pub fn compare_swap_share(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        x: &[u8; N],
+        u: &[u8; LAMBDA],
+        v: &[u8; LAMBDA],
+        triple: &BeaverTriple<LAMBDA>,
+    ) -> Result<CompareSwap<LAMBDA, G>, DcfError> {
+        let mut d = self.eval_at(b, k, x)?;
+        G::sub(&mut d, &triple.a);
+        let mut e = *u;
+        G::sub(&mut e, v);
+        G::sub(&mut e, &triple.b);
+        Ok(CompareSwap {
+            b,
+            u: *u,
+            v: *v,
+            triple: triple.clone(),
+            msg: [d, e],
+            group: PhantomData,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::group::{ArithGroup, Group};
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{BoundState, CmpFn};
+
+    fn share(x: u128) -> [[u8; 16]; 2] {
+        let mask: u128 = thread_rng().gen();
+        [mask.to_be_bytes(), x.wrapping_sub(mask).to_be_bytes()]
+    }
+
+    #[test]
+    fn test_compare_swap_share_reconstruct_min_max() {
+        let dcf = DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        for _ in 0..20 {
+            let (u, v) = (thread_rng().gen::<u32>(), thread_rng().gen::<u32>());
+            // The dealer masks `v` with `r`, and the parties get `x = u + r` with `alpha = v + r`.
+            // `r` is small enough here to avoid wrapping around.
+            let r = thread_rng().gen::<u32>() >> 2;
+            let (u, v) = (u >> 2, v >> 2);
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            let f = CmpFn {
+                alpha: (v + r).to_be_bytes(),
+                beta: <ArithGroup as Ring<16>>::one(),
+            };
+            let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+            let x = (u + r).to_be_bytes();
+            let (us, vs) = (share(u as u128), share(v as u128));
+            let masks: [[u8; 16]; 3] = thread_rng().gen();
+            let triples = BeaverTriple::gen::<ArithGroup>(
+                &thread_rng().gen(),
+                &thread_rng().gen(),
+                [&masks[0], &masks[1], &masks[2]],
+            );
+            let ks = k.split();
+            let states = [0, 1].map(|i| {
+                let k = &ks[i];
+                dcf.compare_swap_share(i == 1, k, &x, &us[i], &vs[i], &triples[i])
+                    .unwrap()
+            });
+            let (mut min, mut max) = states[0].finish(&states[1].msg);
+            let (min1, max1) = states[1].finish(&states[0].msg);
+            ArithGroup::add(&mut min, &min1);
+            ArithGroup::add(&mut max, &max1);
+            assert_eq!(u128::from_be_bytes(min), u.min(v) as u128);
+            assert_eq!(u128::from_be_bytes(max), u.max(v) as u128);
+        }
+    }
+
+    #[test]
+    fn test_compare_swap_cws_hide_alpha() {
+        let dcf = DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        crate::test_fixtures::assert_cws_hide_alpha(|alpha, s0s| {
+            let f = CmpFn {
+                alpha: *alpha,
+                beta: <ArithGroup as Ring<16>>::one(),
+            };
+            dcf.gen(&f, s0s, BoundState::LtBeta).unwrap()
+        });
+    }
+}
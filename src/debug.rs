@@ -0,0 +1,88 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Diagnostics when debugging protocols built on [`crate::Dcf`]
+
+use crate::group::Group;
+use crate::{Dcf, DcfError, DcfImpl, Prg, Share};
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Exactly [`Dcf::eval`], named to make it clear in diagnostics code
+    /// that the outputs are the raw shares of the single party `b`.
+    ///
+    /// A share alone is pseudorandom and reveals nothing about `$\alpha$` or `$\beta$`,
+    /// so printing it is only meaningful along with the share of the other party.
+    pub fn eval_raw_share(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        self.eval(b, k, xs, ys)
+    }
+
+    /// Whether the shares `y0` of party 0 and `y1` of party 1 at a point reconstruct by [`DcfImpl::reconstruct`]
+    /// to `beta` if `expected_support`, i.e., the point is claimed to satisfy the comparison,
+    /// or to 0 otherwise
+    pub fn reconstruct_is_consistent(
+        &self,
+        y0: &[u8; LAMBDA],
+        y1: &[u8; LAMBDA],
+        expected_support: bool,
+        beta: &[u8; LAMBDA],
+    ) -> bool {
+        let y = self.reconstruct(y0, y1);
+        if expected_support {
+            &y == beta
+        } else {
+            y == [0; LAMBDA]
+        }
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, BoundState, CmpFn};
+
+    #[test]
+    fn test_dcf_reconstruct_is_consistent() {
+        let dcf = DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let alpha: u32 = thread_rng().gen();
+        let f = CmpFn {
+            alpha: alpha.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<[u8; 4]> = (0..100)
+            .map(|_| thread_rng().gen::<u32>())
+            .chain([alpha, alpha.wrapping_sub(1)])
+            .map(|x| x.to_be_bytes())
+            .collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let ks = k.split();
+        let [ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_raw_share(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            let support = x < &&f.alpha;
+            assert!(dcf.reconstruct_is_consistent(&ys0[i], &ys1[i], support, &f.beta));
+            assert!(!dcf.reconstruct_is_consistent(&ys0[i], &ys1[i], !support, &f.beta));
+        });
+    }
+}
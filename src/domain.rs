@@ -0,0 +1,449 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Domains of totally-ordered types other than `[u8; N]`
+
+use bitvec::prelude::*;
+
+use crate::group::Group;
+use crate::{BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// Encoding of a totally-ordered type into the `[u8; N]` domain of the tree.
+///
+/// The tree compares the inputs as bits in [`bitvec::order::Msb0`], i.e., `[u8; N]` in the big-endian order,
+/// so the encoding must keep the order:
+/// `a < b` iff. `a.to_domain_bits() < b.to_domain_bits()` as `[u8; N]`.
+/// E.g., a composite key of a timestamp and an id is the big-endian timestamp followed by the big-endian id.
+pub trait ToDomainBits<const N: usize> {
+    fn to_domain_bits(&self) -> [u8; N];
+}
+
+macro_rules! impl_to_domain_bits_uint {
+    ($($t:ty),*) => {$(
+        impl ToDomainBits<{ std::mem::size_of::<$t>() }> for $t {
+            fn to_domain_bits(&self) -> [u8; std::mem::size_of::<$t>()] {
+                self.to_be_bytes()
+            }
+        }
+    )*};
+}
+
+impl_to_domain_bits_uint!(u8, u16, u32, u64, u128);
+
+macro_rules! impl_to_domain_bits_int {
+    ($($t:ty),*) => {$(
+        /// Big-endian with the sign bit flipped, so that negative values come first
+        impl ToDomainBits<{ std::mem::size_of::<$t>() }> for $t {
+            fn to_domain_bits(&self) -> [u8; std::mem::size_of::<$t>()] {
+                let mut bytes = self.to_be_bytes();
+                bytes[0] ^= 0x80;
+                bytes
+            }
+        }
+    )*};
+}
+
+impl_to_domain_bits_int!(i8, i16, i32, i64, i128);
+
+/// Unsigned integer encoded in the reflected binary Gray code, i.e., `v ^ (v >> 1)` in big-endian.
+///
+/// **It does not keep the order**, so gen and eval with it compare in the order of the codes,
+/// not of the values, e.g., 3 is encoded as `0b10` and comes before 2 as `0b11`.
+/// What it keeps is the equality and the adjacency:
+/// 2 values are equal iff. their codes are, which an `LtBeta` and a `GtBeta` key of the same `$\alpha$` both being 0 at `x` tells,
+/// and the codes of `v` and `v + 1` always differ in exactly 1 bit, so fewer bits of the tree path change between neighbors.
+/// Use it for such equality or adjacency use cases only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrayEncoding<T>(pub T);
+
+macro_rules! impl_to_domain_bits_gray {
+    ($($t:ty),*) => {$(
+        impl ToDomainBits<{ std::mem::size_of::<$t>() }> for GrayEncoding<$t> {
+            fn to_domain_bits(&self) -> [u8; std::mem::size_of::<$t>()] {
+                (self.0 ^ (self.0 >> 1)).to_be_bytes()
+            }
+        }
+    )*};
+}
+
+impl_to_domain_bits_gray!(u8, u16, u32, u64, u128);
+
+impl<const N: usize, const LAMBDA: usize> CmpFn<N, LAMBDA> {
+    /// [`CmpFn`] with `$\alpha$` encoded by [`ToDomainBits`].
+    ///
+    /// `N` is inferred from the type of `alpha`, e.g., 4 for `u32`.
+    pub fn from_ord<T>(alpha: &T, beta: [u8; LAMBDA]) -> Self
+    where
+        T: ToDomainBits<N> + ?Sized,
+    {
+        Self {
+            alpha: alpha.to_domain_bits(),
+            beta,
+        }
+    }
+}
+
+/// [`CmpFn`](crate::CmpFn) comparing to a `u8` threshold, i.e., `N = 1`.
+///
+/// `alpha` must be a `u8` and is encoded in big-endian. `beta` is a `[u8; LAMBDA]`.
+#[macro_export]
+macro_rules! cmp_fn_u8 {
+    ($alpha:expr, $beta:expr) => {
+        $crate::CmpFn::<1, _>::from_ord::<u8>(&$alpha, $beta)
+    };
+}
+
+/// [`CmpFn`](crate::CmpFn) comparing to a `u16` threshold, i.e., `N = 2`.
+///
+/// `alpha` must be a `u16` and is encoded in big-endian. `beta` is a `[u8; LAMBDA]`.
+#[macro_export]
+macro_rules! cmp_fn_u16 {
+    ($alpha:expr, $beta:expr) => {
+        $crate::CmpFn::<2, _>::from_ord::<u16>(&$alpha, $beta)
+    };
+}
+
+/// [`CmpFn`](crate::CmpFn) comparing to a `u32` threshold, i.e., `N = 4`.
+///
+/// `alpha` must be a `u32` and is encoded in big-endian. `beta` is a `[u8; LAMBDA]`.
+#[macro_export]
+macro_rules! cmp_fn_u32 {
+    ($alpha:expr, $beta:expr) => {
+        $crate::CmpFn::<4, _>::from_ord::<u32>(&$alpha, $beta)
+    };
+}
+
+/// [`CmpFn`](crate::CmpFn) comparing to a `u64` threshold, i.e., `N = 8`.
+///
+/// `alpha` must be a `u64` and is encoded in big-endian. `beta` is a `[u8; LAMBDA]`.
+#[macro_export]
+macro_rules! cmp_fn_u64 {
+    ($alpha:expr, $beta:expr) => {
+        $crate::CmpFn::<8, _>::from_ord::<u64>(&$alpha, $beta)
+    };
+}
+
+/// [`CmpFn`](crate::CmpFn) comparing to a `u128` threshold, i.e., `N = 16`.
+///
+/// `alpha` must be a `u128` and is encoded in big-endian. `beta` is a `[u8; LAMBDA]`.
+#[macro_export]
+macro_rules! cmp_fn_u128 {
+    ($alpha:expr, $beta:expr) => {
+        $crate::CmpFn::<16, _>::from_ord::<u128>(&$alpha, $beta)
+    };
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`Dcf::gen`] but with `$\alpha$` of a type encoded by [`ToDomainBits`].
+    ///
+    /// The keys must be evaluated by [`DcfImpl::eval_ord`] with the same encoding,
+    /// or by [`Dcf::eval`] with the inputs encoded by it.
+    pub fn gen_ord<T>(
+        &self,
+        alpha: &T,
+        beta: &[u8; LAMBDA],
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> Share<LAMBDA>
+    where
+        T: ToDomainBits<N> + ?Sized,
+    {
+        let f = CmpFn {
+            alpha: alpha.to_domain_bits(),
+            beta: *beta,
+        };
+        self.gen_parts(&f, s0s, &bound, 0)
+    }
+
+    /// Like [`Dcf::eval`] but with `xs` of a type encoded by [`ToDomainBits`].
+    ///
+    /// The encoding must be the same as the one of gen, otherwise the outputs compare to another `$\alpha$`.
+    pub fn eval_ord<T>(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&T],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError>
+    where
+        T: ToDomainBits<N> + ?Sized,
+    {
+        let xs: Vec<[u8; N]> = xs.iter().map(|x| x.to_domain_bits()).collect();
+        self.eval(b, k, &xs.iter().collect::<Vec<_>>(), ys)
+    }
+
+    /// Like [`Dcf::gen`] but over the domain of only the top `bits` bits of `[u8; N]` in [`Msb0`],
+    /// e.g., a 10-bit domain in `[u8; 2]` without the 6 extra tree levels of the padding.
+    ///
+    /// `f.alpha` and the inputs are compared by their top `bits` bits only, and the rest of them are ignored.
+    /// The keys have `bits` correlation words and must be evaluated by [`DcfImpl::eval_bits`].
+    /// For a value in the low bits instead, e.g., a 10-bit integer in a big-endian `u16`,
+    /// use [`Dcf::gen_fixed_bits`] with `8 * N - bits` fixed bits, which [`Dcf::eval`] evaluates directly.
+    ///
+    /// Fails with [`DcfError::InvalidBitWidth`] if `bits` is 0 or more than `8 * N`.
+    pub fn gen_bits(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+        bits: usize,
+    ) -> Result<Share<LAMBDA>, DcfError> {
+        if !(1..=8 * N).contains(&bits) {
+            return Err(DcfError::InvalidBitWidth { bits, max: 8 * N });
+        }
+        let f = CmpFn {
+            alpha: top_bits_to_low(&f.alpha, bits),
+            beta: f.beta,
+        };
+        self.gen_fixed_bits(&f, s0s, bound, 8 * N - bits)
+    }
+
+    /// Like [`Dcf::eval`] but for the keys of [`DcfImpl::gen_bits`], reading only the top bits of `xs`.
+    ///
+    /// The bit width is the number of the correlation words of the key.
+    pub fn eval_bits(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        let bits = k.cws.len();
+        let xs: Vec<[u8; N]> = xs.iter().map(|x| top_bits_to_low(x, bits)).collect();
+        self.eval(b, k, &xs.iter().collect::<Vec<_>>(), ys)
+    }
+}
+
+/// Moves the top `bits` bits of `x` to the low bits, with the high bits 0,
+/// which is where the tree of [`Dcf::gen_fixed_bits`] reads them from
+fn top_bits_to_low<const N: usize>(x: &[u8; N], bits: usize) -> [u8; N] {
+    let mut low = [0; N];
+    low.view_bits_mut::<Msb0>()[8 * N - bits..].copy_from_bitslice(&x.view_bits::<Msb0>()[..bits]);
+    low
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::utils::xor;
+
+    /// Ordered by `ts` and then by `id` descending
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Event {
+        ts: i32,
+        id: std::cmp::Reverse<u16>,
+    }
+
+    impl ToDomainBits<6> for Event {
+        fn to_domain_bits(&self) -> [u8; 6] {
+            let mut bits = [0; 6];
+            bits[..4].copy_from_slice(&self.ts.to_domain_bits());
+            bits[4..].copy_from_slice(&(!self.id.0).to_domain_bits());
+            bits
+        }
+    }
+
+    fn rand_event() -> Event {
+        // Small ranges so that the ties on `ts` are covered
+        Event {
+            ts: thread_rng().gen_range(-4..4),
+            id: std::cmp::Reverse(thread_rng().gen()),
+        }
+    }
+
+    #[test]
+    fn test_dcf_gen_ord_then_eval_ord_ok() {
+        let dcf = DcfImpl::<6, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let beta: [u8; 16] = thread_rng().gen();
+        let alpha = rand_event();
+        let k = dcf.gen_ord(&alpha, &beta, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+        let xs: Vec<Event> = (0..200).map(|_| rand_event()).collect();
+        let xs: Vec<&Event> = xs.iter().chain([&alpha]).collect();
+        let ks = k.split();
+        let [ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_ord(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            let expected = if *x < &alpha { beta } else { [0; 16] };
+            assert_eq!(xor(&[&ys0[i], &ys1[i]]), expected);
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_bits_then_eval_bits_ok() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let beta: [u8; 16] = thread_rng().gen();
+        // 10 bits in the top of `[u8; 2]`, with the low 6 bits as noise
+        let alpha = (thread_rng().gen_range(0..1024u16) << 6) | thread_rng().gen_range(0..64);
+        let f = cmp_fn_u16!(alpha, beta);
+        [BoundState::LtBeta, BoundState::Geq]
+            .into_iter()
+            .for_each(|bound| {
+                let s0s: [[u8; 16]; 2] = thread_rng().gen();
+                let k = dcf.gen_bits(&f, [&s0s[0], &s0s[1]], bound, 10).unwrap();
+                assert_eq!(k.cws.len(), 10);
+                let xs: Vec<[u8; 2]> = (0..1024u16)
+                    .map(|x| ((x << 6) | thread_rng().gen_range(0..64)).to_be_bytes())
+                    .collect();
+                let xs: Vec<&[u8; 2]> = xs.iter().collect();
+                let keys = k.split();
+                let [ys0, ys1] = [false, true].map(|b| {
+                    let mut ys = vec![[0; 16]; xs.len()];
+                    let kb = &keys[b as usize];
+                    dcf.eval_bits(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                        .unwrap();
+                    ys
+                });
+                xs.iter().enumerate().for_each(|(i, x)| {
+                    let x_top = u16::from_be_bytes(**x) >> 6;
+                    let hit = match bound {
+                        BoundState::LtBeta => x_top < alpha >> 6,
+                        _ => x_top >= alpha >> 6,
+                    };
+                    let expected = if hit { beta } else { [0; 16] };
+                    assert_eq!(xor(&[&ys0[i], &ys1[i]]), expected);
+                });
+            });
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        [0, 17].iter().for_each(|&bits| {
+            assert_eq!(
+                dcf.gen_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, bits)
+                    .err(),
+                Some(DcfError::InvalidBitWidth { bits, max: 16 })
+            );
+        });
+        // The full width is the same as gen
+        let k = dcf
+            .gen_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 16)
+            .unwrap();
+        assert!(k == dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap());
+    }
+
+    #[test]
+    fn test_cmp_fn_int_macros_same_as_numeric() {
+        let beta: [u8; 16] = thread_rng().gen();
+        let bound = BoundState::LtBeta;
+        let expected = |hit: bool| if hit { beta } else { [0; 16] };
+        // 0x0100 and 0x00ff are swapped in the little-endian order
+        let f = cmp_fn_u16!(0x0100, beta);
+        assert_eq!(f.alpha, [0x01, 0x00]);
+        assert_eq!(
+            f.eval_plain(&0x00ffu16.to_be_bytes(), bound),
+            expected(true)
+        );
+        assert_eq!(
+            f.eval_plain(&0x0101u16.to_be_bytes(), bound),
+            expected(false)
+        );
+        let f = cmp_fn_u8!(0x80, beta);
+        assert_eq!(f.eval_plain(&[0x7f], bound), expected(true));
+        let alpha: u32 = thread_rng().gen();
+        let f = cmp_fn_u32!(alpha, beta);
+        (0..100).for_each(|_| {
+            let x: u32 = thread_rng().gen();
+            assert_eq!(f.eval_plain(&x.to_be_bytes(), bound), expected(x < alpha));
+        });
+        let alpha: u64 = thread_rng().gen();
+        let f = cmp_fn_u64!(alpha, beta);
+        (0..100).for_each(|_| {
+            let x: u64 = thread_rng().gen();
+            assert_eq!(f.eval_plain(&x.to_be_bytes(), bound), expected(x < alpha));
+        });
+        let alpha: u128 = thread_rng().gen();
+        let f = cmp_fn_u128!(alpha, beta);
+        (0..100).for_each(|_| {
+            let x: u128 = thread_rng().gen();
+            assert_eq!(f.eval_plain(&x.to_be_bytes(), bound), expected(x < alpha));
+        });
+        // Also through the tree
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let f = cmp_fn_u32!(1000, beta);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+        let xs = [999u32, 1000, 1001];
+        let ks = k.split();
+        let [ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_ord(
+                b,
+                kb,
+                &xs.iter().collect::<Vec<_>>(),
+                &mut ys.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+            ys
+        });
+        let ys: Vec<_> = ys0
+            .iter()
+            .zip(ys1.iter())
+            .map(|(y0, y1)| xor(&[y0, y1]))
+            .collect();
+        assert_eq!(ys, vec![expected(true), expected(false), expected(false)]);
+    }
+
+    #[test]
+    fn test_to_domain_bits_int_keeps_order() {
+        let mut xs: Vec<i16> = (0..100).map(|_| thread_rng().gen()).collect();
+        xs.extend([i16::MIN, -1, 0, 1, i16::MAX]);
+        xs.iter().for_each(|a| {
+            xs.iter()
+                .for_each(|b| assert_eq!(a.cmp(b), a.to_domain_bits().cmp(&b.to_domain_bits())));
+        });
+    }
+
+    #[test]
+    fn test_dcf_gray_encoding_equality_and_adjacency() {
+        let dcf = DcfImpl::<1, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let beta: [u8; 16] = thread_rng().gen();
+        let alpha = GrayEncoding(thread_rng().gen::<u8>());
+        let [k_lt, k_gt] = [BoundState::LtBeta, BoundState::GtBeta].map(|bound| {
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            dcf.gen_ord(&alpha, &beta, [&s0s[0], &s0s[1]], bound)
+        });
+        let xs: Vec<GrayEncoding<u8>> = (0..=255).map(GrayEncoding).collect();
+        let xs: Vec<&GrayEncoding<u8>> = xs.iter().collect();
+        let [ys_lt, ys_gt] = [k_lt, k_gt].map(|k| {
+            let ks = k.split();
+            let [ys0, ys1] = [false, true].map(|b| {
+                let kb = &ks[b as usize];
+                let mut ys = vec![[0; 16]; xs.len()];
+                dcf.eval_ord(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                ys
+            });
+            ys0.iter()
+                .zip(ys1.iter())
+                .map(|(y0, y1)| xor(&[y0, y1]))
+                .collect::<Vec<_>>()
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            // Both 0 only at `$\alpha$`
+            let is_eq = ys_lt[i] == [0; 16] && ys_gt[i] == [0; 16];
+            assert_eq!(is_eq, x.0 == alpha.0);
+            // Compared in the order of the codes
+            let expected_lt = x.to_domain_bits() < alpha.to_domain_bits();
+            assert_eq!(ys_lt[i], if expected_lt { beta } else { [0; 16] });
+        });
+        (0..u8::MAX).for_each(|v| {
+            let [c0, c1] = [v, v + 1].map(|v| GrayEncoding(v).to_domain_bits()[0]);
+            assert_eq!((c0 ^ c1).count_ones(), 1);
+        });
+        // Not the order of the values
+        assert!(GrayEncoding(3u8).to_domain_bits() < GrayEncoding(2u8).to_domain_bits());
+    }
+}
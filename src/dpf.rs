@@ -0,0 +1,307 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Distributed point function on the same tree, keys, and PRG as the DCF
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use bitvec::prelude::*;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::group::{Group, XorGroup};
+use crate::utils::{for_each_zip, xor, xor_inplace, Hex};
+use crate::{check_eval_inputs, prg_fingerprint, Cw, DcfError, Prg, Share, IDX_L, IDX_R};
+
+/// API of Distributed point function.
+///
+/// See [`PointFn`] for `N` and `LAMBDA`.
+pub trait Dpf<const N: usize, const LAMBDA: usize> {
+    /// `s0s` is `$s^{(0)}_0$` and `$s^{(0)}_1$` which should be randomly sampled
+    fn gen(
+        &self,
+        f: &PointFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+    ) -> Result<Share<LAMBDA>, DcfError>;
+
+    /// `b` is the party. `false` is 0 and `true` is 1.
+    ///
+    /// Fails the same as [`crate::Dcf::eval`], and with [`DcfError::InvalidShareLength`]
+    /// if the key does not cover the whole domain, which a DPF key always does.
+    fn eval(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError>;
+}
+
+/// Point function.
+///
+/// - `N` is the **byte** size of the domain.
+/// - `LAMBDA` here is used as the **byte** size of the range, the same as [`crate::CmpFn`].
+pub struct PointFn<const N: usize, const LAMBDA: usize> {
+    /// `$\alpha$`
+    pub alpha: [u8; N],
+    /// `$\beta$`
+    pub beta: [u8; LAMBDA],
+}
+
+impl<const N: usize, const LAMBDA: usize> fmt::Debug for PointFn<N, LAMBDA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PointFn")
+            .field("alpha", &Hex(&self.alpha))
+            .field("beta", &Hex(&self.beta))
+            .finish()
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize> PointFn<N, LAMBDA> {
+    /// Plaintext oracle. What the 2 parties' outputs should be reconstructed to.
+    pub fn eval_plain(&self, x: &[u8; N]) -> [u8; LAMBDA] {
+        if x == &self.alpha {
+            self.beta
+        } else {
+            [0; LAMBDA]
+        }
+    }
+}
+
+/// Implementation of [`Dpf`], which is the DPF of BGI16 in the same tree as [`crate::DcfImpl`].
+///
+/// Only the seeds and the control bits are corrected along the path of `$\alpha$`,
+/// and `$\beta$` is only in the last correction word `cw_np1`,
+/// so the `v` of the PRG outputs is unused and the `v` of all of the [`Cw`]s is 0.
+/// The keys are [`Share`]s, so they are split, serialized, and checked the same as the ones of DCF.
+/// Nothing in a key tells a DPF key from a DCF one, so [`crate::DcfImpl`] with the same PRG
+/// accepts a DPF key and outputs garbage.
+/// The caller must keep track of which keys are of DPF and evaluate them by [`DpfImpl`].
+///
+/// `G` is the group the outputs are reconstructed in, which is [`XorGroup`] by default.
+pub struct DpfImpl<const N: usize, const LAMBDA: usize, PrgT, G = XorGroup>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    prg: PrgT,
+    /// [`DpfImpl::prg_check`] computed on first use
+    prg_check: OnceLock<[u8; LAMBDA]>,
+    /// `fn() -> G` keeps `DpfImpl` `Send` and `Sync` regardless of `G`
+    group: PhantomData<fn() -> G>,
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DpfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Fails the build for degenerate parameters, the same as [`crate::DcfImpl::new`]
+    const PARAMS_OK: () = {
+        assert!(N > 0, "N, the byte size of the domain, must be positive");
+        assert!(
+            LAMBDA > 0,
+            "LAMBDA, the byte size of seeds and outputs, must be positive"
+        );
+    };
+
+    pub fn new(prg: PrgT) -> Self {
+        let () = Self::PARAMS_OK;
+        Self {
+            prg,
+            prg_check: OnceLock::new(),
+            group: PhantomData,
+        }
+    }
+
+    /// Fingerprint of the PRG, the same as [`crate::DcfImpl::prg_check`], computed once and cached
+    fn prg_check(&self) -> [u8; LAMBDA] {
+        *self.prg_check.get_or_init(|| prg_fingerprint(&self.prg))
+    }
+
+    /// Walks the path of `x` from the root and writes the output of the party `b` to `y`
+    fn eval_point(&self, b: bool, k: &Share<LAMBDA>, x: &[u8; N], y: &mut [u8; LAMBDA]) {
+        let mut s = *k.party_seed(b);
+        let mut t = b;
+        for (i, cw) in k.cws.iter().enumerate() {
+            let [(mut sl, _, mut tl), (mut sr, _, mut tr)] = self.prg.gen(&s);
+            if t {
+                xor_inplace(&mut sl, &[&cw.s]);
+                xor_inplace(&mut sr, &[&cw.s]);
+                tl ^= cw.tl;
+                tr ^= cw.tr;
+            }
+            (s, t) = if x.view_bits::<Msb0>()[i] {
+                (sr, tr)
+            } else {
+                (sl, tl)
+            };
+        }
+        *y = s;
+        if t {
+            G::add(y, &k.cw_np1);
+        }
+        // `$(-1)^b$`
+        G::neg_if(y, b);
+        #[cfg(feature = "zeroize")]
+        s.zeroize();
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> Dpf<N, LAMBDA> for DpfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    fn gen(
+        &self,
+        f: &PointFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+    ) -> Result<Share<LAMBDA>, DcfError> {
+        let mut ss = [*s0s[0], *s0s[1]];
+        let mut ts = [false, true];
+        let cws = (0..8 * N)
+            .map(|i| {
+                let [(s0l, _, t0l), (s0r, _, t0r)] = self.prg.gen(&ss[0]);
+                let [(s1l, _, t1l), (s1r, _, t1r)] = self.prg.gen(&ss[1]);
+                let alpha_i = f.alpha.view_bits::<Msb0>()[i];
+                let (keep, lose) = if alpha_i {
+                    (IDX_R, IDX_L)
+                } else {
+                    (IDX_L, IDX_R)
+                };
+                let s_cw = xor(&[[&s0l, &s0r][lose], [&s1l, &s1r][lose]]);
+                let tl_cw = t0l ^ t1l ^ alpha_i ^ true;
+                let tr_cw = t0r ^ t1r ^ alpha_i;
+                let t_keep_cw = [tl_cw, tr_cw][keep];
+                let [s0_keep, s1_keep] = [[&s0l, &s0r][keep], [&s1l, &s1r][keep]];
+                ss = [
+                    xor(&[s0_keep, if ts[0] { &s_cw } else { &[0; LAMBDA] }]),
+                    xor(&[s1_keep, if ts[1] { &s_cw } else { &[0; LAMBDA] }]),
+                ];
+                ts = [
+                    [t0l, t0r][keep] ^ (ts[0] & t_keep_cw),
+                    [t1l, t1r][keep] ^ (ts[1] & t_keep_cw),
+                ];
+                Cw {
+                    s: s_cw,
+                    v: [0; LAMBDA],
+                    tl: tl_cw,
+                    tr: tr_cw,
+                }
+            })
+            .collect();
+        // `$CW^{(n + 1)} = (-1)^{t^{(n)}_1} (\beta - s^{(n)}_0 + s^{(n)}_1)$`
+        let mut cw_np1 = f.beta;
+        G::sub(&mut cw_np1, &ss[0]);
+        G::add(&mut cw_np1, &ss[1]);
+        G::neg_if(&mut cw_np1, ts[1]);
+        #[cfg(feature = "zeroize")]
+        {
+            ss.zeroize();
+            ts.zeroize();
+        }
+        Ok(Share {
+            s0s: vec![*s0s[0], *s0s[1]],
+            cws,
+            cw_np1,
+            n_bytes: N,
+            prg_check: Some(self.prg_check()),
+        })
+    }
+
+    fn eval(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), ys.len())?;
+        if k.cws.len() != 8 * N {
+            return Err(DcfError::InvalidShareLength {
+                expected: 8 * N,
+                got: k.cws.len(),
+            });
+        }
+        for_each_zip(xs, ys, |x, y| self.eval_point(b, k, x, y));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::ArithGroup;
+
+    const ALPHAS: &[&[u8; 16]] = &[
+        b"K\xa9W\xf5\xdd\x05\xe9\xfc?\x04\xf6\xfbUo\xa8C",
+        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csU",
+        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csV",
+        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csW",
+        b"\xef\x96\x97\xd7\x8f\x8a\xa4AP\n\xb35\xb5k\xff\x97",
+    ];
+    const BETA: &[u8; 16] = b"\x03\x11\x97\x12C\x8a\xe9#\x81\xa8\xde\xa8\x8f \xc0\xbb";
+
+    #[test]
+    fn test_dpf_gen_then_eval_ok() {
+        let dpf = DpfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = PointFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dpf.gen(&f, [&s0s[0], &s0s[1]]).unwrap();
+        assert!(k.cws.iter().all(|cw| cw.v == [0; 16]));
+        let [k0, k1] = k.split();
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dpf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dpf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        ys1 = vec![[0; 16], [0; 16], BETA.to_owned(), [0; 16], [0; 16]];
+        assert_eq!(ys0, ys1);
+        assert_eq!(
+            format!(
+                "{:?}",
+                PointFn {
+                    alpha: [0xab, 0x01],
+                    beta: [0x0c]
+                }
+            ),
+            "PointFn { alpha: ab01, beta: 0c }"
+        );
+    }
+
+    #[test]
+    fn test_dpf_gen_then_eval_arith_full_domain_ok() {
+        let dpf = DpfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        [0, 1, 128, 254, 255].iter().for_each(|&alpha| {
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            let f = PointFn {
+                alpha: [alpha],
+                beta: thread_rng().gen(),
+            };
+            let [k0, k1] = dpf.gen(&f, [&s0s[0], &s0s[1]]).unwrap().split();
+            (0..=255u8).for_each(|x| {
+                let [mut y, y1] = [(&k0, false), (&k1, true)].map(|(kb, b)| {
+                    let mut y = [0; 16];
+                    dpf.eval(b, kb, &[&[x]], &mut [&mut y]).unwrap();
+                    y
+                });
+                ArithGroup::add(&mut y, &y1);
+                assert_eq!(y, f.eval_plain(&[x]));
+            });
+        });
+    }
+}
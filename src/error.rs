@@ -0,0 +1,196 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Errors of the fallible APIs
+
+use std::{fmt, io};
+
+/// Error of the fallible APIs, which validate the inputs instead of panicking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DcfError {
+    /// `xs[index]` has `len` bytes, more than the domain byte size `max`
+    InputTooLong {
+        index: usize,
+        len: usize,
+        max: usize,
+    },
+    /// The PRG of eval differs from the one of gen, e.g., built with different keys,
+    /// detected by [`crate::Share::prg_check`] in eval or by [`crate::DcfImpl::eval_checked`]
+    PrgMismatch,
+    /// The share takes `len` bytes with the length prefix, more than `target_len` of [`crate::Share::to_bytes_padded`]
+    ShareTooLong { len: usize, target_len: usize },
+    /// The bytes given to [`crate::Share::from_bytes`] or [`crate::Share::from_bytes_padded`]
+    /// are not an output of the serialization of [`crate::Share`]
+    MalformedBytes,
+    /// The share is generated with the domain byte size `got`,
+    /// but the [`crate::Share::from_bytes`] caller expects `expected`
+    DomainSizeMismatch { expected: usize, got: usize },
+    /// The outputs of party 0 and party 1 to reconstruct have different lengths `len0` and `len1`
+    OutputLengthMismatch { len0: usize, len1: usize },
+    /// The key has `got` seeds in `s0s`, but `expected` are required
+    SeedCountMismatch { expected: usize, got: usize },
+    /// `align` of [`crate::aligned::AlignedBuf`] is not a power of 2
+    InvalidAlignment { align: usize },
+    /// The threshold at `index` of [`crate::step::StepFn`] is the same as the one before it
+    DuplicateThreshold { index: usize },
+    /// The threshold at `index` of [`crate::step::StepFn`] is less than the one before it
+    UnsortedThresholds { index: usize },
+    /// The key has `got` seeds in `s0s`, other than 1 for a party or 2 from gen,
+    /// detected by [`crate::Share::check_invariants`]
+    InvalidSeedCount { got: usize },
+    /// The key has `got` correlation words, 0 or more than `max`, the bit size of the domain,
+    /// detected by [`crate::Share::check_invariants`]
+    CwCountOutOfRange { got: usize, max: usize },
+    /// The CRC32 of the bytes given to [`crate::Share::from_bytes`] mismatches
+    CorruptData,
+    /// `lambda` is out of the envelope `params` of the PRG
+    UnsupportedLambda {
+        lambda: usize,
+        params: crate::SupportedParams,
+    },
+    /// The stream of [`crate::Share::read_framed`] ends within a frame
+    TruncatedFrame,
+    /// Reading or writing a stream fails with an IO error of `kind`
+    Io { kind: io::ErrorKind },
+    /// `leading_fixed_bits` of [`crate::Dcf::gen_fixed_bits`] is not less than the bit size of the domain,
+    /// or `$\alpha$` has a 1 in them
+    InvalidFixedBits { leading_fixed_bits: usize },
+    /// `ys` has `got` outputs, but `expected` are required, e.g., 1 for each of `xs` of [`crate::Dcf::eval`]
+    MismatchedOutputBuffer { expected: usize, got: usize },
+    /// An argument with 1 item for each input, e.g., the masks of [`crate::DcfImpl::eval_masked_output`],
+    /// has `got` items, but `expected` are required
+    InvalidInputCount { expected: usize, got: usize },
+    /// The key has `got` correlation words, but exactly `expected` are required,
+    /// e.g., the bit size of the domain for [`crate::dpf::DpfImpl`], which has no fixed bits
+    InvalidShareLength { expected: usize, got: usize },
+    /// The key of the group `from` can not be evaluated with the group `to`,
+    /// detected by [`crate::Share::reinterpret_group`]
+    IncompatibleGroups {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// The domain has `bits` bits, more than `max` of [`crate::DcfImpl::eval_full`],
+    /// or of the indices of [`crate::Dcf::eval_all`]
+    DomainTooLarge { bits: usize, max: usize },
+    /// `bits` of [`crate::DcfImpl::gen_bits`] is 0 or more than `max`, the bit size of `[u8; N]`
+    InvalidBitWidth { bits: usize, max: usize },
+    /// The implementation of [`crate::Dcf`] does not support [`crate::Dcf::gen_with_strategy`]
+    UnsupportedStrategy,
+    /// The interval `$[a, b)$` at `index` has `$a \ge b$`, e.g., the one of [`crate::DcfImpl::gen_difference`] at 0
+    InvalidInterval { index: usize },
+    /// `sample_points` of [`crate::DcfImpl::statistical_uniformity_test`] is empty,
+    /// for which the statistic is undefined
+    NoSamplePoints,
+}
+
+impl fmt::Display for DcfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DcfError::InputTooLong { index, len, max } => write!(
+                f,
+                "input {} has {} bytes, more than the domain of {} bytes",
+                index, len, max
+            ),
+            DcfError::PrgMismatch => write!(f, "PRG of eval differs from the one of gen"),
+            DcfError::ShareTooLong { len, target_len } => write!(
+                f,
+                "share takes {} bytes, more than the padded size of {} bytes",
+                len, target_len
+            ),
+            DcfError::MalformedBytes => write!(f, "malformed share bytes"),
+            DcfError::DomainSizeMismatch { expected, got } => write!(
+                f,
+                "share is of the domain of {} bytes, but {} bytes are expected",
+                got, expected
+            ),
+            DcfError::OutputLengthMismatch { len0, len1 } => {
+                write!(f, "party 0 has {} outputs but party 1 has {}", len0, len1)
+            }
+            DcfError::SeedCountMismatch { expected, got } => {
+                write!(f, "key has {} seeds, but {} are required", got, expected)
+            }
+            DcfError::InvalidAlignment { align } => {
+                write!(f, "alignment {} is not a power of 2", align)
+            }
+            DcfError::DuplicateThreshold { index } => {
+                write!(f, "threshold {} is the same as the one before it", index)
+            }
+            DcfError::UnsortedThresholds { index } => {
+                write!(f, "threshold {} is less than the one before it", index)
+            }
+            DcfError::InvalidSeedCount { got } => {
+                write!(f, "key has {} seeds, but 1 or 2 are required", got)
+            }
+            DcfError::CwCountOutOfRange { got, max } => write!(
+                f,
+                "key has {} correlation words, but 1 to {} are required",
+                got, max
+            ),
+            DcfError::CorruptData => write!(f, "share bytes are corrupted"),
+            DcfError::UnsupportedLambda { lambda, params } => write!(
+                f,
+                "LAMBDA {} is not a positive multiple of {} up to {}",
+                lambda, params.lambda_multiple, params.max_lambda
+            ),
+            DcfError::TruncatedFrame => write!(f, "stream ends within a share frame"),
+            DcfError::Io { kind } => write!(f, "IO error: {}", kind),
+            DcfError::InvalidFixedBits { leading_fixed_bits } => write!(
+                f,
+                "{} leading bits can not be fixed for the domain and alpha",
+                leading_fixed_bits
+            ),
+            DcfError::MismatchedOutputBuffer { expected, got } => {
+                write!(f, "{} outputs, but {} are required", got, expected)
+            }
+            DcfError::InvalidInputCount { expected, got } => {
+                write!(
+                    f,
+                    "{} items for the inputs, but {} are required",
+                    got, expected
+                )
+            }
+            DcfError::InvalidShareLength { expected, got } => write!(
+                f,
+                "key has {} correlation words, but {} are required",
+                got, expected
+            ),
+            DcfError::IncompatibleGroups { from, to } => {
+                write!(f, "key of the group {} can not be used as {}", from, to)
+            }
+            DcfError::DomainTooLarge { bits, max } => write!(
+                f,
+                "domain of {} bits is too large to materialize, at most {} bits",
+                bits, max
+            ),
+            DcfError::InvalidBitWidth { bits, max } => {
+                write!(f, "bit width {} is not in 1 to {}", bits, max)
+            }
+            DcfError::UnsupportedStrategy => {
+                write!(
+                    f,
+                    "correction strategies other than the bound are not supported"
+                )
+            }
+            DcfError::InvalidInterval { index } => {
+                write!(
+                    f,
+                    "interval {} does not have its start before its end",
+                    index
+                )
+            }
+            DcfError::NoSamplePoints => write!(f, "no sample points to test"),
+        }
+    }
+}
+
+impl std::error::Error for DcfError {}
+
+/// [`io::ErrorKind::UnexpectedEof`] is mapped to [`DcfError::TruncatedFrame`], since streams only carry frames here
+impl From<io::Error> for DcfError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => DcfError::TruncatedFrame,
+            kind => DcfError::Io { kind },
+        }
+    }
+}
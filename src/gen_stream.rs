@@ -0,0 +1,136 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gen yielding the correlation words level by level, e.g., to pipeline gen with sending them
+
+use crate::group::Group;
+use crate::{BoundState, CmpFn, Cw, DcfImpl, GenState, Prg, Share};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// Output of [`DcfImpl::gen_streaming`], in the order of the fields of [`Share`] after `s0s`
+pub enum GenEvent<const LAMBDA: usize> {
+    /// The correlation word of the next level, from the root
+    Cw(Cw<LAMBDA>),
+    /// `$CW^{(n + 1)}$`, which is the last event
+    CwNp1([u8; LAMBDA]),
+}
+
+impl<const LAMBDA: usize> GenEvent<LAMBDA> {
+    /// Inverse of [`DcfImpl::gen_streaming`] with the same `s0s` and the domain byte size `n_bytes`.
+    ///
+    /// Returns `None` if the events do not end with exactly 1 [`GenEvent::CwNp1`].
+    /// [`Share::prg_check`] is not in the events, so it is `None`,
+    /// which the dealer can set to [`DcfImpl::prg_check`].
+    pub fn collect_share<I>(
+        events: I,
+        s0s: [&[u8; LAMBDA]; 2],
+        n_bytes: usize,
+    ) -> Option<Share<LAMBDA>>
+    where
+        I: IntoIterator<Item = GenEvent<LAMBDA>>,
+    {
+        let mut cws = vec![];
+        let mut cw_np1 = None;
+        for event in events {
+            match (event, cw_np1) {
+                (GenEvent::Cw(cw), None) => cws.push(cw),
+                (GenEvent::CwNp1(cw), None) => cw_np1 = Some(cw),
+                (_, Some(_)) => return None,
+            }
+        }
+        Some(Share {
+            s0s: vec![s0s[0].to_owned(), s0s[1].to_owned()],
+            cws,
+            cw_np1: cw_np1?,
+            n_bytes,
+            prg_check: None,
+        })
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Like [`crate::Dcf::gen`] but lazily yields each correlation word as soon as its level is done.
+    ///
+    /// The `8 N` levels come first as [`GenEvent::Cw`] and [`GenEvent::CwNp1`] comes last.
+    /// The seeds are not yielded since the dealer already has them,
+    /// and [`GenEvent::collect_share`] assembles the events with them into the same [`Share`] as gen.
+    pub fn gen_streaming<'a>(
+        &'a self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> impl Iterator<Item = GenEvent<LAMBDA>> + 'a {
+        let f = CmpFn {
+            alpha: f.alpha,
+            beta: f.beta,
+        };
+        let mut state = GenState::new(s0s);
+        (0..8 * N + 1).map(move |i| {
+            if i < 8 * N {
+                GenEvent::Cw(self.gen_level(&f, &bound, i, &mut state))
+            } else {
+                let cw_np1 = Self::gen_leaf(&f, &bound, &state);
+                // The state is no longer needed after the last level
+                #[cfg(feature = "zeroize")]
+                state.zeroize();
+                GenEvent::CwNp1(cw_np1)
+            }
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, Dcf, XorGroup};
+
+    fn run<G: Group<16>>() {
+        let dcf = DcfImpl::<4, 16, _, G>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        [BoundState::LtBeta, BoundState::GtBeta]
+            .into_iter()
+            .for_each(|bound| {
+                let k = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+                // What a receiver gets by sending each event as soon as it is yielded
+                let sent: Vec<Vec<u8>> = dcf
+                    .gen_streaming(&f, [&s0s[0], &s0s[1]], bound)
+                    .map(|event| match event {
+                        GenEvent::Cw(cw) => bincode::serialize(&cw).unwrap(),
+                        GenEvent::CwNp1(cw_np1) => cw_np1.to_vec(),
+                    })
+                    .collect();
+                assert_eq!(sent.len(), 4 * 8 + 1);
+                let events = dcf.gen_streaming(&f, [&s0s[0], &s0s[1]], bound);
+                let mut k_streamed =
+                    GenEvent::collect_share(events, [&s0s[0], &s0s[1]], 4).unwrap();
+                assert_eq!(k_streamed.prg_check, None);
+                k_streamed.prg_check = Some(dcf.prg_check());
+                assert_eq!(k_streamed.to_bytes(), k.to_bytes());
+                k.cws.iter().zip(sent.iter()).for_each(|(cw, bytes)| {
+                    assert_eq!(&bincode::serialize(cw).unwrap(), bytes);
+                });
+                assert_eq!(sent.last().unwrap(), &k.cw_np1.to_vec());
+            });
+        assert!(GenEvent::<16>::collect_share([], [&s0s[0], &s0s[1]], 4).is_none());
+    }
+
+    #[test]
+    fn test_dcf_gen_streaming_same_as_gen() {
+        run::<XorGroup>();
+        run::<ArithGroup>();
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Output groups of [`crate::DcfImpl`].
+//!
+//! The 2 parties' outputs are reconstructed by the addition of the group.
+//! Elements are `[u8; LAMBDA]`, which are also what the PRG outputs,
+//! so `Convert` in the paper is the identity.
+
+/// Abelian group on `[u8; LAMBDA]` whose identity is all zeros
+pub trait Group<const LAMBDA: usize> {
+    /// Name of the addition, e.g., for [`crate::Share::reinterpret_group`] to tell groups sharing keys.
+    ///
+    /// Groups with the same name must have the same [`Group::add`] and [`Group::neg`].
+    /// `None`, the default, matches no other group.
+    const ADDITION: Option<&'static str> = None;
+
+    /// `lhs = lhs + rhs`
+    fn add(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]);
+
+    /// `x = -x`
+    fn neg(x: &mut [u8; LAMBDA]);
+
+    /// `lhs = lhs - rhs`
+    fn sub(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        let mut rhs_neg = *rhs;
+        Self::neg(&mut rhs_neg);
+        Self::add(lhs, &rhs_neg);
+    }
+
+    /// `x = (-1)^sign * x`
+    fn neg_if(x: &mut [u8; LAMBDA], sign: bool) {
+        if sign {
+            Self::neg(x);
+        }
+    }
+}
+
+/// Reconstruction of the 2 parties' outputs at the same input, e.g., by [`crate::simulation::TwoPartyDcf::reconstruct_with`].
+///
+/// Every [`Group`] combines by its addition.
+/// A custom rule is only valid if it is the addition of the group `G` the keys are generated with,
+/// as the tree derives the outputs linearly in `G`, e.g., XOR for [`XorGroup`].
+/// Rules that are not abelian groups, e.g., OR, or do not match `G` give meaningless outputs.
+pub trait Combine<const LAMBDA: usize> {
+    fn combine(y0: &[u8; LAMBDA], y1: &[u8; LAMBDA]) -> [u8; LAMBDA];
+}
+
+impl<const LAMBDA: usize, G> Combine<LAMBDA> for G
+where
+    G: Group<LAMBDA>,
+{
+    fn combine(y0: &[u8; LAMBDA], y1: &[u8; LAMBDA]) -> [u8; LAMBDA] {
+        let mut y = *y0;
+        G::add(&mut y, y1);
+        y
+    }
+}
+
+/// Reconstructs `$f(x)$` from the output `y0` of party 0 and `y1` of party 1 at the same `x`.
+///
+/// Reconstructs by XOR, i.e., only for keys of [`XorGroup`], the default.
+/// For other groups, use [`Combine::combine`] of the group.
+pub fn combine<const LAMBDA: usize>(y0: &[u8; LAMBDA], y1: &[u8; LAMBDA]) -> [u8; LAMBDA] {
+    <XorGroup as Combine<LAMBDA>>::combine(y0, y1)
+}
+
+/// [`combine`] of the outputs of party 0 and party 1 at the same inputs in the same order.
+///
+/// Panics if they have different lengths.
+/// [`crate::simulation::TwoPartyDcf::reconstruct`] fails instead.
+pub fn combine_all<const LAMBDA: usize>(
+    ys0: &[[u8; LAMBDA]],
+    ys1: &[[u8; LAMBDA]],
+) -> Vec<[u8; LAMBDA]> {
+    assert_eq!(ys0.len(), ys1.len());
+    ys0.iter()
+        .zip(ys1.iter())
+        .map(|(y0, y1)| combine(y0, y1))
+        .collect()
+}
+
+/// [`Group`] with a multiplication distributing over the addition
+pub trait Ring<const LAMBDA: usize>: Group<LAMBDA> {
+    /// The multiplicative identity
+    fn one() -> [u8; LAMBDA];
+
+    /// `lhs = lhs * rhs`
+    fn mul(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]);
+}
+
+/// Bitwise XOR, i.e., `$(\mathbb{Z}_2)^{8 \lambda}$`, where negation is the identity.
+///
+/// This is the group used by default.
+pub struct XorGroup;
+
+impl<const LAMBDA: usize> Group<LAMBDA> for XorGroup {
+    const ADDITION: Option<&'static str> = Some("xor");
+
+    fn add(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        lhs.iter_mut().zip(rhs.iter()).for_each(|(l, r)| *l ^= r);
+    }
+
+    fn neg(_x: &mut [u8; LAMBDA]) {}
+}
+
+/// Bitwise AND as the multiplication
+impl<const LAMBDA: usize> Ring<LAMBDA> for XorGroup {
+    fn one() -> [u8; LAMBDA] {
+        [0xff; LAMBDA]
+    }
+
+    fn mul(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        lhs.iter_mut().zip(rhs.iter()).for_each(|(l, r)| *l &= r);
+    }
+}
+
+/// Integers modulo `$2^{8 \lambda}$`, encoded in big-endian.
+///
+/// Reducing the outputs modulo a smaller power of 2, e.g., taking the last 4 bytes as a `u32`,
+/// still gives valid additive shares.
+pub struct ArithGroup;
+
+impl<const LAMBDA: usize> Group<LAMBDA> for ArithGroup {
+    const ADDITION: Option<&'static str> = Some("wrapping add");
+
+    fn add(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        let mut carry = 0u16;
+        lhs.iter_mut().zip(rhs.iter()).rev().for_each(|(l, r)| {
+            let sum = *l as u16 + *r as u16 + carry;
+            *l = sum as u8;
+            carry = sum >> 8;
+        });
+    }
+
+    fn neg(x: &mut [u8; LAMBDA]) {
+        x.iter_mut().for_each(|byte| *byte = !*byte);
+        Self::add(x, &<Self as Ring<LAMBDA>>::one());
+    }
+}
+
+impl<const LAMBDA: usize> Ring<LAMBDA> for ArithGroup {
+    fn one() -> [u8; LAMBDA] {
+        let mut one = [0; LAMBDA];
+        one[LAMBDA - 1] = 1;
+        one
+    }
+
+    fn mul(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        // Schoolbook multiplication from the least significant byte, dropping the overflow
+        let mut res = [0u8; LAMBDA];
+        for i in 0..LAMBDA {
+            let mut carry = 0u32;
+            for j in 0..LAMBDA - i {
+                let k = LAMBDA - 1 - i - j;
+                let prod =
+                    lhs[LAMBDA - 1 - i] as u32 * rhs[LAMBDA - 1 - j] as u32 + res[k] as u32 + carry;
+                res[k] = prod as u8;
+                carry = prod >> 8;
+            }
+        }
+        *lhs = res;
+    }
+}
+
+/// Each byte as an element of `$GF(2^8)$` with the AES polynomial `$x^8 + x^4 + x^3 + x + 1$`,
+/// e.g., for Reed-Solomon encoding of the outputs.
+///
+/// The addition is XOR, so the keys and outputs are the same as the ones of [`XorGroup`].
+/// Only the multiplication differs.
+pub struct Gf256LaneGroup;
+
+impl<const LAMBDA: usize> Group<LAMBDA> for Gf256LaneGroup {
+    const ADDITION: Option<&'static str> = <XorGroup as Group<LAMBDA>>::ADDITION;
+
+    fn add(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        XorGroup::add(lhs, rhs);
+    }
+
+    fn neg(_x: &mut [u8; LAMBDA]) {}
+}
+
+/// Lane-wise `$GF(2^8)$` multiplication
+impl<const LAMBDA: usize> Ring<LAMBDA> for Gf256LaneGroup {
+    fn one() -> [u8; LAMBDA] {
+        [1; LAMBDA]
+    }
+
+    fn mul(lhs: &mut [u8; LAMBDA], rhs: &[u8; LAMBDA]) {
+        lhs.iter_mut()
+            .zip(rhs.iter())
+            .for_each(|(l, r)| *l = gf256_mul(*l, *r));
+    }
+}
+
+/// Shift-and-add multiplication in `$GF(2^8)$`, reducing by `0x1b` on overflow
+fn gf256_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut prod = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            prod ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    prod
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arith_group_same_as_u128() {
+        let a = 0xfedcba9876543210_0123456789abcdefu128;
+        let b = 0x0123456789abcdef_fedcba9876543210u128;
+        let mut x = a.to_be_bytes();
+        ArithGroup::add(&mut x, &b.to_be_bytes());
+        assert_eq!(u128::from_be_bytes(x), a.wrapping_add(b));
+        let mut x = a.to_be_bytes();
+        ArithGroup::neg(&mut x);
+        assert_eq!(u128::from_be_bytes(x), a.wrapping_neg());
+        let mut x = a.to_be_bytes();
+        ArithGroup::sub(&mut x, &b.to_be_bytes());
+        assert_eq!(u128::from_be_bytes(x), a.wrapping_sub(b));
+        let mut x = a.to_be_bytes();
+        ArithGroup::mul(&mut x, &b.to_be_bytes());
+        assert_eq!(u128::from_be_bytes(x), a.wrapping_mul(b));
+    }
+
+    #[test]
+    fn test_xor_group_neg_is_identity() {
+        let a = [0x5a; 16];
+        let mut x = a;
+        XorGroup::neg(&mut x);
+        assert_eq!(x, a);
+        XorGroup::sub(&mut x, &a);
+        assert_eq!(x, [0; 16]);
+    }
+
+    #[test]
+    fn test_gf256_lane_group_mul() {
+        // The example in FIPS 197
+        let mut x = [0x57; 4];
+        Gf256LaneGroup::mul(&mut x, &[0x83, 0x13, 0x01, 0x00]);
+        assert_eq!(x, [0xc1, 0xfe, 0x57, 0x00]);
+        // Every non-zero element has an inverse
+        for a in 1..=255u8 {
+            assert!((1..=255u8).any(|b| gf256_mul(a, b) == 1));
+        }
+    }
+
+    #[cfg(feature = "prg")]
+    #[test]
+    fn test_arith_group_cws_hide_alpha() {
+        use rand::{thread_rng, Rng};
+
+        use crate::prg::Aes256HirosePrg;
+        use crate::{BoundState, CmpFn, Dcf, DcfImpl};
+
+        let dcf =
+            DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(crate::test_fixtures::KEYS));
+        crate::test_fixtures::assert_cws_hide_alpha(|alpha, s0s| {
+            let f = CmpFn {
+                alpha: *alpha,
+                beta: thread_rng().gen(),
+            };
+            dcf.gen(&f, s0s, BoundState::LtBeta).unwrap()
+        });
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inner products of the outputs of many DCFs with public weights, e.g., for linear algebra over shares
+
+use crate::group::Ring;
+use crate::{Dcf, DcfError, DcfImpl, Prg, Share};
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Ring<LAMBDA>,
+{
+    /// The share of the party `b` of `$\sum_j w_j f_j(x)$`,
+    /// where `$f_j$` is the DCF of `ks[j]` and `$w_j$` is the public `weights[j]`.
+    ///
+    /// Scaling by a public value is linear in the shares, so each party scales its own outputs.
+    /// The weighted sum only makes sense with [`crate::ArithGroup`].
+    /// `ks` are the keys of the party, 1 for each of the weights.
+    ///
+    /// Fails with [`DcfError::InvalidInputCount`] if the numbers of `ks` and `weights` differ,
+    /// or the same as [`Dcf::eval_at`] for any of `ks`.
+    pub fn eval_inner_product(
+        &self,
+        b: bool,
+        ks: &[&Share<LAMBDA>],
+        x: &[u8; N],
+        weights: &[[u8; LAMBDA]],
+    ) -> Result<[u8; LAMBDA], DcfError> {
+        if ks.len() != weights.len() {
+            return Err(DcfError::InvalidInputCount {
+                expected: weights.len(),
+                got: ks.len(),
+            });
+        }
+        let mut sum = [0; LAMBDA];
+        for (k, w) in ks.iter().zip(weights.iter()) {
+            let mut y = self.eval_at(b, k, x)?;
+            G::mul(&mut y, w);
+            G::add(&mut sum, &y);
+        }
+        Ok(sum)
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::group::Group;
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, BoundState, CmpFn};
+
+    #[test]
+    fn test_dcf_eval_inner_product_ok() {
+        let dcf = DcfImpl::<2, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let fs: Vec<CmpFn<2, 16>> = (0..8)
+            .map(|_| CmpFn {
+                alpha: thread_rng().gen(),
+                beta: (thread_rng().gen::<u32>() as u128).to_be_bytes(),
+            })
+            .collect();
+        let ks: Vec<Share<16>> = fs
+            .iter()
+            .map(|f| {
+                let s0s: [[u8; 16]; 2] = thread_rng().gen();
+                dcf.gen(f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap()
+            })
+            .collect();
+        let (ks0, ks1): (Vec<_>, Vec<_>) = ks
+            .into_iter()
+            .map(|k| {
+                let [k0, k1] = k.split();
+                (k0, k1)
+            })
+            .unzip();
+        let weights: Vec<u128> = (0..8).map(|_| thread_rng().gen::<u32>() as u128).collect();
+        let weight_bytes: Vec<[u8; 16]> = weights.iter().map(|w| w.to_be_bytes()).collect();
+        (0..20).map(|_| thread_rng().gen::<u16>()).for_each(|x| {
+            let x = x.to_be_bytes();
+            let [mut y, y1] = [(false, &ks0), (true, &ks1)].map(|(b, kbs)| {
+                let kbs: Vec<&Share<16>> = kbs.iter().collect();
+                dcf.eval_inner_product(b, &kbs, &x, &weight_bytes).unwrap()
+            });
+            ArithGroup::add(&mut y, &y1);
+            let expected = fs
+                .iter()
+                .zip(weights.iter())
+                .map(|(f, w)| u128::from_be_bytes(f.eval_plain(&x, BoundState::LtBeta)) * w)
+                .fold(0u128, |acc, v| acc.wrapping_add(v));
+            assert_eq!(u128::from_be_bytes(y), expected);
+        });
+    }
+}
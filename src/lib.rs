@@ -2,21 +2,86 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! See [`Dcf`]
+//!
+//! All randomness of gen flows in through `s0s`, and eval is deterministic given the key and the PRG.
+//! The library never samples from an RNG itself.
+//! `rand` is only imported by the `test_util` module of the `test-util` feature,
+//! which takes the RNG from the caller.
+//! To audit it, build with the `no-implicit-rng` feature, which excludes `test_util` and refuses to compile with `test-util`,
+//! so that no `rand` is linked into the library at all.
 #![feature(trivial_bounds)]
-#[cfg(feature = "prg")]
+
+#[cfg(all(feature = "test-util", feature = "no-implicit-rng"))]
+compile_error!("features `test-util` and `no-implicit-rng` are mutually exclusive, since `test-util` needs `rand`");
+
+pub mod aligned;
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+#[cfg(feature = "async")]
+pub mod async_eval;
+pub mod bit;
+pub mod challenge;
+pub mod codec;
+pub mod compare_swap;
+pub mod debug;
+pub mod domain;
+pub mod dpf;
+pub mod error;
+pub mod gen_stream;
+pub mod group;
+pub mod inner_product;
+#[cfg(any(feature = "prg", feature = "chacha"))]
 pub mod prg;
+pub mod ramp;
+pub mod rank;
+pub mod rectangle;
+#[cfg(feature = "secure-mem")]
+pub mod secure_mem;
+pub mod simulation;
+pub mod step;
+pub mod subtree;
+pub mod table;
+pub mod z2_32;
 
+// The only module with `rand`, which takes the RNG from the caller
+#[cfg(all(test, feature = "prg"))]
+mod test_fixtures;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 mod utils;
 
 use bitvec::prelude::*;
-#[cfg(feature = "multithread")]
-use rayon::prelude::*;
 
-use crate::utils::{xor, xor_inplace};
+pub use crate::codec::LeafCodec;
+pub use crate::domain::{GrayEncoding, ToDomainBits};
+pub use crate::error::DcfError;
+pub use crate::group::{
+    combine, combine_all, ArithGroup, Combine, Gf256LaneGroup, Group, Ring, XorGroup,
+};
+#[cfg(feature = "zeroize")]
+use crate::utils::zeroize_expanded;
+use crate::utils::{crc32, for_each_zip, xor, xor_inplace, Hex, HexPrefix, Secret};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use serde_with::serde_as;
-use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
 use std::fmt;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, RangeInclusive};
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// Default of [`DcfImpl::parallel_threshold`]
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 1 << 16;
+/// Largest bit size of the domain of a key that [`DcfImpl::eval_full`] materializes,
+/// which is `$2^{24}$` outputs, i.e., 256 MiB with `LAMBDA = 16`
+pub const MAX_FULL_DOMAIN_BITS: usize = 24;
+/// Env var overriding [`DEFAULT_PARALLEL_THRESHOLD`]
+const PARALLEL_THRESHOLD_ENV: &str = "DCF_PARALLEL_THRESHOLD";
 
 /// API of Distributed comparison function.
 ///
@@ -28,10 +93,124 @@ pub trait Dcf<const N: usize, const LAMBDA: usize> {
         f: &CmpFn<N, LAMBDA>,
         s0s: [&[u8; LAMBDA]; 2],
         bound: BoundState,
-    ) -> Share<LAMBDA>;
+    ) -> Result<Share<LAMBDA>, DcfError>;
+
+    /// Like [`Dcf::gen`], but the leading `leading_fixed_bits` bits of the domain are fixed to 0.
+    ///
+    /// The top tree levels are then deterministic and collapsed,
+    /// so the share only has `8 * N - leading_fixed_bits` correlation words.
+    /// `eval` infers the fixed bits from the share and ignores them in `xs`,
+    /// which means the inputs are assumed to have them as 0.
+    ///
+    /// Fails with [`DcfError::InvalidFixedBits`] if `leading_fixed_bits` is not less than `8 * N`
+    /// or `f.alpha` has a 1 in them.
+    ///
+    /// The default implementation validates the fixed bits and falls back to the full tree of [`Dcf::gen`],
+    /// which outputs the same for the inputs with the fixed bits as 0, but collapses no level.
+    fn gen_fixed_bits(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+        leading_fixed_bits: usize,
+    ) -> Result<Share<LAMBDA>, DcfError> {
+        check_fixed_bits(f, leading_fixed_bits)?;
+        self.gen(f, s0s, bound)
+    }
+
+    /// Like [`Dcf::gen`], but the per-level correction of `$V_{CW}$` is done by `strategy`.
+    ///
+    /// [`Dcf::gen`] is the same as passing its `bound` as the strategy.
+    /// It is generic, so it is not available on `dyn Dcf`.
+    /// The default implementation fails with [`DcfError::UnsupportedStrategy`],
+    /// for implementations that only support [`BoundState`].
+    fn gen_with_strategy<S>(
+        &self,
+        _f: &CmpFn<N, LAMBDA>,
+        _s0s: [&[u8; LAMBDA]; 2],
+        _strategy: &S,
+    ) -> Result<Share<LAMBDA>, DcfError>
+    where
+        Self: Sized,
+        S: CorrectionStrategy<N, LAMBDA> + ?Sized,
+    {
+        Err(DcfError::UnsupportedStrategy)
+    }
 
     /// `b` is the party. `false` is 0 and `true` is 1.
-    fn eval(&self, b: bool, k: &Share<LAMBDA>, xs: &[&[u8; N]], ys: &mut [&mut [u8; LAMBDA]]);
+    /// `k` is the key of the party, which has only its own seed, e.g., from [`Share::split`],
+    /// or the output of gen, e.g., for the dealer, of which `s0s[b]` is used.
+    ///
+    /// Fails instead of panicking on a malformed key or batch,
+    /// e.g., in a server that must not crash on a key received from the network,
+    /// with the errors of [`Share::check_invariants`] for the key
+    /// or [`DcfError::MismatchedOutputBuffer`] if `ys` does not have 1 output for each of `xs`.
+    /// Nothing is written to `ys` on failure.
+    fn eval(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError>;
+
+    /// [`Dcf::eval`] at the single point `x`, e.g., for points arriving one at a time from a stream
+    fn eval_at(&self, b: bool, k: &Share<LAMBDA>, x: &[u8; N]) -> Result<[u8; LAMBDA], DcfError> {
+        let mut y = [0; LAMBDA];
+        self.eval(b, k, &[x], &mut [&mut y])?;
+        Ok(y)
+    }
+
+    /// [`Dcf::eval`] at every point of the domain, with the output of `x` at `ys[x]`,
+    /// where `x` is the big-endian integer of the input, e.g., to build a lookup table.
+    ///
+    /// `ys` must have exactly `$2^{8N}$` outputs, i.e., `$2^{8N} \lambda$` bytes,
+    /// e.g., 1 MiB for `N = 2` and `LAMBDA = 16`, but 256 MiB for `N = 3`,
+    /// so it is only practical for small `N`.
+    /// Fails with [`DcfError::DomainTooLarge`] if the domain can not be indexed by `usize`,
+    /// or [`DcfError::MismatchedOutputBuffer`] if `ys` has another number of outputs.
+    /// The default implementation evaluates all of the inputs as a batch,
+    /// which also allocates them and walks the tree from the root for each.
+    fn eval_all(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        ys: &mut [[u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_all_outputs::<N>(ys.len())?;
+        let xs: Vec<[u8; N]> = (0..ys.len())
+            .map(|i| {
+                let i_bytes = (i as u64).to_be_bytes();
+                i_bytes[i_bytes.len() - N..].try_into().unwrap()
+            })
+            .collect();
+        let xs: Vec<&[u8; N]> = xs.iter().collect();
+        self.eval(b, k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+    }
+
+    /// Like [`Dcf::eval`] but with a [`PartyKey`], which keeps the secret seed apart from the public part.
+    ///
+    /// Fails the same as [`Dcf::eval`],
+    /// with the errors of [`PublicShare::check_invariants`] for the public part of the key.
+    ///
+    /// The default implementation copies the key into a [`Share`] of the party for [`Dcf::eval`].
+    fn eval_party(
+        &self,
+        b: bool,
+        k: &PartyKey<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_party_inputs::<N, LAMBDA>(k, xs.len(), ys.len())?;
+        let k = Share {
+            s0s: vec![*k.seed],
+            cws: k.public.cws.clone(),
+            cw_np1: k.public.cw_np1,
+            n_bytes: N,
+            prg_check: None,
+        };
+        self.eval(b, &k, xs, ys)
+    }
 }
 
 /// Comparison function.
@@ -45,348 +224,3342 @@ pub struct CmpFn<const N: usize, const LAMBDA: usize> {
     pub beta: [u8; LAMBDA],
 }
 
+impl<const N: usize, const LAMBDA: usize> fmt::Debug for CmpFn<N, LAMBDA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CmpFn")
+            .field("alpha", &Hex(&self.alpha))
+            .field("beta", &Hex(&self.beta))
+            .finish()
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize> CmpFn<N, LAMBDA> {
+    /// Plaintext oracle. What the 2 parties' outputs should be reconstructed to.
+    ///
+    /// Comparison of `[u8; N]` is in the big-endian order, the same as the tree walk.
+    pub fn eval_plain(&self, x: &[u8; N], bound: BoundState) -> [u8; LAMBDA] {
+        let hit = match bound {
+            BoundState::LtBeta => x < &self.alpha,
+            BoundState::GtBeta => x > &self.alpha,
+            BoundState::Leq => x <= &self.alpha,
+            BoundState::Geq => x >= &self.alpha,
+        };
+        if hit {
+            self.beta
+        } else {
+            [0; LAMBDA]
+        }
+    }
+}
+
 /// Pseudorandom generator used in the algorithm.
 ///
 /// `$\{0, 1\}^{\lambda} \rightarrow \{0, 1\}^{2(2\lambda + 1)}$`.
+///
+/// With the `multithread` feature, the single PRG of a [`DcfImpl`] is shared by reference by all of the rayon workers,
+/// which is why it must be `Sync`, and it is never cloned or recreated per task.
+/// A PRG with per-thread state, e.g., a scratch buffer, should keep it in a thread local itself.
 #[cfg(feature = "multithread")]
 pub trait Prg<const LAMBDA: usize>: Sync {
     fn gen(&self, seed: &[u8; LAMBDA]) -> [([u8; LAMBDA], [u8; LAMBDA], bool); 2];
+
+    /// `LAMBDA`s the PRG type is designed for, to pick `LAMBDA` with [`SupportedParams::check`]. `None` if unknown.
+    ///
+    /// They are the limits of the construction, not of the instance,
+    /// which has already been built for its `LAMBDA`, e.g., the PRGs of the `prg` module fail the build for the others.
+    fn supported_params(&self) -> Option<SupportedParams> {
+        None
+    }
 }
 #[cfg(not(feature = "multithread"))]
 pub trait Prg<const LAMBDA: usize> {
     fn gen(&self, seed: &[u8; LAMBDA]) -> [([u8; LAMBDA], [u8; LAMBDA], bool); 2];
+
+    /// `LAMBDA`s the PRG type is designed for, to pick `LAMBDA` with [`SupportedParams::check`]. `None` if unknown.
+    ///
+    /// They are the limits of the construction, not of the instance,
+    /// which has already been built for its `LAMBDA`, e.g., the PRGs of the `prg` module fail the build for the others.
+    fn supported_params(&self) -> Option<SupportedParams> {
+        None
+    }
+}
+
+/// Operating envelope of a [`Prg`], returned by [`Prg::supported_params`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupportedParams {
+    /// The largest supported `LAMBDA`
+    pub max_lambda: usize,
+    /// `LAMBDA` to use without other requirements, e.g., 16 for 128-bit security
+    pub recommended_lambda: usize,
+    /// `LAMBDA` must be a multiple of it, e.g., the block size of the underlying cipher
+    pub lambda_multiple: usize,
+}
+
+impl SupportedParams {
+    /// Fails with [`DcfError::UnsupportedLambda`] if `lambda` is 0, larger than `max_lambda`,
+    /// or not a multiple of `lambda_multiple`
+    pub fn check(&self, lambda: usize) -> Result<(), DcfError> {
+        if lambda == 0 || lambda > self.max_lambda || !lambda.is_multiple_of(self.lambda_multiple) {
+            return Err(DcfError::UnsupportedLambda {
+                lambda,
+                params: *self,
+            });
+        }
+        Ok(())
+    }
 }
 
 /// Implementation of [`Dcf`].
 ///
 /// `$\alpha$` itself is not included, which means `$f(\alpha)$ = 0`.
-pub struct DcfImpl<const N: usize, const LAMBDA: usize, PrgT>
+///
+/// `G` is the group the outputs are reconstructed in, which is [`XorGroup`] by default.
+/// See [`group`] for the choices.
+///
+/// The PRG must output seeds of the same `LAMBDA`, which the `PrgT: Prg<LAMBDA>` bound enforces.
+/// `N` and `LAMBDA` are further checked at compile time when [`DcfImpl::new`] is instantiated:
+///
+/// ```compile_fail
+/// use dcf::{DcfImpl, Prg};
+///
+/// struct NopPrg;
+///
+/// impl Prg<16> for NopPrg {
+///     fn gen(&self, seed: &[u8; 16]) -> [([u8; 16], [u8; 16], bool); 2] {
+///         [(*seed, *seed, false), (*seed, *seed, true)]
+///     }
+/// }
+///
+/// // An empty domain is rejected
+/// let _ = DcfImpl::<0, 16, _>::new(NopPrg);
+/// ```
+pub struct DcfImpl<const N: usize, const LAMBDA: usize, PrgT, G = XorGroup>
 where
     PrgT: Prg<LAMBDA>,
 {
     prg: PrgT,
+    /// [`DcfImpl::prg_check`] computed on first use, and reset by [`DcfImpl::prg_mut`]
+    prg_check: OnceLock<[u8; LAMBDA]>,
+    /// See [`DcfImpl::eval_adaptive`]
+    parallel_threshold: usize,
+    /// Number of batches [`DcfImpl::eval_adaptive`] evaluates serially
+    #[cfg(feature = "metrics")]
+    serial_evals: AtomicU64,
+    /// Number of calls to `prg`
+    #[cfg(feature = "metrics")]
+    prg_calls: AtomicU64,
+    /// `fn() -> G` keeps `DcfImpl` `Send` and `Sync` regardless of `G`
+    group: PhantomData<fn() -> G>,
 }
 
-impl<const N: usize, const LAMBDA: usize, PrgT> DcfImpl<N, LAMBDA, PrgT>
+/// Labels the types of the PRG and the group, since the PRG may hold keys and is not required to be `Debug`
+impl<const N: usize, const LAMBDA: usize, PrgT, G> fmt::Debug for DcfImpl<N, LAMBDA, PrgT, G>
 where
     PrgT: Prg<LAMBDA>,
 {
-    pub fn new(prg: PrgT) -> Self {
-        Self { prg }
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DcfImpl")
+            .field("n", &N)
+            .field("lambda", &LAMBDA)
+            .field("prg", &std::any::type_name::<PrgT>())
+            .field("group", &std::any::type_name::<G>())
+            .field("parallel_threshold", &self.parallel_threshold)
+            .finish()
     }
 }
 
-const IDX_L: usize = 0;
-const IDX_R: usize = 1;
-
-impl<const N: usize, const LAMBDA: usize, PrgT> Dcf<N, LAMBDA> for DcfImpl<N, LAMBDA, PrgT>
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
 where
     PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
 {
-    fn gen(
-        &self,
-        f: &CmpFn<N, LAMBDA>,
-        s0s: [&[u8; LAMBDA]; 2],
-        bound: BoundState,
-    ) -> Share<LAMBDA> {
-        // The bit size of `$\alpha$`
-        let n = 8 * N;
-        let mut v_alpha = [0; LAMBDA];
-        let mut ss = Vec::<[[u8; LAMBDA]; 2]>::with_capacity(n + 1);
-        // Set `$s^{(1)}_0$` and `$s^{(1)}_1$`
-        ss.push([s0s[0].to_owned(), s0s[1].to_owned()]);
-        let mut ts = Vec::<[bool; 2]>::with_capacity(n + 1);
-        // Set `$t^{(0)}_0$` and `$t^{(0)}_1$`
-        ts.push([false, true]);
-        let mut cws = Vec::<Cw<LAMBDA>>::with_capacity(n);
-        for i in 1..n + 1 {
-            let [(s0l, v0l, t0l), (s0r, v0r, t0r)] = self.prg.gen(&ss[i - 1][0]);
-            let [(s1l, v1l, t1l), (s1r, v1r, t1r)] = self.prg.gen(&ss[i - 1][1]);
-            // MSB is required since we index from high to low in arrays
-            let alpha_i = f.alpha.view_bits::<Msb0>()[i - 1];
-            let (keep, lose) = if alpha_i {
-                (IDX_R, IDX_L)
-            } else {
-                (IDX_L, IDX_R)
-            };
-            let s_cw = xor(&[[&s0l, &s0r][lose], [&s1l, &s1r][lose]]);
-            let mut v_cw = xor(&[[&v0l, &v0r][lose], [&v1l, &v1r][lose], &v_alpha]);
-            match bound {
-                BoundState::LtBeta => {
-                    if lose == IDX_L {
-                        xor_inplace(&mut v_cw, &[&f.beta]);
-                    }
-                }
-                BoundState::GtBeta => {
-                    if lose == IDX_R {
-                        xor_inplace(&mut v_cw, &[&f.beta]);
-                    }
-                }
-            }
-            xor_inplace(
-                &mut v_alpha,
-                &[[&v0l, &v0r][keep], [&v1l, &v1r][keep], &v_cw],
-            );
-            let tl_cw = t0l ^ t1l ^ alpha_i ^ true;
-            let tr_cw = t0r ^ t1r ^ alpha_i;
-            let cw = Cw {
-                s: s_cw,
-                v: v_cw,
-                tl: tl_cw,
-                tr: tr_cw,
-            };
-            cws.push(cw);
-            ss.push([
-                xor(&[
-                    [&s0l, &s0r][keep],
-                    if ts[i - 1][0] { &s_cw } else { &[0; LAMBDA] },
-                ]),
-                xor(&[
-                    [&s1l, &s1r][keep],
-                    if ts[i - 1][1] { &s_cw } else { &[0; LAMBDA] },
-                ]),
-            ]);
-            ts.push([
-                [t0l, t0r][keep] ^ (ts[i - 1][0] & [tl_cw, tr_cw][keep]),
-                [t1l, t1r][keep] ^ (ts[i - 1][1] & [tl_cw, tr_cw][keep]),
-            ]);
-        }
-        assert_eq!((ss.len(), ts.len(), cws.len()), (n + 1, n + 1, n));
-        let cw_np1 = xor(&[&ss[n][0], &ss[n][1], &v_alpha]);
-        Share {
-            s0s: vec![s0s[0].to_owned(), s0s[1].to_owned()],
-            cws,
-            cw_np1,
-        }
-    }
+    /// Fails the build for degenerate parameters.
+    /// `N = 0` leaves no tree to walk and `LAMBDA = 0` leaves no room for seeds or outputs.
+    const PARAMS_OK: () = {
+        assert!(N > 0, "N, the byte size of the domain, must be positive");
+        assert!(
+            LAMBDA > 0,
+            "LAMBDA, the byte size of seeds and outputs, must be positive"
+        );
+    };
 
-    fn eval(&self, b: bool, k: &Share<LAMBDA>, xs: &[&[u8; N]], ys: &mut [&mut [u8; LAMBDA]]) {
-        let n = k.cws.len();
-        assert_eq!(n, N * 8);
-        let f = |x: &[u8; N], y: &mut [u8; LAMBDA]| {
-            let mut ss = Vec::<[u8; LAMBDA]>::with_capacity(n + 1);
-            ss.push(k.s0s[0].to_owned());
-            let mut ts = Vec::<bool>::with_capacity(n + 1);
-            ts.push(b);
-            y.fill(0);
-            let v = y;
-            for i in 1..n + 1 {
-                let cw = &k.cws[i - 1];
-                // `*_hat` before in-place xor
-                let [(mut sl, vl_hat, mut tl), (mut sr, vr_hat, mut tr)] = self.prg.gen(&ss[i - 1]);
-                xor_inplace(&mut sl, &[if ts[i - 1] { &cw.s } else { &[0; LAMBDA] }]);
-                xor_inplace(&mut sr, &[if ts[i - 1] { &cw.s } else { &[0; LAMBDA] }]);
-                tl ^= ts[i - 1] & cw.tl;
-                tr ^= ts[i - 1] & cw.tr;
-                if x.view_bits::<Msb0>()[i - 1] {
-                    xor_inplace(v, &[&vr_hat, if ts[i - 1] { &cw.v } else { &[0; LAMBDA] }]);
-                    ss.push(sr);
-                    ts.push(tr);
-                } else {
-                    xor_inplace(v, &[&vl_hat, if ts[i - 1] { &cw.v } else { &[0; LAMBDA] }]);
-                    ss.push(sl);
-                    ts.push(tl);
-                }
-            }
-            assert_eq!((ss.len(), ts.len()), (n + 1, n + 1));
-            xor_inplace(v, &[&ss[n], if ts[n] { &k.cw_np1 } else { &[0; LAMBDA] }]);
-        };
-        #[cfg(feature = "multithread")]
-        {
-            xs.par_iter()
-                .zip(ys.par_iter_mut())
-                .for_each(|(x, y)| f(x, y));
-        }
-        #[cfg(not(feature = "multithread"))]
-        {
-            xs.iter().zip(ys.iter_mut()).for_each(|(x, y)| f(x, y));
+    /// Fails the build of [`DcfImpl::derive_seed`] if `key_index` does not fit in a seed
+    const DERIVE_SEED_OK: () = assert!(
+        LAMBDA >= 8,
+        "LAMBDA, the byte size of seeds, must be at least 8 to derive seeds"
+    );
+
+    pub fn new(prg: PrgT) -> Self {
+        let () = Self::PARAMS_OK;
+        let parallel_threshold = std::env::var(PARALLEL_THRESHOLD_ENV)
+            .ok()
+            .and_then(|threshold| threshold.parse().ok())
+            .unwrap_or(DEFAULT_PARALLEL_THRESHOLD);
+        Self {
+            prg,
+            prg_check: OnceLock::new(),
+            parallel_threshold,
+            #[cfg(feature = "metrics")]
+            serial_evals: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            prg_calls: AtomicU64::new(0),
+            group: PhantomData,
         }
     }
-}
 
-/// `Cw`. Correclation word.
-#[derive(Clone)]
-pub struct Cw<const LAMBDA: usize> {
-    pub s: [u8; LAMBDA],
-    pub v: [u8; LAMBDA],
-    pub tl: bool,
-    pub tr: bool,
-}
+    /// See [`DcfImpl::eval_adaptive`]
+    pub fn parallel_threshold(&self) -> usize {
+        self.parallel_threshold
+    }
 
+    pub fn set_parallel_threshold(&mut self, threshold: usize) {
+        self.parallel_threshold = threshold;
+    }
 
-impl<const LAMBDA: usize> Serialize for Cw<LAMBDA> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: Serializer,
-    {
-        let mut s = serializer.serialize_struct("Cw", 4)?;
-        s.serialize_field("s", &self.s.to_vec())?;
-        s.serialize_field("v", &self.v.to_vec())?;
-        s.serialize_field("tl", &self.tl)?;
-        s.serialize_field("tr", &self.tr)?;
-        s.end()
+    /// See [`Prg::supported_params`]
+    pub fn supported_params(&self) -> Option<SupportedParams> {
+        self.prg.supported_params()
     }
-}
 
-impl<const LAMBDA: usize> Deserialize<'static> for Cw<LAMBDA> {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: Deserializer<'static>,
-    {
-        struct CwVisitor<const LAMBDA: usize>;
+    /// Reconstructs `$f(x)$` from the output `y0` of party 0 and `y1` of party 1 at the same `x`,
+    /// by [`Combine::combine`] of `G`
+    pub fn reconstruct(&self, y0: &[u8; LAMBDA], y1: &[u8; LAMBDA]) -> [u8; LAMBDA] {
+        <G as Combine<LAMBDA>>::combine(y0, y1)
+    }
 
-        impl<const LAMBDA: usize> Visitor<'static> for CwVisitor<LAMBDA> {
-            type Value = Cw<LAMBDA>;
+    /// Mutable access to the PRG, e.g., to reseed it in place with `Aes256HirosePrg::reseed`.
+    ///
+    /// Keys generated before changing the PRG must be regenerated.
+    /// Eval of them fails with [`DcfError::PrgMismatch`] if the PRG outputs differ after the change.
+    pub fn prg_mut(&mut self) -> &mut PrgT {
+        self.prg_check = OnceLock::new();
+        &mut self.prg
+    }
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct Cw")
-            }
+    /// Number of PRG calls made by `gen` and `eval` since the creation or the last reset
+    #[cfg(feature = "metrics")]
+    pub fn prg_call_count(&self) -> u64 {
+        self.prg_calls.load(Ordering::Relaxed)
+    }
 
-            fn visit_seq<V>(self, mut seq: V) -> Result<Cw<LAMBDA>, V::Error>
-                where
-                    V: SeqAccess<'static>,
-            {
-                let s_vec: Vec<u8> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
-                let mut s = [0u8; LAMBDA];
-                s.copy_from_slice(&s_vec);
+    #[cfg(feature = "metrics")]
+    pub fn reset_prg_call_count(&self) {
+        self.prg_calls.store(0, Ordering::Relaxed);
+    }
 
-                let v_vec: Vec<u8> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
-                let mut v = [0u8; LAMBDA];
-                v.copy_from_slice(&v_vec);
+    /// Number of batches [`DcfImpl::eval_adaptive`] has evaluated serially since the creation
+    #[cfg(feature = "metrics")]
+    pub fn serial_eval_count(&self) -> u64 {
+        self.serial_evals.load(Ordering::Relaxed)
+    }
 
-                let tl: bool = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
-                let tr: bool = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(3, &self))?;
+    /// All PRG calls go through here so that they can be counted with the `metrics` feature
+    #[inline]
+    fn prg_gen(&self, seed: &[u8; LAMBDA]) -> [([u8; LAMBDA], [u8; LAMBDA], bool); 2] {
+        #[cfg(feature = "metrics")]
+        self.prg_calls.fetch_add(1, Ordering::Relaxed);
+        self.prg.gen(seed)
+    }
 
-                Ok(Cw { s, v, tl, tr })
-            }
-        }
+    /// Gen with `$\beta$` given as 2 shares of the group `G`.
+    ///
+    /// The outputs are reconstructed to `beta_share0 + beta_share1` where `$f(x) = \beta$`.
+    /// The keys are the same as the ones of `gen` with the sum as `$\beta$`,
+    /// so like `gen`, neither of the parties evaluating them learns `$\beta$`.
+    /// The dealer calling this still sees both shares.
+    pub fn gen_shared_beta(
+        &self,
+        alpha: &[u8; N],
+        beta_share0: &[u8; LAMBDA],
+        beta_share1: &[u8; LAMBDA],
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> Share<LAMBDA> {
+        let mut beta = *beta_share0;
+        G::add(&mut beta, beta_share1);
+        let f = CmpFn {
+            alpha: *alpha,
+            beta,
+        };
+        self.gen_parts(&f, s0s, &bound, 0)
+    }
 
-        const FIELDS: &'static [&'static str] = &["s", "v", "tl", "tr"];
-        deserializer.deserialize_struct("Cw", FIELDS, CwVisitor)
+    /// Derives the `$s^{(0)}_b$` of the key `key_index` from the long-term seed `master` of the party `b`.
+    ///
+    /// The derived seed is the left seed of the PRG output on `master` XOR the big-endian `key_index`
+    /// (into the last 8 bytes).
+    /// The seeds of different indices are pseudorandom and independent of each other
+    /// as long as the PRG is a PRF keyed by `master`,
+    /// which holds for `Aes256HirosePrg` when AES is modeled as an ideal cipher,
+    /// but not for an arbitrary [`Prg`] that is only length-doubling.
+    /// `master` must be sampled uniformly and kept by the party,
+    /// and each `key_index` must only be used once per master seed.
+    ///
+    /// `LAMBDA >= 8` is required, which is checked at build time.
+    pub fn derive_seed(&self, master: &[u8; LAMBDA], key_index: u64) -> [u8; LAMBDA] {
+        let () = Self::DERIVE_SEED_OK;
+        let mut seed = *master;
+        seed[LAMBDA - 8..]
+            .iter_mut()
+            .zip(key_index.to_be_bytes())
+            .for_each(|(s, i)| *s ^= i);
+        self.prg_gen(&seed)[IDX_L].0
     }
-}
 
-/// `k`.
-///
-/// `cws` and `cw_np1` is shared by the 2 parties.
-/// Only `s0s[0]` is different.
-#[serde_as]
-#[derive(Clone)]
-pub struct Share<const LAMBDA: usize> {
-    /// For the output of `gen`, its length is 2.
-    /// For the input of `eval`, the first one is used.
-    pub s0s: Vec<[u8; LAMBDA]>,
-    /// The length of `cws` must be `n = 8 * N`
-    pub cws: Vec<Cw<LAMBDA>>,
-    /// `$CW^{(n + 1)}$`
-    pub cw_np1: [u8; LAMBDA],
-}
+    /// Gen with `s0s` derived by [`DcfImpl::derive_seed`] from the master seeds of the 2 parties.
+    ///
+    /// So the master seeds only need to be distributed once,
+    /// and the parties can derive `s0s` of the key themselves.
+    pub fn gen_derived_seeds(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        master0: &[u8; LAMBDA],
+        master1: &[u8; LAMBDA],
+        key_index: u64,
+        bound: BoundState,
+    ) -> Share<LAMBDA> {
+        let s0 = self.derive_seed(master0, key_index);
+        let s1 = self.derive_seed(master1, key_index);
+        self.gen_parts(f, [&s0, &s1], &bound, 0)
+    }
 
-impl<const LAMBDA: usize> Serialize for Share<LAMBDA> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: Serializer,
-    {
-        let mut s = serializer.serialize_struct("Share", 3)?;
-        let s0s_as_vecs: Vec<Vec<u8>> = self.s0s.iter().map(|arr| arr.to_vec()).collect();
-        s.serialize_field("s0s", &s0s_as_vecs)?;
-        s.serialize_field("cws", &self.cws)?;
-        s.serialize_field("cw_np1", &self.cw_np1.to_vec())?;
-        s.end()
+    /// Like [`Dcf::gen`] but outputs the keys of party 0 and party 1 already serialized by [`Share::to_bytes`],
+    /// each with only its own seed, e.g., for a dealer sending them right away.
+    ///
+    /// Panics the same as [`Share::split`].
+    pub fn gen_serialized(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let [k0, k1] = self.gen_parts(f, s0s, &bound, 0).split();
+        (k0.to_bytes(), k1.to_bytes())
     }
-}
 
-impl<const LAMBDA: usize> Deserialize<'static> for Share<LAMBDA> {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: Deserializer<'static>,
-    {
-        struct ShareVisitor<const LAMBDA: usize>;
+    /// Like [`Dcf::eval`] but with `xs` as big-endian integers of at most `N` bytes.
+    ///
+    /// Each of `xs` is zero-extended in the high bytes to `N` bytes,
+    /// e.g., `[0x12, 0x34]` is `[0, 0, 0x12, 0x34]` with `N = 4`, which keeps its order in the tree.
+    /// All of `xs` are checked before any of `ys` is written.
+    pub fn eval_be_ints(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        let xs = xs
+            .iter()
+            .enumerate()
+            .map(|(index, x)| {
+                if x.len() > N {
+                    return Err(DcfError::InputTooLong {
+                        index,
+                        len: x.len(),
+                        max: N,
+                    });
+                }
+                let mut x_full = [0; N];
+                x_full[N - x.len()..].copy_from_slice(x);
+                Ok(x_full)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.eval(b, k, &xs.iter().collect::<Vec<_>>(), ys)
+    }
 
-        impl<const LAMBDA: usize> Visitor<'static> for ShareVisitor<LAMBDA> {
-            type Value = Share<LAMBDA>;
+    /// Fingerprint of the PRG, i.e., its outputs on fixed inputs.
+    ///
+    /// Gen stores it in [`Share::prg_check`], and eval fails with [`DcfError::PrgMismatch`]
+    /// if it differs from the one of the evaluating party.
+    /// Since eval with a PRG different from the one of gen silently outputs garbage,
+    /// this catches misconfigurations like PRGs built with different keys.
+    /// It is not secret and says nothing about the keys.
+    /// Every output of the PRG on its inputs is covered, e.g., the one of each AES key of `Aes256HirosePrg`.
+    /// It is computed once and cached, not counted in the PRG calls of the `metrics` feature.
+    pub fn prg_check(&self) -> [u8; LAMBDA] {
+        *self.prg_check.get_or_init(|| prg_fingerprint(&self.prg))
+    }
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct Share")
-            }
+    /// [`Share::party_seed`] also checking [`Share::prg_check`] like [`Dcf::eval`],
+    /// for the eval paths that panic on malformed keys
+    fn party_seed<'k>(&self, b: bool, k: &'k Share<LAMBDA>) -> &'k [u8; LAMBDA] {
+        assert!(
+            check_prg(k, &self.prg_check()).is_ok(),
+            "the key is generated with another PRG"
+        );
+        k.party_seed(b)
+    }
 
-            fn visit_seq<V>(self, mut seq: V) -> Result<Share<LAMBDA>, V::Error>
-                where
-                    V: SeqAccess<'static>,
-            {
-                let s0s_as_vecs: Vec<Vec<u8>> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
-                let s0s: Vec<[u8; LAMBDA]> = s0s_as_vecs.into_iter().map(|v| {
-                    let mut arr = [0u8; LAMBDA];
-                    arr.copy_from_slice(&v);
-                    arr
-                }).collect();
-
-                let cws: Vec<Cw<LAMBDA>> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
-                let cw_np1_vec: Vec<u8> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
-                let mut cw_np1 = [0u8; LAMBDA];
-                cw_np1.copy_from_slice(&cw_np1_vec);
-
-                Ok(Share {
-                    s0s,
-                    cws,
-                    cw_np1,
-                })
-            }
+    /// Like [`Dcf::eval`] but fails with [`DcfError::PrgMismatch`] before eval
+    /// if `prg_check` from the dealer differs from [`DcfImpl::prg_check`] of `self`,
+    /// e.g., for keys without [`Share::prg_check`]
+    pub fn eval_checked(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        prg_check: &[u8; LAMBDA],
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        if &self.prg_check() != prg_check {
+            return Err(DcfError::PrgMismatch);
         }
-
-        const FIELDS: &'static [&'static str] = &["s0s", "cws", "cw_np1"];
-        deserializer.deserialize_struct("Share", FIELDS, ShareVisitor)
+        self.eval(b, k, xs, ys)
     }
-}
 
-pub enum BoundState {
-    /// `$f(x) = \beta$` iff. `$x < \alpha$`.
+    /// Like [`Dcf::eval`] but adds the pre-shared mask `masks[i]` of the party to the output at `xs[i]` in `G`,
+    /// so that the output of the party alone is blinded by the mask.
     ///
-    /// This is the preference in the paper.
-    LtBeta,
-    /// `$f(x) = \beta$` iff. `$x > \alpha$`
-    GtBeta,
-}
+    /// The masks only cancel when the masks of party 0 plus the ones of party 1 at the same index are 0 in `G`,
+    /// i.e., the same with [`XorGroup`], or negations of each other with [`ArithGroup`].
+    /// Otherwise the reconstruction is off by the sum.
+    /// Fails with [`DcfError::InvalidInputCount`] if `masks` does not have 1 mask for each of `xs`.
+    pub fn eval_masked_output(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        masks: &[&[u8; LAMBDA]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        if masks.len() != xs.len() {
+            return Err(DcfError::InvalidInputCount {
+                expected: xs.len(),
+                got: masks.len(),
+            });
+        }
+        self.eval(b, k, xs, ys)?;
+        ys.iter_mut()
+            .zip(masks.iter())
+            .for_each(|(y, mask)| G::add(y, mask));
+        Ok(())
+    }
 
-#[cfg(all(test, feature = "prg"))]
-mod tests {
-    use super::*;
+    /// Like [`Dcf::eval`] with `count` copies of `x` as `xs`,
+    /// but evaluates `x` only once and copies the output into all of `ys`
+    pub fn eval_broadcast(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        x: &[u8; N],
+        count: usize,
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_output_count(count, ys.len())?;
+        let y = self.eval_at(b, k, x)?;
+        ys.iter_mut().for_each(|y_i| **y_i = y);
+        Ok(())
+    }
 
-    use rand::{thread_rng, Rng};
+    /// Like [`Dcf::eval`] but only in parallel when the batch is large enough to pay off the overhead.
+    ///
+    /// The work is estimated as `xs.len() * k.cws.len() * LAMBDA`,
+    /// and the batch is evaluated serially if it is below [`DcfImpl::parallel_threshold`].
+    /// The threshold defaults to [`DEFAULT_PARALLEL_THRESHOLD`],
+    /// or the env var `DCF_PARALLEL_THRESHOLD` if set when the [`DcfImpl`] is created.
+    /// Without the `multithread` feature it is always serial, the same as [`Dcf::eval`].
+    /// Fails the same as [`Dcf::eval`].
+    pub fn eval_adaptive(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), ys.len())?;
+        let work = xs.len().saturating_mul(k.cws.len()).saturating_mul(LAMBDA);
+        let parallel = work >= self.parallel_threshold;
+        #[cfg(feature = "metrics")]
+        if !parallel {
+            self.serial_evals.fetch_add(1, Ordering::Relaxed);
+        }
+        self.eval_parts_then(
+            b,
+            k.party_seed(b),
+            &k.cws,
+            &k.cw_np1,
+            xs,
+            ys,
+            parallel,
+            |_| (),
+        );
+        Ok(())
+    }
 
-    use crate::prg::Aes256HirosePrg;
+    /// Evaluates all of the inputs in the domain of the key in order,
+    /// calling `visit` with the index and the output of each, without allocating the outputs.
+    ///
+    /// The domain has `$2^n$` inputs where `n` is the number of the levels of the key,
+    /// i.e., excluding the leading fixed bits of [`Dcf::gen_fixed_bits`],
+    /// and the index is the input as a big-endian integer.
+    /// Walks the tree depth-first, so each node is expanded only once.
+    /// Panics if the domain is larger than `$2^{128}$` or `k` is malformed.
+    pub fn eval_full_visit<F>(&self, b: bool, k: &Share<LAMBDA>, mut visit: F)
+    where
+        F: FnMut(u128, &[u8; LAMBDA]),
+    {
+        let n = k.cws.len();
+        assert!(n > 0 && n <= N * 8);
+        assert!(n <= 128, "the domain indexes overflow u128");
+        self.visit_node(
+            b,
+            self.party_seed(b, k),
+            b,
+            &[0; LAMBDA],
+            &k.cws,
+            &k.cw_np1,
+            0,
+            &(0..=u128::MAX),
+            &mut visit,
+        );
+    }
 
-    const KEYS: [&[u8; 32]; 2] = [
-        b"j9\x1b_\xb3X\xf33\xacW\x15\x1b\x0812K\xb3I\xb9\x90r\x1cN\xb5\xee9W\xd3\xbb@\xc6d",
-        b"\x9b\x15\xc8\x0f\xb7\xbc!q\x9e\x89\xb8\xf7\x0e\xa0S\x9dN\xfa\x0c;\x16\xe4\x98\x82b\xfcdy\xb5\x8c{\xc2",
-    ];
-    const ALPHAS: &[&[u8; 16]] = &[
-        b"K\xa9W\xf5\xdd\x05\xe9\xfc?\x04\xf6\xfbUo\xa8C",
-        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csU",
-        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csV",
-        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csW",
-        b"\xef\x96\x97\xd7\x8f\x8a\xa4AP\n\xb35\xb5k\xff\x97",
-    ];
-    const BETA: &[u8; 16] = b"\x03\x11\x97\x12C\x8a\xe9#\x81\xa8\xde\xa8\x8f \xc0\xbb";
+    /// The outputs of all of the inputs in the domain of the key, in the order of [`DcfImpl::eval_full_visit`],
+    /// which walks the tree once, so it takes `$O(2^n)$` PRG calls other than `$O(2^n n)$` of [`Dcf::eval`] on each input.
+    ///
+    /// Fails with the errors of [`Dcf::eval`] for the key,
+    /// or [`DcfError::DomainTooLarge`] if the domain of the key has more than [`MAX_FULL_DOMAIN_BITS`] bits.
+    pub fn eval_full(&self, b: bool, k: &Share<LAMBDA>) -> Result<Vec<[u8; LAMBDA]>, DcfError> {
+        check_party_share::<N, LAMBDA>(k, &self.prg_check())?;
+        let bits = k.cws.len();
+        if bits > MAX_FULL_DOMAIN_BITS {
+            return Err(DcfError::DomainTooLarge {
+                bits,
+                max: MAX_FULL_DOMAIN_BITS,
+            });
+        }
+        let mut ys = vec![[0; LAMBDA]; 1 << bits];
+        self.eval_full_visit(b, k, |i, y| ys[i as usize] = *y);
+        Ok(ys)
+    }
 
-    #[test]
-    fn test_dcf_gen_then_eval_ok() {
-        let prg = Aes256HirosePrg::new(KEYS);
-        let dcf = DcfImpl::<16, 16, _>::new(prg);
-        let s0s: [[u8; 16]; 2] = thread_rng().gen();
-        let f = CmpFn {
-            alpha: ALPHAS[2].to_owned(),
-            beta: BETA.to_owned(),
+    /// Recursion of [`DcfImpl::eval_full_visit`] from the node of `(s, t)` with the accumulated `v`.
+    ///
+    /// `cws` are the levels below the node, and `index` is the path to the node.
+    /// Subtrees without any index in `range` are skipped.
+    #[allow(clippy::too_many_arguments)]
+    fn visit_node<F>(
+        &self,
+        b: bool,
+        s: &[u8; LAMBDA],
+        t: bool,
+        v: &[u8; LAMBDA],
+        cws: &[Cw<LAMBDA>],
+        cw_np1: &[u8; LAMBDA],
+        index: u128,
+        range: &RangeInclusive<u128>,
+        visit: &mut F,
+    ) where
+        F: FnMut(u128, &[u8; LAMBDA]),
+    {
+        // The first and the last index under the node
+        let (first, last) = match cws.len() {
+            0 => (index, index),
+            128 => (0, u128::MAX),
+            depth => (
+                index << depth,
+                (index << depth) | (u128::MAX >> (128 - depth)),
+            ),
         };
-        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
-        let mut k0 = k.clone();
-        k0.s0s = vec![k0.s0s[0]];
-        let mut k1 = k.clone();
-        k1.s0s = vec![k1.s0s[1]];
-        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
-        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
-        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>());
-        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>());
+        if last < *range.start() || first > *range.end() {
+            return;
+        }
+        let Some((cw, cws_below)) = cws.split_first() else {
+            let mut y = *v;
+            self.eval_leaf(b, s, t, cw_np1, &mut y);
+            visit(index, &y);
+            return;
+        };
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut expanded = self.prg_gen(s);
+        for x_i in [false, true] {
+            let mut v_child = *v;
+            #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+            let (mut s_child, t_child) =
+                self.eval_level_expanded(expanded, t, cw, x_i, &mut v_child);
+            let index_child = (index << 1) | x_i as u128;
+            self.visit_node(
+                b,
+                &s_child,
+                t_child,
+                &v_child,
+                cws_below,
+                cw_np1,
+                index_child,
+                range,
+                visit,
+            );
+            #[cfg(feature = "zeroize")]
+            s_child.zeroize();
+        }
+        #[cfg(feature = "zeroize")]
+        zeroize_expanded(&mut expanded);
+    }
+
+    /// Shared by [`Dcf::gen_fixed_bits`] and [`Dcf::gen_with_strategy`]
+    fn gen_parts<S>(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        strategy: &S,
+        leading_fixed_bits: usize,
+    ) -> Share<LAMBDA>
+    where
+        S: CorrectionStrategy<N, LAMBDA> + ?Sized,
+    {
+        assert!(leading_fixed_bits < 8 * N);
+        assert!(f.alpha.view_bits::<Msb0>()[..leading_fixed_bits].not_any());
+        // The bit size of `$\alpha$` without the fixed bits
+        let n = 8 * N - leading_fixed_bits;
+        // Only the state of the current level is kept, since each level only reads the one before it
+        let mut state = GenState::new(s0s);
+        let mut cws = Vec::<Cw<LAMBDA>>::with_capacity(n);
+        for i in 1..n + 1 {
+            cws.push(self.gen_level(f, strategy, leading_fixed_bits + i - 1, &mut state));
+        }
+        #[cfg(all(test, feature = "prg"))]
+        if test_fixtures::CORRUPT_GEN_STATE.get() {
+            cws.pop();
+        }
+        // Guaranteed by the loop, so only checked in debug builds
+        debug_assert_eq!(cws.len(), n);
+        let cw_np1 = Self::gen_leaf(f, strategy, &state);
+        #[cfg(feature = "zeroize")]
+        state.zeroize();
+        Share {
+            s0s: vec![s0s[0].to_owned(), s0s[1].to_owned()],
+            cws,
+            cw_np1,
+            n_bytes: N,
+            prg_check: Some(self.prg_check()),
+        }
+    }
+
+    /// Gens the correlation word of the level of the bit `i` of `$\alpha$`, and updates `state` to the next level.
+    ///
+    /// Shared by [`DcfImpl::gen_parts`] and [`DcfImpl::gen_streaming`].
+    fn gen_level<S>(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        strategy: &S,
+        i: usize,
+        state: &mut GenState<LAMBDA>,
+    ) -> Cw<LAMBDA>
+    where
+        S: CorrectionStrategy<N, LAMBDA> + ?Sized,
+    {
+        let [(s0l, v0l, t0l), (s0r, v0r, t0r)] = self.prg_gen(&state.ss[0]);
+        let [(s1l, v1l, t1l), (s1r, v1r, t1r)] = self.prg_gen(&state.ss[1]);
+        // MSB is required since we index from high to low in arrays
+        let alpha_i = f.alpha.view_bits::<Msb0>()[i];
+        let (keep, lose) = if alpha_i {
+            (IDX_R, IDX_L)
+        } else {
+            (IDX_L, IDX_R)
+        };
+        let s_cw = xor(&[[&s0l, &s0r][lose], [&s1l, &s1r][lose]]);
+        // `$(-1)^{t^{(i - 1)}_1}$`
+        let sign = state.ts[1];
+        let mut v_cw = [v1l, v1r][lose];
+        G::sub(&mut v_cw, &[v0l, v0r][lose]);
+        G::sub(&mut v_cw, &state.v_alpha);
+        if let Some(v) = strategy.v_cw_correction(f, i, alpha_i) {
+            G::add(&mut v_cw, &v);
+        }
+        G::neg_if(&mut v_cw, sign);
+        G::sub(&mut state.v_alpha, &[v1l, v1r][keep]);
+        G::add(&mut state.v_alpha, &[v0l, v0r][keep]);
+        let mut v_cw_signed = v_cw;
+        G::neg_if(&mut v_cw_signed, sign);
+        G::add(&mut state.v_alpha, &v_cw_signed);
+        let tl_cw = t0l ^ t1l ^ alpha_i ^ true;
+        let tr_cw = t0r ^ t1r ^ alpha_i;
+        let cw = Cw {
+            s: s_cw,
+            v: v_cw,
+            tl: tl_cw,
+            tr: tr_cw,
+        };
+        state.ss = [
+            xor(&[
+                [&s0l, &s0r][keep],
+                if state.ts[0] { &s_cw } else { &[0; LAMBDA] },
+            ]),
+            xor(&[
+                [&s1l, &s1r][keep],
+                if state.ts[1] { &s_cw } else { &[0; LAMBDA] },
+            ]),
+        ];
+        state.ts = [
+            [t0l, t0r][keep] ^ (state.ts[0] & [tl_cw, tr_cw][keep]),
+            [t1l, t1r][keep] ^ (state.ts[1] & [tl_cw, tr_cw][keep]),
+        ];
+        cw
+    }
+
+    /// `$CW^{(n + 1)}$` from the state after the last level
+    fn gen_leaf<S>(f: &CmpFn<N, LAMBDA>, strategy: &S, state: &GenState<LAMBDA>) -> [u8; LAMBDA]
+    where
+        S: CorrectionStrategy<N, LAMBDA> + ?Sized,
+    {
+        let mut cw_np1 = state.ss[1];
+        G::sub(&mut cw_np1, &state.ss[0]);
+        G::sub(&mut cw_np1, &state.v_alpha);
+        if let Some(v) = strategy.leaf_correction(f) {
+            G::add(&mut cw_np1, &v);
+        }
+        G::neg_if(&mut cw_np1, state.ts[1]);
+        cw_np1
+    }
+
+    /// Shared by [`Dcf::eval`] and [`Dcf::eval_party`].
+    ///
+    /// `s0` is the seed of the party. `cws` and `cw_np1` are the public part of the key.
+    fn eval_parts(
+        &self,
+        b: bool,
+        s0: &[u8; LAMBDA],
+        cws: &[Cw<LAMBDA>],
+        cw_np1: &[u8; LAMBDA],
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) {
+        self.eval_parts_then(b, s0, cws, cw_np1, xs, ys, true, |_| ());
+    }
+
+    /// [`DcfImpl::eval_parts`] with `post` applied to each output right after it is finished,
+    /// which avoids a second pass over `ys`.
+    ///
+    /// The inputs are evaluated in parallel with the `multithread` feature only if `parallel`.
+    #[allow(clippy::too_many_arguments)]
+    fn eval_parts_then<F>(
+        &self,
+        b: bool,
+        s0: &[u8; LAMBDA],
+        cws: &[Cw<LAMBDA>],
+        cw_np1: &[u8; LAMBDA],
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+        parallel: bool,
+        post: F,
+    ) where
+        F: Fn(&mut [u8; LAMBDA]) + Sync + Send,
+    {
+        let n = cws.len();
+        assert!(n > 0 && n <= N * 8);
+        // All inputs start from the same root, so its expansion is shared by the batch
+        #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+        let mut root = self.prg_gen(s0);
+        let f = |x: &[u8; N], y: &mut [u8; LAMBDA]| {
+            self.eval_point(b, s0, &root, cws, cw_np1, x, y);
+            post(y);
+        };
+        if parallel {
+            for_each_zip(xs, ys, |x, y| f(x, y));
+        } else {
+            xs.iter().zip(ys.iter_mut()).for_each(|(x, y)| f(x, y));
+        }
+        #[cfg(feature = "zeroize")]
+        zeroize_expanded(&mut root);
+    }
+
+    /// Walks the path of `x` from the root, whose PRG output is `root`, and writes the output of it to `y`.
+    ///
+    /// Each level only reads `(s, t)` of the level before it,
+    /// so only the one of the current node is kept, without allocation.
+    #[allow(clippy::too_many_arguments)]
+    fn eval_point(
+        &self,
+        b: bool,
+        s0: &[u8; LAMBDA],
+        root: &[([u8; LAMBDA], [u8; LAMBDA], bool); 2],
+        cws: &[Cw<LAMBDA>],
+        cw_np1: &[u8; LAMBDA],
+        x: &[u8; N],
+        y: &mut [u8; LAMBDA],
+    ) {
+        // Leading bits collapsed by `gen_fixed_bits`
+        let fixed = N * 8 - cws.len();
+        let mut s = s0.to_owned();
+        let mut t = b;
+        y.fill(0);
+        for (i, cw) in cws.iter().enumerate() {
+            let x_i = x.view_bits::<Msb0>()[fixed + i];
+            #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+            let mut expanded = if i == 0 { *root } else { self.prg_gen(&s) };
+            (s, t) = self.eval_level_expanded(expanded, t, cw, x_i, y);
+            #[cfg(feature = "zeroize")]
+            zeroize_expanded(&mut expanded);
+        }
+        self.eval_leaf(b, &s, t, cw_np1, y);
+        #[cfg(feature = "zeroize")]
+        s.zeroize();
+    }
+
+    /// One level of the tree walk in eval.
+    ///
+    /// Goes from the node of `(s, t)` to its child at the side `x_i`,
+    /// adds the (not yet signed) output of the level to `v`, and returns `(s, t)` of the child.
+    fn eval_level(
+        &self,
+        s: &[u8; LAMBDA],
+        t: bool,
+        cw: &Cw<LAMBDA>,
+        x_i: bool,
+        v: &mut [u8; LAMBDA],
+    ) -> ([u8; LAMBDA], bool) {
+        self.eval_level_expanded(self.prg_gen(s), t, cw, x_i, v)
+    }
+
+    /// [`DcfImpl::eval_level`] with the PRG output of `s` already computed
+    fn eval_level_expanded(
+        &self,
+        expanded: [([u8; LAMBDA], [u8; LAMBDA], bool); 2],
+        t: bool,
+        cw: &Cw<LAMBDA>,
+        x_i: bool,
+        v: &mut [u8; LAMBDA],
+    ) -> ([u8; LAMBDA], bool) {
+        // `*_hat` before in-place xor
+        let [(mut sl, vl_hat, mut tl), (mut sr, vr_hat, mut tr)] = expanded;
+        xor_inplace(&mut sl, &[if t { &cw.s } else { &[0; LAMBDA] }]);
+        xor_inplace(&mut sr, &[if t { &cw.s } else { &[0; LAMBDA] }]);
+        tl ^= t & cw.tl;
+        tr ^= t & cw.tr;
+        G::add(v, if x_i { &vr_hat } else { &vl_hat });
+        if t {
+            G::add(v, &cw.v);
+        }
+        if x_i {
+            (sr, tr)
+        } else {
+            (sl, tl)
+        }
+    }
+
+    /// The leaf of the tree walk in eval, which finishes `v` as the output of the party `b`
+    fn eval_leaf(
+        &self,
+        b: bool,
+        s: &[u8; LAMBDA],
+        t: bool,
+        cw_np1: &[u8; LAMBDA],
+        v: &mut [u8; LAMBDA],
+    ) {
+        G::add(v, s);
+        if t {
+            G::add(v, cw_np1);
+        }
+        // `$(-1)^b$`
+        G::neg_if(v, b);
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Ring<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but outputs the party's share of `$f(x) c + d$`, in the same pass.
+    ///
+    /// `c` is public and the same for the 2 parties.
+    /// `d_share` is the party's share of `d`, i.e., `d_share` of party 0 plus the one of party 1 is `d`.
+    /// Use `d` itself for one party and 0 for the other when `d` is public.
+    /// Mostly meaningful with [`ArithGroup`].
+    /// Fails the same as [`Dcf::eval`].
+    pub fn eval_affine(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        c: &[u8; LAMBDA],
+        d_share: &[u8; LAMBDA],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), ys.len())?;
+        self.eval_parts_then(b, k.party_seed(b), &k.cws, &k.cw_np1, xs, ys, true, |y| {
+            G::mul(y, c);
+            G::add(y, d_share);
+        });
+        Ok(())
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT> DcfImpl<N, LAMBDA, PrgT, Gf256LaneGroup>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    /// Like [`Dcf::eval`] but multiplies each output byte by the `$GF(2^8)$` constant of its lane in `scales`.
+    ///
+    /// Since scaling is linear, the outputs of the 2 parties are reconstructed by XOR
+    /// to `$f(x)$` scaled lane-wise, e.g., a Reed-Solomon codeword when `$f(x)$` is the message.
+    /// The keys are the same as the ones of [`XorGroup`].
+    /// Fails the same as [`Dcf::eval`].
+    pub fn eval_scaled_gf256(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        scales: &[u8; LAMBDA],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), ys.len())?;
+        self.eval_parts_then(b, k.party_seed(b), &k.cws, &k.cw_np1, xs, ys, true, |y| {
+            Gf256LaneGroup::mul(y, scales);
+        });
+        Ok(())
+    }
+}
+
+/// State of gen along the path of `$\alpha$` between levels
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize))]
+struct GenState<const LAMBDA: usize> {
+    /// `$s^{(i)}_0$` and `$s^{(i)}_1$`
+    ss: [[u8; LAMBDA]; 2],
+    /// `$t^{(i)}_0$` and `$t^{(i)}_1$`
+    ts: [bool; 2],
+    /// `$V_\alpha$`
+    v_alpha: [u8; LAMBDA],
+}
+
+impl<const LAMBDA: usize> GenState<LAMBDA> {
+    fn new(s0s: [&[u8; LAMBDA]; 2]) -> Self {
+        Self {
+            ss: [s0s[0].to_owned(), s0s[1].to_owned()],
+            ts: [false, true],
+            v_alpha: [0; LAMBDA],
+        }
+    }
+}
+
+const IDX_L: usize = 0;
+const IDX_R: usize = 1;
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> Dcf<N, LAMBDA> for DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    fn gen(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> Result<Share<LAMBDA>, DcfError> {
+        Ok(self.gen_parts(f, s0s, &bound, 0))
+    }
+
+    fn gen_fixed_bits(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+        leading_fixed_bits: usize,
+    ) -> Result<Share<LAMBDA>, DcfError> {
+        check_fixed_bits(f, leading_fixed_bits)?;
+        Ok(self.gen_parts(f, s0s, &bound, leading_fixed_bits))
+    }
+
+    fn gen_with_strategy<S>(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        s0s: [&[u8; LAMBDA]; 2],
+        strategy: &S,
+    ) -> Result<Share<LAMBDA>, DcfError>
+    where
+        Self: Sized,
+        S: CorrectionStrategy<N, LAMBDA> + ?Sized,
+    {
+        Ok(self.gen_parts(f, s0s, strategy, 0))
+    }
+
+    fn eval(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), xs.len(), ys.len())?;
+        self.eval_parts(b, k.party_seed(b), &k.cws, &k.cw_np1, xs, ys);
+        Ok(())
+    }
+
+    /// Walks the tree once by [`DcfImpl::eval_full_visit`], so each node is expanded only once,
+    /// with no allocation other than `ys` and a recursion stack of the tree depth.
+    ///
+    /// For keys of [`Dcf::gen_fixed_bits`], eval ignores the fixed bits,
+    /// so the outputs of the domain of the key repeat for each value of the fixed bits.
+    fn eval_all(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        ys: &mut [[u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_all_outputs::<N>(ys.len())?;
+        check_party_share::<N, LAMBDA>(k, &self.prg_check())?;
+        let key_domain = 1 << k.cws.len();
+        self.eval_full_visit(b, k, |i, y| ys[i as usize] = *y);
+        let (head, rest) = ys.split_at_mut(key_domain);
+        rest.chunks_exact_mut(key_domain)
+            .for_each(|chunk| chunk.copy_from_slice(head));
+        Ok(())
+    }
+
+    /// Skips the parallel dispatch, which is pointless for a single point
+    fn eval_at(&self, b: bool, k: &Share<LAMBDA>, x: &[u8; N]) -> Result<[u8; LAMBDA], DcfError> {
+        check_eval_inputs::<N, LAMBDA>(k, &self.prg_check(), 1, 1)?;
+        let mut y = [0; LAMBDA];
+        self.eval_parts_then(
+            b,
+            k.party_seed(b),
+            &k.cws,
+            &k.cw_np1,
+            &[x],
+            &mut [&mut y],
+            false,
+            |_| (),
+        );
+        Ok(y)
+    }
+
+    fn eval_party(
+        &self,
+        b: bool,
+        k: &PartyKey<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_eval_party_inputs::<N, LAMBDA>(k, xs.len(), ys.len())?;
+        self.eval_parts(b, &k.seed, &k.public.cws, &k.public.cw_np1, xs, ys);
+        Ok(())
+    }
+}
+
+/// Validation of [`Dcf::gen_fixed_bits`] before any work
+fn check_fixed_bits<const N: usize, const LAMBDA: usize>(
+    f: &CmpFn<N, LAMBDA>,
+    leading_fixed_bits: usize,
+) -> Result<(), DcfError> {
+    if leading_fixed_bits >= 8 * N || f.alpha.view_bits::<Msb0>()[..leading_fixed_bits].any() {
+        return Err(DcfError::InvalidFixedBits { leading_fixed_bits });
+    }
+    Ok(())
+}
+
+/// Validation of [`Dcf::eval`] before any work, with `prg_check` of the evaluating PRG
+fn check_eval_inputs<const N: usize, const LAMBDA: usize>(
+    k: &Share<LAMBDA>,
+    prg_check: &[u8; LAMBDA],
+    xs_len: usize,
+    ys_len: usize,
+) -> Result<(), DcfError> {
+    check_party_share::<N, LAMBDA>(k, prg_check)?;
+    check_output_count(xs_len, ys_len)
+}
+
+/// Validation of a key to evaluate, which must be generated with the PRG of `prg_check`.
+/// Both the key of a party and the output of gen, evaluated with the seed of the party, are accepted.
+fn check_party_share<const N: usize, const LAMBDA: usize>(
+    k: &Share<LAMBDA>,
+    prg_check: &[u8; LAMBDA],
+) -> Result<(), DcfError> {
+    k.check_invariants::<N>()?;
+    check_prg(k, prg_check)
+}
+
+/// `k` must be generated with the PRG of `prg_check`.
+/// Keys without [`Share::prg_check`], e.g., the ones from before it, are not checked.
+fn check_prg<const LAMBDA: usize>(
+    k: &Share<LAMBDA>,
+    prg_check: &[u8; LAMBDA],
+) -> Result<(), DcfError> {
+    match &k.prg_check {
+        Some(k_prg_check) if k_prg_check != prg_check => Err(DcfError::PrgMismatch),
+        _ => Ok(()),
+    }
+}
+
+/// See [`DcfImpl::prg_check`].
+///
+/// The outputs are chained rather than XORed together,
+/// so that each of them, e.g., the one of each AES key of `Aes256HirosePrg`, is covered in its own place,
+/// and swapping the keys changes it as well.
+fn prg_fingerprint<const LAMBDA: usize>(prg: &impl Prg<LAMBDA>) -> [u8; LAMBDA] {
+    prg.gen(&[0; LAMBDA])
+        .iter()
+        .fold([0; LAMBDA], |check, (s, v, t)| {
+            let mut seed = xor(&[&check, s, v]);
+            seed[0] ^= *t as u8;
+            let [(_, check, _), _] = prg.gen(&seed);
+            check
+        })
+}
+
+/// Validation of [`Dcf::eval_party`] before any work
+fn check_eval_party_inputs<const N: usize, const LAMBDA: usize>(
+    k: &PartyKey<LAMBDA>,
+    xs_len: usize,
+    ys_len: usize,
+) -> Result<(), DcfError> {
+    k.public.check_invariants::<N>()?;
+    check_output_count(xs_len, ys_len)
+}
+
+/// `ys_len` outputs must be `expected`, e.g., 1 for each input
+fn check_output_count(expected: usize, ys_len: usize) -> Result<(), DcfError> {
+    if ys_len != expected {
+        return Err(DcfError::MismatchedOutputBuffer {
+            expected,
+            got: ys_len,
+        });
+    }
+    Ok(())
+}
+
+/// Validation of [`Dcf::eval_all`], whose outputs must cover the whole domain
+fn check_eval_all_outputs<const N: usize>(ys_len: usize) -> Result<(), DcfError> {
+    check_domain_indexable::<N>()?;
+    check_output_count(1 << (8 * N), ys_len)
+}
+
+/// Whether every point of the domain has a `usize` index
+fn check_domain_indexable<const N: usize>() -> Result<(), DcfError> {
+    if 8 * N >= usize::BITS as usize {
+        return Err(DcfError::DomainTooLarge {
+            bits: 8 * N,
+            max: usize::BITS as usize - 1,
+        });
+    }
+    Ok(())
+}
+
+/// `[u8; LAMBDA]` serialized as a `Vec<u8>`, failing instead of panicking on other lengths
+fn bytes_to_array<const LAMBDA: usize, E>(bytes: Vec<u8>) -> Result<[u8; LAMBDA], E>
+where
+    E: de::Error,
+{
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| E::invalid_length(len, &format!("{} bytes", LAMBDA).as_str()))
+}
+
+/// Sets the field `name` of a struct deserialized from a map, failing if it is set twice
+fn set_field<T, E>(field: &mut Option<T>, name: &'static str, value: T) -> Result<(), E>
+where
+    E: de::Error,
+{
+    if field.replace(value).is_some() {
+        return Err(E::duplicate_field(name));
+    }
+    Ok(())
+}
+
+/// `Cw`. Correclation word.
+///
+/// `==` compares all of the fields in constant time by [`ConstantTimeEq`].
+///
+/// With the `zeroize` feature, it is zeroed on drop.
+#[derive(Clone)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct Cw<const LAMBDA: usize> {
+    pub s: [u8; LAMBDA],
+    pub v: [u8; LAMBDA],
+    pub tl: bool,
+    pub tr: bool,
+}
+
+/// `s` is printed as only its first 4 bytes unless with the `debug-secrets` feature
+impl<const LAMBDA: usize> fmt::Debug for Cw<LAMBDA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cw")
+            .field("s", &Secret(&self.s))
+            .field("v", &Hex(&self.v))
+            .field("tl", &self.tl)
+            .field("tr", &self.tr)
+            .finish()
+    }
+}
+
+impl<const LAMBDA: usize> ConstantTimeEq for Cw<LAMBDA> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.s.ct_eq(&other.s)
+            & self.v.ct_eq(&other.v)
+            & (self.tl as u8).ct_eq(&(other.tl as u8))
+            & (self.tr as u8).ct_eq(&(other.tr as u8))
+    }
+}
+
+impl<const LAMBDA: usize> PartialEq for Cw<LAMBDA> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const LAMBDA: usize> Eq for Cw<LAMBDA> {}
+
+impl<const LAMBDA: usize> Serialize for Cw<LAMBDA> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Cw", 4)?;
+        s.serialize_field("s", &self.s.to_vec())?;
+        s.serialize_field("v", &self.v.to_vec())?;
+        s.serialize_field("tl", &self.tl)?;
+        s.serialize_field("tr", &self.tr)?;
+        s.end()
+    }
+}
+
+impl<'de, const LAMBDA: usize> Deserialize<'de> for Cw<LAMBDA> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CwVisitor<const LAMBDA: usize>;
+
+        impl<'de, const LAMBDA: usize> Visitor<'de> for CwVisitor<LAMBDA> {
+            type Value = Cw<LAMBDA>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct Cw")
+            }
+
+            fn visit_seq<V>(self, mut seq: V) -> Result<Cw<LAMBDA>, V::Error>
+            where
+                V: SeqAccess<'de>,
+            {
+                let s_vec: Vec<u8> = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let s = bytes_to_array(s_vec)?;
+
+                let v_vec: Vec<u8> = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let v = bytes_to_array(v_vec)?;
+
+                let tl: bool = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let tr: bool = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+
+                Ok(Cw { s, v, tl, tr })
+            }
+
+            /// For self-describing formats like JSON, which give structs as maps
+            fn visit_map<V>(self, mut map: V) -> Result<Cw<LAMBDA>, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let (mut s, mut v, mut tl, mut tr) = (None, None, None, None);
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "s" => set_field(&mut s, "s", bytes_to_array(map.next_value()?)?)?,
+                        "v" => set_field(&mut v, "v", bytes_to_array(map.next_value()?)?)?,
+                        "tl" => set_field(&mut tl, "tl", map.next_value()?)?,
+                        "tr" => set_field(&mut tr, "tr", map.next_value()?)?,
+                        _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                    }
+                }
+                Ok(Cw {
+                    s: s.ok_or_else(|| de::Error::missing_field("s"))?,
+                    v: v.ok_or_else(|| de::Error::missing_field("v"))?,
+                    tl: tl.ok_or_else(|| de::Error::missing_field("tl"))?,
+                    tr: tr.ok_or_else(|| de::Error::missing_field("tr"))?,
+                })
+            }
+        }
+
+        const FIELDS: &[&str] = &["s", "v", "tl", "tr"];
+        deserializer.deserialize_struct("Cw", FIELDS, CwVisitor)
+    }
+}
+
+/// `k`.
+///
+/// The output of gen is for the dealer only, since it has the seeds of both parties in `s0s`.
+/// It must be split by [`Share::split`] or [`Share::into_party_keys`] before it is sent to the parties.
+/// Eval accepts it too, using `s0s[b]` for the party `b`, e.g., for the dealer to check the keys.
+///
+/// `cws` and `cw_np1` is shared by the 2 parties.
+/// Only `s0s[0]` is different.
+///
+/// `==` compares all of the fields by [`ConstantTimeEq`].
+/// The bytes of the seeds and the cws are compared in constant time,
+/// while the lengths of `s0s` and `cws` and `n_bytes`, which are public, are not.
+///
+/// With the `zeroize` feature, it is zeroed on drop, including the seeds and the cws.
+#[serde_as]
+#[derive(Clone)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct Share<const LAMBDA: usize> {
+    /// For the output of `gen`, its length is 2.
+    /// For the input of `eval`, its length must be 1, i.e., the seed of the party.
+    pub s0s: Vec<[u8; LAMBDA]>,
+    /// The length of `cws` must be `n = 8 * N`,
+    /// or `n = 8 * N - leading_fixed_bits` for [`Dcf::gen_fixed_bits`]
+    pub cws: Vec<Cw<LAMBDA>>,
+    /// `$CW^{(n + 1)}$`
+    pub cw_np1: [u8; LAMBDA],
+    /// `N` of the [`DcfImpl`] generating it.
+    /// Since `N` is only in the type, it is kept here for [`Share::from_bytes`] to check.
+    pub n_bytes: usize,
+    /// [`DcfImpl::prg_check`] of the PRG generating it, for eval to fail with [`DcfError::PrgMismatch`]
+    /// instead of silently outputting garbage with another PRG.
+    /// `None` skips the check, e.g., for the keys serialized before it.
+    pub prg_check: Option<[u8; LAMBDA]>,
+}
+
+/// The seeds are printed as only their first 4 bytes unless with the `debug-secrets` feature.
+/// Only the number of the cws and the first 4 bytes of `cw_np1` are printed.
+impl<const LAMBDA: usize> fmt::Debug for Share<LAMBDA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s0s: Vec<_> = self.s0s.iter().map(|s0| Secret(s0)).collect();
+        f.debug_struct("Share")
+            .field("s0s", &s0s)
+            .field("cws", &format_args!("[..; {}]", self.cws.len()))
+            .field("cw_np1", &HexPrefix(&self.cw_np1))
+            .field("n_bytes", &self.n_bytes)
+            .field(
+                "prg_check",
+                &self.prg_check.as_ref().map(|check| HexPrefix(check)),
+            )
+            .finish()
+    }
+}
+
+impl<const LAMBDA: usize> ConstantTimeEq for Share<LAMBDA> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        // `prg_check` is public and compared as is
+        if self.s0s.len() != other.s0s.len()
+            || self.cws.len() != other.cws.len()
+            || self.n_bytes != other.n_bytes
+            || self.prg_check != other.prg_check
+        {
+            return Choice::from(0);
+        }
+        let s0s_eq = self
+            .s0s
+            .iter()
+            .zip(other.s0s.iter())
+            .fold(Choice::from(1), |eq, (s0, s0_other)| {
+                eq & s0.ct_eq(s0_other)
+            });
+        let cws_eq = self
+            .cws
+            .iter()
+            .zip(other.cws.iter())
+            .fold(Choice::from(1), |eq, (cw, cw_other)| {
+                eq & cw.ct_eq(cw_other)
+            });
+        s0s_eq & cws_eq & self.cw_np1.ct_eq(&other.cw_np1)
+    }
+}
+
+impl<const LAMBDA: usize> PartialEq for Share<LAMBDA> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const LAMBDA: usize> Eq for Share<LAMBDA> {}
+
+impl<const LAMBDA: usize> Serialize for Share<LAMBDA> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.serialize_fields(serializer, SHARE_FIELDS)
+    }
+}
+
+impl<const LAMBDA: usize> Share<LAMBDA> {
+    /// Serializes `fields`, which are [`SHARE_FIELDS`], or [`LEGACY_SHARE_FIELDS`] without `prg_check`
+    fn serialize_fields<S>(
+        &self,
+        serializer: S,
+        fields: &'static [&'static str],
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Share", fields.len())?;
+        let s0s_as_vecs: Vec<Vec<u8>> = self.s0s.iter().map(|arr| arr.to_vec()).collect();
+        s.serialize_field("s0s", &s0s_as_vecs)?;
+        s.serialize_field("cws", &self.cws)?;
+        s.serialize_field("cw_np1", &self.cw_np1.to_vec())?;
+        s.serialize_field("n_bytes", &(self.n_bytes as u64))?;
+        if fields.len() == SHARE_FIELDS.len() {
+            s.serialize_field("prg_check", &self.prg_check.map(|check| check.to_vec()))?;
+        }
+        s.end()
+    }
+}
+
+/// Fields of the serde layout of [`Share`]
+const SHARE_FIELDS: &[&str] = &["s0s", "cws", "cw_np1", "n_bytes", "prg_check"];
+/// Fields of the serde layout of [`Share`] from before [`Share::prg_check`]
+const LEGACY_SHARE_FIELDS: &[&str] = &["s0s", "cws", "cw_np1", "n_bytes"];
+
+/// Visitor of the serde layout of [`Share`] with `fields`, which are [`SHARE_FIELDS`] or [`LEGACY_SHARE_FIELDS`]
+struct ShareVisitor<const LAMBDA: usize> {
+    fields: &'static [&'static str],
+}
+
+/// [`Share`] in the serde layout from before [`Share::prg_check`], with it as `None`.
+///
+/// Formats like bincode only parse the layout they are told, so the keys stored in it are parsed with this.
+struct LegacyShare<const LAMBDA: usize>(Share<LAMBDA>);
+
+/// Serializes [`Share`] in the layout of [`LegacyShare`], dropping `prg_check`
+struct LegacyShareRef<'a, const LAMBDA: usize>(&'a Share<LAMBDA>);
+
+impl<'de, const LAMBDA: usize> Deserialize<'de> for LegacyShare<LAMBDA> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = ShareVisitor {
+            fields: LEGACY_SHARE_FIELDS,
+        };
+        deserializer
+            .deserialize_struct("Share", LEGACY_SHARE_FIELDS, visitor)
+            .map(LegacyShare)
+    }
+}
+
+impl<const LAMBDA: usize> Serialize for LegacyShareRef<'_, LAMBDA> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_fields(serializer, LEGACY_SHARE_FIELDS)
+    }
+}
+
+impl<'de, const LAMBDA: usize> Visitor<'de> for ShareVisitor<LAMBDA> {
+    type Value = Share<LAMBDA>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct Share")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Share<LAMBDA>, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        let s0s_as_vecs: Vec<Vec<u8>> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let s0s: Vec<[u8; LAMBDA]> = s0s_as_vecs
+            .into_iter()
+            .map(bytes_to_array)
+            .collect::<Result<_, _>>()?;
+
+        let cws: Vec<Cw<LAMBDA>> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let cw_np1_vec: Vec<u8> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let cw_np1 = bytes_to_array(cw_np1_vec)?;
+        let n_bytes: u64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        let prg_check = if self.fields.len() == SHARE_FIELDS.len() {
+            let prg_check: Option<Vec<u8>> = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+            prg_check.map(bytes_to_array).transpose()?
+        } else {
+            None
+        };
+
+        Ok(Share {
+            s0s,
+            cws,
+            cw_np1,
+            n_bytes: n_bytes as usize,
+            prg_check,
+        })
+    }
+
+    /// For self-describing formats like JSON, which give structs as maps.
+    ///
+    /// `prg_check` may be missing, which is the same as `None`.
+    fn visit_map<V>(self, mut map: V) -> Result<Share<LAMBDA>, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let (mut s0s, mut cws, mut cw_np1, mut n_bytes) = (None, None, None, None);
+        let mut prg_check = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "s0s" => {
+                    let s0s_as_vecs: Vec<Vec<u8>> = map.next_value()?;
+                    let value = s0s_as_vecs
+                        .into_iter()
+                        .map(bytes_to_array)
+                        .collect::<Result<Vec<[u8; LAMBDA]>, _>>()?;
+                    set_field(&mut s0s, "s0s", value)?;
+                }
+                "cws" => set_field(&mut cws, "cws", map.next_value()?)?,
+                "cw_np1" => set_field(&mut cw_np1, "cw_np1", bytes_to_array(map.next_value()?)?)?,
+                "n_bytes" => set_field(&mut n_bytes, "n_bytes", map.next_value::<u64>()?)?,
+                "prg_check" if self.fields.contains(&"prg_check") => {
+                    let value = map.next_value::<Option<Vec<u8>>>()?;
+                    set_field(
+                        &mut prg_check,
+                        "prg_check",
+                        value.map(bytes_to_array).transpose()?,
+                    )?
+                }
+                _ => return Err(de::Error::unknown_field(&key, self.fields)),
+            }
+        }
+        Ok(Share {
+            s0s: s0s.ok_or_else(|| de::Error::missing_field("s0s"))?,
+            cws: cws.ok_or_else(|| de::Error::missing_field("cws"))?,
+            cw_np1: cw_np1.ok_or_else(|| de::Error::missing_field("cw_np1"))?,
+            n_bytes: n_bytes.ok_or_else(|| de::Error::missing_field("n_bytes"))? as usize,
+            prg_check: prg_check.flatten(),
+        })
+    }
+}
+
+impl<'de, const LAMBDA: usize> Deserialize<'de> for Share<LAMBDA> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let visitor = ShareVisitor {
+            fields: SHARE_FIELDS,
+        };
+        deserializer.deserialize_struct("Share", SHARE_FIELDS, visitor)
+    }
+}
+
+impl<const LAMBDA: usize> Share<LAMBDA> {
+    /// Migrate to [`PartyKey`]s, one for each seed in `s0s` in order.
+    ///
+    /// The public part is moved into a single [`PublicShare`] shared by all of the returned keys.
+    pub fn into_party_keys(mut self) -> Vec<PartyKey<LAMBDA>> {
+        // Taken instead of moved since `Share` is `Drop` with the `zeroize` feature
+        let public = Arc::new(PublicShare {
+            cws: std::mem::take(&mut self.cws),
+            cw_np1: self.cw_np1,
+        });
+        // Copied, so the seeds left in `self` are zeroed on drop with the `zeroize` feature
+        self.s0s
+            .iter()
+            .map(|seed| PartyKey {
+                seed: Seed::new(seed),
+                public: public.clone(),
+            })
+            .collect()
+    }
+
+    /// The seed of the party `b`, which is the only seed of the key of a party
+    /// or `s0s[b]` of the output of gen.
+    ///
+    /// Panics if `s0s` has other than 1 or 2 seeds.
+    fn party_seed(&self, b: bool) -> &[u8; LAMBDA] {
+        match self.s0s.len() {
+            1 => &self.s0s[0],
+            2 => &self.s0s[b as usize],
+            n => panic!("the key has {} seeds, but 1 or 2 are required", n),
+        }
+    }
+
+    /// Splits the output of gen into the keys of party 0 and party 1, each with only its own seed.
+    ///
+    /// Panics unless `s0s` has exactly 2 different seeds,
+    /// since equal seeds make the 2 keys the same, with which either party alone learns `f`.
+    #[must_use = "each of the 2 keys must be sent to its party"]
+    pub fn split(self) -> [Self; 2] {
+        assert_eq!(self.s0s.len(), 2, "only the output of gen can be split");
+        assert_ne!(self.s0s[0], self.s0s[1], "the 2 seeds must differ");
+        let mut k0 = self;
+        let mut k1 = k0.clone();
+        k0.s0s.truncate(1);
+        k1.s0s.remove(0);
+        [k0, k1]
+    }
+
+    /// Inverse of [`Share::split`], e.g., for the dealer or testing to evaluate both keys.
+    ///
+    /// Panics unless `k0` and `k1` each have 1 seed and the same public part,
+    /// i.e., are the keys of party 0 and party 1 of the same gen.
+    pub fn unsplit(k0: Self, mut k1: Self) -> Self {
+        assert_eq!(
+            (k0.s0s.len(), k1.s0s.len()),
+            (1, 1),
+            "only the keys of the 2 parties can be unsplit"
+        );
+        assert!(
+            k0.cws == k1.cws
+                && k0.cw_np1 == k1.cw_np1
+                && k0.n_bytes == k1.n_bytes
+                && k0.prg_check == k1.prg_check,
+            "the keys must be of the same gen"
+        );
+        let mut k = k0;
+        k.s0s.append(&mut k1.s0s);
+        k
+    }
+
+    /// Validates the shape of a received key for a [`DcfImpl`] of the domain byte size `N` before eval.
+    ///
+    /// `s0s` must have 1 seed for a party or 2 from gen,
+    /// `n_bytes` must be `N`,
+    /// and `cws` must have `8 * N` correlation words, or fewer for [`Dcf::gen_fixed_bits`] but at least 1.
+    /// The seeds and the words are always full-width since their sizes are in the types,
+    /// which deserialization already checks.
+    pub fn check_invariants<const N: usize>(&self) -> Result<(), DcfError> {
+        if !(1..=2).contains(&self.s0s.len()) {
+            return Err(DcfError::InvalidSeedCount {
+                got: self.s0s.len(),
+            });
+        }
+        if self.n_bytes != N {
+            return Err(DcfError::DomainSizeMismatch {
+                expected: N,
+                got: self.n_bytes,
+            });
+        }
+        if !(1..=8 * N).contains(&self.cws.len()) {
+            return Err(DcfError::CwCountOutOfRange {
+                got: self.cws.len(),
+                max: 8 * N,
+            });
+        }
+        Ok(())
+    }
+
+    /// Serializes with bincode, including `n_bytes` for [`Share::from_bytes`] to check
+    /// and `prg_check` for eval to check.
+    ///
+    /// The layout is a format flag byte, which is `0xd0` here, and the bincode encoding.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bincode_bytes(Some(FORMAT_PLAIN), false)
+    }
+
+    /// Like [`Share::to_bytes`] but with a trailing CRC32 of the bincode encoding,
+    /// e.g., for storage on unreliable media.
+    ///
+    /// The format flag byte is `0xd1` and the CRC32 is in little-endian.
+    /// It detects accidental corruption but not tampering, which needs a MAC.
+    pub fn to_bytes_crc32(&self) -> Vec<u8> {
+        self.to_bincode_bytes(Some(FORMAT_CRC32), false)
+    }
+
+    /// Shared by [`Share::to_bytes`], [`Share::to_bytes_crc32`], and [`Share::is_canonical_encoding`].
+    ///
+    /// `flag` is `None` for the keys from before the flags,
+    /// and `legacy` takes the serde layout from before `prg_check`, i.e., [`LegacyShare`].
+    fn to_bincode_bytes(&self, flag: Option<u8>, legacy: bool) -> Vec<u8> {
+        let mut bytes: Vec<u8> = flag.into_iter().collect();
+        let start = bytes.len();
+        if legacy {
+            bincode::serialize_into(&mut bytes, &LegacyShareRef(self)).unwrap();
+        } else {
+            bincode::serialize_into(&mut bytes, self).unwrap();
+        }
+        if flag == Some(FORMAT_CRC32) {
+            let crc = crc32(&bytes[start..]);
+            bytes.extend_from_slice(&crc.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Serializes in a compact layout of its own instead of bincode, e.g., for a wire format not tied to serde.
+    ///
+    /// The layout is, with the integers in little-endian:
+    ///
+    /// - The format flag byte, which is `0xd2` here
+    /// - `n_bytes` as a `u32`
+    /// - The number of the seeds as a `u8`, and the number `n` of the correlation words as a `u32`
+    /// - The seeds, each of `LAMBDA` bytes
+    /// - `s || v` of each correlation word, i.e., `2 * LAMBDA` bytes each
+    /// - The `2n` control bits packed into `ceil(2n / 8)` bytes,
+    ///   with `tl` of the word `i` at the bit `2i` and `tr` at the bit `2i + 1`, counting from the LSB of the first byte,
+    ///   and the unused high bits of the last byte as 0
+    /// - `cw_np1` of `LAMBDA` bytes
+    /// - `prg_check` of `LAMBDA` bytes, or nothing if it is `None`,
+    ///   which the total length tells apart
+    ///
+    /// [`Share::from_bytes`] parses it as well.
+    /// It is a method of its own since [`Share::to_bytes`] keeps the bincode layout of the keys already stored,
+    /// and the number of correlation words is in the layout, so parsing takes no bit size of the domain.
+    ///
+    /// Panics if a count does not fit its integer, which no key from gen comes close to.
+    pub fn to_bytes_compact(&self) -> Vec<u8> {
+        let n = self.cws.len();
+        let n_bytes =
+            u32::try_from(self.n_bytes).expect("n_bytes too large for the compact layout");
+        let seed_count =
+            u8::try_from(self.s0s.len()).expect("too many seeds for the compact layout");
+        let n_u32 = u32::try_from(n).expect("too many correlation words for the compact layout");
+        let mut bytes = Vec::with_capacity(
+            COMPACT_HEADER + (self.s0s.len() + 2 * n + 2) * LAMBDA + (2 * n).div_ceil(8),
+        );
+        bytes.push(FORMAT_COMPACT);
+        bytes.extend_from_slice(&n_bytes.to_le_bytes());
+        bytes.push(seed_count);
+        bytes.extend_from_slice(&n_u32.to_le_bytes());
+        self.s0s.iter().for_each(|s0| bytes.extend_from_slice(s0));
+        self.cws.iter().for_each(|cw| {
+            bytes.extend_from_slice(&cw.s);
+            bytes.extend_from_slice(&cw.v);
+        });
+        let mut ts = bitvec![u8, Lsb0; 0; (2 * n).div_ceil(8) * 8];
+        self.cws.iter().enumerate().for_each(|(i, cw)| {
+            ts.set(2 * i, cw.tl);
+            ts.set(2 * i + 1, cw.tr);
+        });
+        bytes.extend_from_slice(ts.as_raw_slice());
+        bytes.extend_from_slice(&self.cw_np1);
+        if let Some(prg_check) = &self.prg_check {
+            bytes.extend_from_slice(prg_check);
+        }
+        bytes
+    }
+
+    /// Inverse of [`Share::to_bytes_compact`] without the format flag byte
+    fn parse_compact(payload: &[u8]) -> Result<Self, DcfError> {
+        if payload.len() < COMPACT_HEADER - 1 {
+            return Err(DcfError::MalformedBytes);
+        }
+        let (header, rest) = payload.split_at(COMPACT_HEADER - 1);
+        let n_bytes = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let seed_count = header[4] as usize;
+        let n = u32::from_le_bytes(header[5..].try_into().unwrap()) as usize;
+        let t_len = (2 * n).div_ceil(8);
+        // Checked before slicing, so that an untrusted `n` can not overflow or over-allocate
+        let expected_len = (seed_count as u64 + 2 * n as u64 + 1) * LAMBDA as u64 + t_len as u64;
+        let with_prg_check = rest.len() as u64 == expected_len + LAMBDA as u64;
+        if rest.len() as u64 != expected_len && !with_prg_check {
+            return Err(DcfError::MalformedBytes);
+        }
+        let array = |chunk: &[u8]| -> [u8; LAMBDA] { chunk.try_into().unwrap() };
+        let (seeds, rest) = rest.split_at(seed_count * LAMBDA);
+        let (svs, rest) = rest.split_at(2 * n * LAMBDA);
+        let (ts, rest) = rest.split_at(t_len);
+        let (cw_np1, prg_check) = rest.split_at(LAMBDA);
+        let ts = ts.view_bits::<Lsb0>();
+        if ts[2 * n..].any() {
+            return Err(DcfError::MalformedBytes);
+        }
+        Ok(Self {
+            s0s: seeds.chunks_exact(LAMBDA).map(array).collect(),
+            cws: svs
+                .chunks_exact(2 * LAMBDA)
+                .enumerate()
+                .map(|(i, sv)| Cw {
+                    s: array(&sv[..LAMBDA]),
+                    v: array(&sv[LAMBDA..]),
+                    tl: ts[2 * i],
+                    tr: ts[2 * i + 1],
+                })
+                .collect(),
+            cw_np1: array(cw_np1),
+            n_bytes,
+            prg_check: with_prg_check.then(|| array(prg_check)),
+        })
+    }
+
+    /// Inverse of [`Share::to_bytes`], [`Share::to_bytes_crc32`], and [`Share::to_bytes_compact`]
+    /// for a [`DcfImpl`] of the domain byte size `N`.
+    ///
+    /// The keys serialized before the format flags, which are bincode without a flag, still parse.
+    ///
+    /// Fails with [`DcfError::DomainSizeMismatch`] if the share is generated with another `N`,
+    /// which would otherwise make eval silently output garbage,
+    /// [`DcfError::CorruptData`] if the CRC32 mismatches,
+    /// or [`DcfError::MalformedBytes`] if the bytes can not be deserialized.
+    pub fn from_bytes<const N: usize>(bytes: &[u8]) -> Result<Self, DcfError> {
+        let (k, _, _) = Self::parse_bytes(bytes)?;
+        if k.n_bytes != N {
+            return Err(DcfError::DomainSizeMismatch {
+                expected: N,
+                got: k.n_bytes,
+            });
+        }
+        Ok(k)
+    }
+
+    /// Shared by [`Share::from_bytes`] and [`Share::is_canonical_encoding`],
+    /// returning the format flag as well, which is `None` for the keys from before the flags,
+    /// and whether the bincode is in the serde layout from before `prg_check`
+    fn parse_bytes(bytes: &[u8]) -> Result<(Self, Option<u8>, bool), DcfError> {
+        let (&flag, payload) = bytes.split_first().ok_or(DcfError::MalformedBytes)?;
+        let payload = match flag {
+            FORMAT_PLAIN => payload,
+            FORMAT_CRC32 => {
+                if payload.len() < 4 {
+                    return Err(DcfError::MalformedBytes);
+                }
+                let (payload, crc) = payload.split_at(payload.len() - 4);
+                if crc32(payload).to_le_bytes() != crc {
+                    return Err(DcfError::CorruptData);
+                }
+                payload
+            }
+            FORMAT_COMPACT => return Ok((Self::parse_compact(payload)?, Some(flag), false)),
+            _ => {
+                let (k, legacy) = Self::parse_bincode(bytes)?;
+                return Ok((k, None, legacy));
+            }
+        };
+        let (k, legacy) = Self::parse_bincode(payload)?;
+        Ok((k, Some(flag), legacy))
+    }
+
+    /// Parses bincode in the serde layout, or the one from before `prg_check`, returning which it is
+    fn parse_bincode(bytes: &[u8]) -> Result<(Self, bool), DcfError> {
+        if let Ok(k) = bincode::deserialize(bytes) {
+            return Ok((k, false));
+        }
+        let LegacyShare(k) = bincode::deserialize(bytes).map_err(|_| DcfError::MalformedBytes)?;
+        Ok((k, true))
+    }
+
+    /// Whether `bytes` is exactly what [`Share::to_bytes`], [`Share::to_bytes_crc32`], or [`Share::to_bytes_compact`],
+    /// whichever the format flag says, outputs for the share it deserializes to,
+    /// or the bincode encoding for the keys from before the flags.
+    /// The bincode of the keys from before `prg_check` is checked in its own layout.
+    ///
+    /// Rejects malformed bytes and non-canonical ones like those with trailing bytes,
+    /// so that each share has only 1 accepted encoding per format, e.g., for commitments.
+    pub fn is_canonical_encoding(bytes: &[u8]) -> bool {
+        match Self::parse_bytes(bytes) {
+            Ok((k, Some(FORMAT_COMPACT), _)) => k.to_bytes_compact() == bytes,
+            Ok((k, flag, legacy)) => k.to_bincode_bytes(flag, legacy) == bytes,
+            Err(_) => false,
+        }
+    }
+
+    /// Serializes with [`Share::to_bytes`] and pads to exactly `target_len` bytes,
+    /// so that shares of different domain sizes can not be told apart by the message length.
+    ///
+    /// The layout is the little-endian `u64` byte length of the serialized bytes,
+    /// the bytes, and then 0s.
+    /// Fails with [`DcfError::ShareTooLong`] if `target_len` can not hold them.
+    pub fn to_bytes_padded(&self, target_len: usize) -> Result<Vec<u8>, DcfError> {
+        let content = self.to_bytes();
+        let len = PADDED_LEN_PREFIX + content.len();
+        if len > target_len {
+            return Err(DcfError::ShareTooLong { len, target_len });
+        }
+        let mut bytes = Vec::with_capacity(target_len);
+        bytes.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&content);
+        bytes.resize(target_len, 0);
+        Ok(bytes)
+    }
+
+    /// Inverse of [`Share::to_bytes_padded`], checking `N` like [`Share::from_bytes`].
+    ///
+    /// Fails with [`DcfError::MalformedBytes`] if the length prefix is out of range,
+    /// the padding is not all 0s, or the encoding can not be deserialized.
+    pub fn from_bytes_padded<const N: usize>(bytes: &[u8]) -> Result<Self, DcfError> {
+        if bytes.len() < PADDED_LEN_PREFIX {
+            return Err(DcfError::MalformedBytes);
+        }
+        let (prefix, rest) = bytes.split_at(PADDED_LEN_PREFIX);
+        let len = u64::from_le_bytes(prefix.try_into().unwrap());
+        if len > rest.len() as u64 {
+            return Err(DcfError::MalformedBytes);
+        }
+        let (content, padding) = rest.split_at(len as usize);
+        if padding.iter().any(|&byte| byte != 0) {
+            return Err(DcfError::MalformedBytes);
+        }
+        Self::from_bytes::<N>(content)
+    }
+
+    /// Writes a self-delimiting frame of [`Share::to_bytes`] to `w`,
+    /// e.g., to stream keys over a socket without sending the count in advance.
+    ///
+    /// The layout is the magic byte `0xdc`, which also versions the layout,
+    /// the little-endian `u32` byte length of the serialized bytes, and the bytes.
+    /// Fails with [`DcfError::Io`] if writing fails.
+    pub fn write_framed<W: Write>(&self, mut w: W) -> Result<(), DcfError> {
+        let content = self.to_bytes();
+        let len = u32::try_from(content.len()).expect("share too long to frame");
+        let mut bytes = Vec::with_capacity(FRAME_HEADER + content.len());
+        bytes.push(FRAME_MAGIC);
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&content);
+        w.write_all(&bytes).map_err(DcfError::from)
+    }
+
+    /// Reads the next frame of [`Share::write_framed`] from `r`, checking `N` like [`Share::from_bytes`].
+    ///
+    /// Returns `None` if `r` ends exactly at a frame boundary.
+    /// Short reads are retried until the frame is complete.
+    /// Fails with [`DcfError::TruncatedFrame`] if `r` ends within a frame,
+    /// [`DcfError::MalformedBytes`] if the magic byte mismatches or the bytes can not be deserialized,
+    /// or [`DcfError::Io`] if reading fails otherwise.
+    pub fn read_framed<R: Read, const N: usize>(mut r: R) -> Result<Option<Self>, DcfError> {
+        let mut magic = [0];
+        loop {
+            match r.read(&mut magic) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if magic[0] != FRAME_MAGIC {
+            return Err(DcfError::MalformedBytes);
+        }
+        let mut len = [0; FRAME_HEADER - 1];
+        r.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        // Not allocating by the untrusted length upfront
+        let mut content = vec![];
+        r.take(len as u64).read_to_end(&mut content)?;
+        if content.len() < len {
+            return Err(DcfError::TruncatedFrame);
+        }
+        Self::from_bytes::<N>(&content).map(Some)
+    }
+
+    /// Checks that the key generated with the group `From` can be evaluated with the group `To`, returning it as is.
+    ///
+    /// A share does not record its group, so the caller names both.
+    /// It is valid iff. they are the same group or have the same [`Group::ADDITION`],
+    /// e.g., from [`XorGroup`] to [`Gf256LaneGroup`], since the tree only uses the addition.
+    /// Otherwise it fails with [`DcfError::IncompatibleGroups`],
+    /// e.g., between [`XorGroup`] and [`ArithGroup`] **even for single-bit outputs**,
+    /// since the tree accumulates full-width PRG outputs in the group, not only `$\beta$`.
+    /// For them, convert the outputs instead of the key,
+    /// e.g., the lowest bits of the [`ArithGroup`] shares of a bit are XOR shares of it.
+    pub fn reinterpret_group<From, To>(self) -> Result<Self, DcfError>
+    where
+        From: Group<LAMBDA> + 'static,
+        To: Group<LAMBDA> + 'static,
+    {
+        let same_group = std::any::TypeId::of::<From>() == std::any::TypeId::of::<To>();
+        let same_addition = From::ADDITION.is_some() && From::ADDITION == To::ADDITION;
+        if same_group || same_addition {
+            Ok(self)
+        } else {
+            Err(DcfError::IncompatibleGroups {
+                from: std::any::type_name::<From>(),
+                to: std::any::type_name::<To>(),
+            })
+        }
+    }
+
+    /// Estimated cost of [`Dcf::eval`] with this key on `batch_size` inputs, e.g., to decide whether to parallelize
+    pub fn eval_cost(&self, batch_size: usize) -> EvalCost {
+        let n = self.cws.len() as u64;
+        let batch_size = batch_size as u64;
+        EvalCost {
+            // The root expansion is shared by the batch
+            prg_calls: 1 + batch_size * n.saturating_sub(1),
+            // Each level: 2 for the seeds, 1 for `v`, and 1 for `$V_{CW}$`.
+            // The leaf: 1 for the seed, 1 for `$CW^{(n + 1)}$`, and 1 for the sign.
+            xors: batch_size * (4 * n + 3),
+        }
+    }
+}
+
+/// Format flag of [`Share::to_bytes`].
+///
+/// The flags are in `0xd0..=0xdf`, which the first byte of the keys from before the flags never is.
+/// Those are bincode without a flag, which starts with the little-endian `u64` number of the seeds, i.e., 1 or 2.
+const FORMAT_PLAIN: u8 = 0xd0;
+/// Format flag of [`Share::to_bytes_crc32`]
+const FORMAT_CRC32: u8 = 0xd1;
+/// Format flag of [`Share::to_bytes_compact`]
+const FORMAT_COMPACT: u8 = 0xd2;
+/// Byte size of the format flag byte, `n_bytes`, and the counts of [`Share::to_bytes_compact`]
+const COMPACT_HEADER: usize = 10;
+
+/// Byte size of the length prefix of [`Share::to_bytes_padded`]
+const PADDED_LEN_PREFIX: usize = 8;
+
+/// Magic byte of [`Share::write_framed`]
+const FRAME_MAGIC: u8 = 0xdc;
+/// Byte size of the magic byte and the length prefix of [`Share::write_framed`]
+const FRAME_HEADER: usize = 5;
+
+/// Cost estimate of eval returned by [`Share::eval_cost`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalCost {
+    /// Number of PRG calls, which is exact
+    pub prg_calls: u64,
+    /// Upper bound of the number of `LAMBDA`-byte XORs, or operations of the group for non-[`XorGroup`] outputs.
+    /// Some of them are skipped depending on the control bits.
+    pub xors: u64,
+}
+
+/// Public part of `k`, which is the same for the 2 parties and can be sent to both.
+#[derive(Clone)]
+pub struct PublicShare<const LAMBDA: usize> {
+    /// The length of `cws` must be `n = 8 * N`,
+    /// or `n = 8 * N - leading_fixed_bits` for [`Dcf::gen_fixed_bits`]
+    pub cws: Vec<Cw<LAMBDA>>,
+    /// `$CW^{(n + 1)}$`
+    pub cw_np1: [u8; LAMBDA],
+}
+
+impl<const LAMBDA: usize> PublicShare<LAMBDA> {
+    /// Checks the structural invariants of the public part that eval assumes, like [`Share::check_invariants`].
+    ///
+    /// `cws` must have `8 * N` correlation words, or fewer for [`Dcf::gen_fixed_bits`] but at least 1.
+    pub fn check_invariants<const N: usize>(&self) -> Result<(), DcfError> {
+        if !(1..=8 * N).contains(&self.cws.len()) {
+            return Err(DcfError::CwCountOutOfRange {
+                got: self.cws.len(),
+                max: 8 * N,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<const LAMBDA: usize> Serialize for PublicShare<LAMBDA> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("PublicShare", 2)?;
+        s.serialize_field("cws", &self.cws)?;
+        s.serialize_field("cw_np1", &self.cw_np1.to_vec())?;
+        s.end()
+    }
+}
+
+impl<'de, const LAMBDA: usize> Deserialize<'de> for PublicShare<LAMBDA> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PublicShareVisitor<const LAMBDA: usize>;
+
+        impl<'de, const LAMBDA: usize> Visitor<'de> for PublicShareVisitor<LAMBDA> {
+            type Value = PublicShare<LAMBDA>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct PublicShare")
+            }
+
+            fn visit_seq<V>(self, mut seq: V) -> Result<PublicShare<LAMBDA>, V::Error>
+            where
+                V: SeqAccess<'de>,
+            {
+                let cws: Vec<Cw<LAMBDA>> = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let cw_np1_vec: Vec<u8> = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let cw_np1 = bytes_to_array(cw_np1_vec)?;
+
+                Ok(PublicShare { cws, cw_np1 })
+            }
+
+            /// For self-describing formats like JSON, which give structs as maps
+            fn visit_map<V>(self, mut map: V) -> Result<PublicShare<LAMBDA>, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let (mut cws, mut cw_np1) = (None, None);
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "cws" => set_field(&mut cws, "cws", map.next_value()?)?,
+                        "cw_np1" => {
+                            set_field(&mut cw_np1, "cw_np1", bytes_to_array(map.next_value()?)?)?
+                        }
+                        _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                    }
+                }
+                Ok(PublicShare {
+                    cws: cws.ok_or_else(|| de::Error::missing_field("cws"))?,
+                    cw_np1: cw_np1.ok_or_else(|| de::Error::missing_field("cw_np1"))?,
+                })
+            }
+        }
+
+        const FIELDS: &[&str] = &["cws", "cw_np1"];
+        deserializer.deserialize_struct("PublicShare", FIELDS, PublicShareVisitor)
+    }
+}
+
+/// Storage of the secret seed in [`PartyKey`], which is in locked memory with the `secure-mem` feature.
+///
+/// It is the same type with or without the feature, which only changes the storage,
+/// so that enabling it for one crate does not break the others sharing the build.
+/// It derefs to `[u8; LAMBDA]`, so it can be used wherever a seed is read.
+#[derive(Clone)]
+pub struct Seed<const LAMBDA: usize>(SeedStorage<LAMBDA>);
+
+/// See [`Seed`]
+#[cfg(feature = "secure-mem")]
+type SeedStorage<const LAMBDA: usize> = secure_mem::SecureSeed<LAMBDA>;
+/// See [`Seed`]
+#[cfg(not(feature = "secure-mem"))]
+type SeedStorage<const LAMBDA: usize> = [u8; LAMBDA];
+
+impl<const LAMBDA: usize> Seed<LAMBDA> {
+    /// Copies `seed` into the storage.
+    /// The caller should zero its own copy afterwards.
+    pub fn new(seed: &[u8; LAMBDA]) -> Self {
+        #[cfg(feature = "secure-mem")]
+        let storage = secure_mem::SecureSeed::new(seed);
+        #[cfg(not(feature = "secure-mem"))]
+        let storage = *seed;
+        Self(storage)
+    }
+}
+
+impl<const LAMBDA: usize> From<[u8; LAMBDA]> for Seed<LAMBDA> {
+    fn from(seed: [u8; LAMBDA]) -> Self {
+        Self::new(&seed)
+    }
+}
+
+impl<const LAMBDA: usize> Deref for Seed<LAMBDA> {
+    type Target = [u8; LAMBDA];
+
+    fn deref(&self) -> &[u8; LAMBDA] {
+        &self.0
+    }
+}
+
+impl<const LAMBDA: usize> DerefMut for Seed<LAMBDA> {
+    fn deref_mut(&mut self) -> &mut [u8; LAMBDA] {
+        &mut self.0
+    }
+}
+
+/// Never prints the seed
+impl<const LAMBDA: usize> fmt::Debug for Seed<LAMBDA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Seed(..)")
+    }
+}
+
+/// `k` of a single party.
+///
+/// Only `seed` is secret. It is intentionally not serializable as a whole,
+/// so that the seed is not sent along with `public` by accident.
+#[derive(Clone)]
+pub struct PartyKey<const LAMBDA: usize> {
+    /// `$s^{(0)}_b$`
+    pub seed: Seed<LAMBDA>,
+    pub public: Arc<PublicShare<LAMBDA>>,
+}
+
+/// Per-level correction of `$V_{CW}$` in [`Dcf::gen_with_strategy`].
+///
+/// At level `i`, the path of `$\alpha$` keeps one side and loses the other.
+/// `$V_{CW}$` is computed so that the outputs of the lost side are cancelled,
+/// and the returned value is additionally folded into it,
+/// which makes every `x` leaving the path of `$\alpha$` to the lost side at level `i` output the value.
+///
+/// This is the extension point to prototype other FSS constructions on the same tree.
+/// [`BoundState`] is the strategy of the comparison function.
+pub trait CorrectionStrategy<const N: usize, const LAMBDA: usize> {
+    /// `i` is the index of the level from 0, which is also the index of the bit of `$\alpha$` in [`Msb0`].
+    /// `alpha_i` is the bit. The lost side is left if it is 1 and right if it is 0.
+    fn v_cw_correction(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        i: usize,
+        alpha_i: bool,
+    ) -> Option<[u8; LAMBDA]>;
+
+    /// Folded into `$CW^{(n + 1)}$`, which makes `$\alpha$` itself output the value.
+    ///
+    /// None by default, i.e., `$\alpha$` outputs 0.
+    fn leaf_correction(&self, _f: &CmpFn<N, LAMBDA>) -> Option<[u8; LAMBDA]> {
+        None
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize> CorrectionStrategy<N, LAMBDA> for BoundState {
+    fn v_cw_correction(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        _i: usize,
+        alpha_i: bool,
+    ) -> Option<[u8; LAMBDA]> {
+        match (self, alpha_i) {
+            // Lose left
+            (BoundState::LtBeta | BoundState::Leq, true) => Some(f.beta),
+            // Lose right
+            (BoundState::GtBeta | BoundState::Geq, false) => Some(f.beta),
+            _ => None,
+        }
+    }
+
+    fn leaf_correction(&self, f: &CmpFn<N, LAMBDA>) -> Option<[u8; LAMBDA]> {
+        match self {
+            BoundState::Leq | BoundState::Geq => Some(f.beta),
+            BoundState::LtBeta | BoundState::GtBeta => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum BoundState {
+    /// `$f(x) = \beta$` iff. `$x < \alpha$`.
+    ///
+    /// This is the preference in the paper.
+    LtBeta,
+    /// `$f(x) = \beta$` iff. `$x > \alpha$`
+    GtBeta,
+    /// `$f(x) = \beta$` iff. `$x \le \alpha$`
+    Leq,
+    /// `$f(x) = \beta$` iff. `$x \ge \alpha$`
+    Geq,
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+
+    const ALPHAS: &[&[u8; 16]] = &[
+        b"K\xa9W\xf5\xdd\x05\xe9\xfc?\x04\xf6\xfbUo\xa8C",
+        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csU",
+        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csV",
+        b"\xc2GK\xda\xc6\xbb\x99\x98Fq\"f\xb7\x8csW",
+        b"\xef\x96\x97\xd7\x8f\x8a\xa4AP\n\xb35\xb5k\xff\x97",
+    ];
+    const BETA: &[u8; 16] = b"\x03\x11\x97\x12C\x8a\xe9#\x81\xa8\xde\xa8\x8f \xc0\xbb";
+
+    #[test]
+    fn test_dcf_gen_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let mut k0 = k.clone();
+        k0.s0s = vec![k0.s0s[0]];
+        let mut k1 = k.clone();
+        k1.s0s = vec![k1.s0s[1]];
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        ys1 = vec![BETA.to_owned(), BETA.to_owned(), [0; 16], [0; 16], [0; 16]];
+        assert_eq!(ys0, ys1);
+    }
+
+    #[cfg(feature = "chacha")]
+    #[test]
+    fn test_dcf_gen_then_eval_chacha20_ok() {
+        let prg = crate::prg::ChaCha20Prg::new();
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        assert_eq!(dcf.supported_params().unwrap().check(16), Ok(()));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.split();
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        ys1 = vec![BETA.to_owned(), BETA.to_owned(), [0; 16], [0; 16], [0; 16]];
+        assert_eq!(ys0, ys1);
+    }
+
+    #[test]
+    fn test_dcf_gen_then_eval_combine_all_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.split();
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        let ys = combine_all(&ys0, &ys1);
+        assert_eq!(
+            ys,
+            vec![BETA.to_owned(), BETA.to_owned(), [0; 16], [0; 16], [0; 16]]
+        );
+        assert_eq!(combine(&ys0[0], &ys1[0]), ys[0]);
+    }
+
+    #[test]
+    fn test_dcf_gen_leq_geq_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        [
+            (BoundState::Leq, [true, true, true, false, false]),
+            (BoundState::Geq, [false, false, true, true, true]),
+            (BoundState::LtBeta, [true, true, false, false, false]),
+            (BoundState::GtBeta, [false, false, false, true, true]),
+        ]
+        .into_iter()
+        .for_each(|(bound, hits)| {
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            let k = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+            let ks = k.split();
+            let [mut ys0, ys1] = [false, true].map(|b| {
+                let kb = &ks[b as usize];
+                let mut ys = vec![[0; 16]; ALPHAS.len()];
+                dcf.eval(b, kb, ALPHAS, &mut ys.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                ys
+            });
+            ys0.iter_mut()
+                .zip(ys1.iter())
+                .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+            let ys_expected: Vec<[u8; 16]> = hits
+                .iter()
+                .map(|&hit| if hit { BETA.to_owned() } else { [0; 16] })
+                .collect();
+            assert_eq!(ys0, ys_expected);
+            ALPHAS
+                .iter()
+                .zip(ys0.iter())
+                .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x, bound)));
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_leq_then_eval_arith_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(prg);
+        let xs: Vec<[u8; 1]> = (0..=255).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        // Including the boundaries of the domain
+        [0u8, 1, 128, 254, 255].into_iter().for_each(|alpha| {
+            let f = CmpFn {
+                alpha: [alpha],
+                beta: thread_rng().gen(),
+            };
+            [BoundState::Leq, BoundState::Geq]
+                .into_iter()
+                .for_each(|bound| {
+                    let s0s: [[u8; 16]; 2] = thread_rng().gen();
+                    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+                    let ks = k.split();
+                    let [mut ys0, ys1] = [false, true].map(|b| {
+                        let kb = &ks[b as usize];
+                        let mut ys = vec![[0; 16]; xs.len()];
+                        dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                            .unwrap();
+                        ys
+                    });
+                    ys0.iter_mut()
+                        .zip(ys1.iter())
+                        .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+                    xs.iter()
+                        .zip(ys0.iter())
+                        .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x, bound)));
+                });
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_gt_beta_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta).unwrap();
+        let mut k0 = k.clone();
+        k0.s0s = vec![k0.s0s[0]];
+        let mut k1 = k.clone();
+        k1.s0s = vec![k1.s0s[1]];
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        ys1 = vec![[0; 16], [0; 16], [0; 16], BETA.to_owned(), BETA.to_owned()];
+        assert_eq!(ys0, ys1);
+    }
+
+    /// Gen-then-eval with `N = 32`, i.e., 256 levels, at random `$\alpha$`s and the boundaries
+    fn check_n32_gen_then_eval<const LAMBDA: usize, PrgT>(dcf: DcfImpl<32, LAMBDA, PrgT>)
+    where
+        PrgT: Prg<LAMBDA>,
+    {
+        // `x + delta` in big-endian modulo `$2^{256}$`
+        let add = |x: &[u8; 32], delta: i8| {
+            let mut x = *x;
+            for byte in x.iter_mut().rev() {
+                let (res, carry) = if delta > 0 {
+                    byte.overflowing_add(1)
+                } else {
+                    byte.overflowing_sub(1)
+                };
+                *byte = res;
+                if !carry {
+                    break;
+                }
+            }
+            x
+        };
+        let alphas: Vec<[u8; 32]> = (0..3)
+            .map(|_| thread_rng().gen())
+            .chain([[0; 32], [0xff; 32], add(&[0; 32], 1)])
+            .collect();
+        for alpha in alphas.iter() {
+            let beta: [u8; LAMBDA] = std::array::from_fn(|_| thread_rng().gen());
+            let f = CmpFn {
+                alpha: *alpha,
+                beta,
+            };
+            let mut xs: Vec<[u8; 32]> = (0..20).map(|_| thread_rng().gen()).collect();
+            xs.extend([*alpha, add(alpha, 1), add(alpha, -1), [0; 32], [0xff; 32]]);
+            let xs: Vec<&[u8; 32]> = xs.iter().collect();
+            [BoundState::LtBeta, BoundState::GtBeta]
+                .into_iter()
+                .for_each(|bound| {
+                    let s0s: [[u8; LAMBDA]; 2] =
+                        std::array::from_fn(|_| std::array::from_fn(|_| thread_rng().gen()));
+                    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+                    assert_eq!(k.cws.len(), 256);
+                    let ks = k.split();
+                    let [ys0, ys1] = [false, true].map(|b| {
+                        let kb = &ks[b as usize];
+                        let mut ys = vec![[0; LAMBDA]; xs.len()];
+                        dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                            .unwrap();
+                        ys
+                    });
+                    xs.iter().enumerate().for_each(|(i, x)| {
+                        assert_eq!(xor(&[&ys0[i], &ys1[i]]), f.eval_plain(x, bound));
+                    });
+                });
+        }
+    }
+
+    #[test]
+    fn test_dcf_n32_lambda16_gen_then_eval_ok() {
+        check_n32_gen_then_eval(DcfImpl::<32, 16, _>::new(Aes256HirosePrg::new(KEYS)));
+    }
+
+    #[test]
+    fn test_dcf_n32_lambda32_gen_then_eval_ok() {
+        let keys: [[u8; 32]; 4] = thread_rng().gen();
+        let prg = Aes256HirosePrg::<32, 4>::new(std::array::from_fn(|i| &keys[i]));
+        check_n32_gen_then_eval(DcfImpl::<32, 32, _>::new(prg));
+    }
+
+    fn check_n2_lambda64_gen_then_eval<G: Group<64>>() {
+        let keys: [[u8; 32]; 8] = thread_rng().gen();
+        let prg = Aes256HirosePrg::<64, 8>::new(std::array::from_fn(|i| &keys[i]));
+        let dcf = DcfImpl::<2, 64, _, G>::new(prg);
+        let alpha: u16 = thread_rng().gen();
+        let f = CmpFn {
+            alpha: alpha.to_be_bytes(),
+            beta: std::array::from_fn(|_| thread_rng().gen()),
+        };
+        let xs: Vec<[u8; 2]> = (0..200)
+            .map(|_| thread_rng().gen::<u16>())
+            .chain([
+                0,
+                u16::MAX,
+                alpha,
+                alpha.wrapping_add(1),
+                alpha.wrapping_sub(1),
+            ])
+            .map(|x| x.to_be_bytes())
+            .collect();
+        let xs: Vec<&[u8; 2]> = xs.iter().collect();
+        [BoundState::LtBeta, BoundState::GtBeta]
+            .into_iter()
+            .for_each(|bound| {
+                let s0s: [[u8; 64]; 2] =
+                    std::array::from_fn(|_| std::array::from_fn(|_| thread_rng().gen()));
+                let k = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+                let ks = k.split();
+                let [mut ys0, ys1] = [false, true].map(|b| {
+                    let kb = &ks[b as usize];
+                    let mut ys = vec![[0; 64]; xs.len()];
+                    dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                        .unwrap();
+                    ys
+                });
+                ys0.iter_mut()
+                    .zip(ys1.iter())
+                    .for_each(|(y0, y1)| G::add(y0, y1));
+                xs.iter()
+                    .zip(ys0.iter())
+                    .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x, bound)));
+            });
+    }
+
+    #[test]
+    fn test_dcf_n2_lambda64_gen_then_eval_ok() {
+        check_n2_lambda64_gen_then_eval::<XorGroup>();
+        check_n2_lambda64_gen_then_eval::<ArithGroup>();
+    }
+
+    #[test]
+    fn test_share_reinterpret_group_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf_xor = DcfImpl::<2, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf_xor
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap();
+
+        let k_gf = k
+            .clone()
+            .reinterpret_group::<XorGroup, Gf256LaneGroup>()
+            .unwrap();
+        let dcf_gf = DcfImpl::<2, 16, _, Gf256LaneGroup>::new(Aes256HirosePrg::new(KEYS));
+        let xs: Vec<[u8; 2]> = (0..100)
+            .map(|_| thread_rng().gen())
+            .chain([f.alpha])
+            .collect();
+        let xs: Vec<&[u8; 2]> = xs.iter().collect();
+        let ks = k_gf.split();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf_gf
+                .eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| Gf256LaneGroup::add(y0, y1));
+        xs.iter()
+            .zip(ys0.iter())
+            .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x, BoundState::LtBeta)));
+
+        assert!(k
+            .clone()
+            .reinterpret_group::<ArithGroup, ArithGroup>()
+            .is_ok());
+        assert_eq!(
+            k.clone().reinterpret_group::<XorGroup, ArithGroup>().err(),
+            Some(DcfError::IncompatibleGroups {
+                from: std::any::type_name::<XorGroup>(),
+                to: std::any::type_name::<ArithGroup>(),
+            })
+        );
+        assert!(k.reinterpret_group::<ArithGroup, XorGroup>().is_err());
+    }
+
+    #[test]
+    fn test_dcf_gen_fixed_bits_then_eval_same_as_full() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<4, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        // Values are known to be < 2^20, so the leading 12 bits are fixed to 0
+        let f = CmpFn {
+            alpha: (thread_rng().gen::<u32>() >> 13).to_be_bytes(),
+            beta: BETA.to_owned(),
+        };
+        let xs: Vec<[u8; 4]> = (0..100)
+            .map(|_| (thread_rng().gen::<u32>() >> 12).to_be_bytes())
+            .chain([f.alpha, (u32::from_be_bytes(f.alpha) + 1).to_be_bytes()])
+            .collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let k_full = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let k_reduced = dcf
+            .gen_fixed_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 12)
+            .unwrap();
+        assert_eq!(k_full.cws.len(), 32);
+        assert_eq!(k_reduced.cws.len(), 20);
+        let ys = [k_full, k_reduced].map(|k| {
+            let [k0, k1] = k.split();
+            let mut ys0 = vec![[0; 16]; xs.len()];
+            let mut ys1 = vec![[0; 16]; xs.len()];
+            dcf.eval(false, &k0, &xs, &mut ys0.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            dcf.eval(true, &k1, &xs, &mut ys1.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys0.iter_mut()
+                .zip(ys1.iter())
+                .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+            ys0
+        });
+        assert_eq!(ys[0], ys[1]);
+        xs.iter().zip(ys[1].iter()).for_each(|(x, y)| {
+            if x < &&f.alpha {
+                assert_eq!(y, BETA);
+            } else {
+                assert_eq!(y, &[0; 16]);
+            }
+        });
+    }
+
+    #[test]
+    fn test_share_into_party_keys() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let pks = k.clone().into_party_keys();
+        assert_eq!(pks.len(), 2);
+        // Indexing reads through the seed storage
+        assert_eq!(
+            [&pks[0].seed[..], &pks[1].seed[..]],
+            [&s0s[0][..], &s0s[1][..]]
+        );
+        assert!(Arc::ptr_eq(&pks[0].public, &pks[1].public));
+        assert_eq!(pks[0].public.cws.len(), k.cws.len());
+        assert_eq!(pks[0].public.cw_np1, k.cw_np1);
+        let public_bytes = bincode::serialize(pks[0].public.as_ref()).unwrap();
+        let public: PublicShare<16> = bincode::deserialize(&public_bytes).unwrap();
+        assert_eq!(public.cw_np1, k.cw_np1);
+    }
+
+    #[test]
+    fn test_share_bincode_from_local_buffer_ok() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        // Would not compile with `Deserialize<'static>` since the buffers are dropped at the end of the block
+        let (k_de, cw_de) = {
+            let buf: Vec<u8> = bincode::serialize(&k).unwrap();
+            let cw_buf: Vec<u8> = bincode::serialize(&k.cws[0]).unwrap();
+            let k_de: Share<16> = bincode::deserialize(&buf).unwrap();
+            let cw_de: Cw<16> = bincode::deserialize(&cw_buf).unwrap();
+            (k_de, cw_de)
+        };
+        assert!(k_de == k);
+        assert!(cw_de == k.cws[0]);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_share_zeroize() {
+        fn assert_zeroize_on_drop<T: zeroize::ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<Cw<16>>();
+        assert_zeroize_on_drop::<Share<16>>();
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let mut k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let mut cw = k.cws[0].clone();
+        cw.zeroize();
+        assert!(cw.s == [0; 16] && cw.v == [0; 16] && !cw.tl && !cw.tr);
+        assert_ne!(k.cw_np1, [0; 16]);
+        k.zeroize();
+        assert!(k.s0s.is_empty() && k.cws.is_empty());
+        assert_eq!((k.cw_np1, k.n_bytes), ([0; 16], 0));
+        // Moving the fields out still works with `Drop`
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.clone().split();
+        assert!(Share::unsplit(k0, k1) == k);
+        assert_eq!(k.into_party_keys().len(), 2);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_share_zeroizing_wipes_key_material() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let mut seed = zeroize::Zeroizing::new(s0s[0]);
+        let mut k =
+            zeroize::Zeroizing::new(dcf.gen(&f, [&seed, &s0s[1]], BoundState::LtBeta).unwrap());
+        let mut cw_np1 = zeroize::Zeroizing::new(k.cw_np1);
+        assert_ne!(*cw_np1, [0; 16]);
+        // What the drop of them does
+        seed.zeroize();
+        cw_np1.zeroize();
+        k.zeroize();
+        assert_eq!((*seed, *cw_np1), ([0; 16], [0; 16]));
+        assert!(k.s0s.is_empty() && k.cws.is_empty() && k.cw_np1 == [0; 16]);
+    }
+
+    #[test]
+    fn test_share_eq_after_serde_round_trip() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let k_bytes = Share::<16>::from_bytes::<2>(&k.to_bytes()).unwrap();
+        let k_bincode: Share<16> = bincode::deserialize(&bincode::serialize(&k).unwrap()).unwrap();
+        let k_json: Share<16> = serde_json::from_str(&serde_json::to_string(&k).unwrap()).unwrap();
+        [&k_bytes, &k_bincode, &k_json].iter().for_each(|k_de| {
+            assert!(**k_de == k);
+            assert!(bool::from(k_de.ct_eq(&k)));
+        });
+        let cw_de: Cw<16> = bincode::deserialize(&bincode::serialize(&k.cws[3]).unwrap()).unwrap();
+        assert!(cw_de == k.cws[3]);
+        assert!(k.cws[3] != k.cws[4]);
+
+        // Any difference, in the bytes or in the lengths
+        let mut k_diff = k.clone();
+        k_diff.s0s[1][15] ^= 1;
+        assert!(k_diff != k);
+        let mut k_diff = k.clone();
+        k_diff.cws[7].tr ^= true;
+        assert!(k_diff != k);
+        let mut k_diff = k.clone();
+        k_diff.cw_np1[0] ^= 0x80;
+        assert!(k_diff != k);
+        let mut k_diff = k.clone();
+        k_diff.s0s.pop();
+        assert!(k_diff != k);
+        let mut k_diff = k.clone();
+        k_diff.cws.pop();
+        assert!(k_diff != k);
+    }
+
+    #[test]
+    fn test_debug_redacts_key_material() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let dcf_debug = format!("{:?}", dcf);
+        assert!(dcf_debug.contains("Aes256HirosePrg") && dcf_debug.contains("XorGroup"));
+        let f = CmpFn {
+            alpha: [0x12, 0x34],
+            beta: [0xab; 16],
+        };
+        assert_eq!(
+            format!("{:?}", f),
+            format!("CmpFn {{ alpha: 1234, beta: {} }}", "ab".repeat(16))
+        );
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let k_debug = format!("{:?}", k);
+        let cw_debug = format!("{:?}", k.cws[0]);
+        assert!(k_debug.contains("cws: [..; 16]"));
+        assert!(k_debug.contains(&format!("cw_np1: {:?}..,", Hex(&k.cw_np1[..4]))));
+        assert!(cw_debug.contains(&format!("v: {:?},", Hex(&k.cws[0].v))));
+        let s_hex = format!("{:?}", Hex(&k.cws[0].s));
+        #[cfg(not(feature = "debug-secrets"))]
+        {
+            assert!(k_debug.contains(&format!(
+                "s0s: [{:?}.., {:?}..]",
+                Hex(&s0s[0][..4]),
+                Hex(&s0s[1][..4])
+            )));
+            assert!(cw_debug.contains(&format!("s: {}..,", &s_hex[..8])));
+            assert!(!cw_debug.contains(&format!("s: {}", s_hex)));
+        }
+        #[cfg(feature = "debug-secrets")]
+        {
+            assert!(k_debug.contains(&format!("s0s: [{:?}, {:?}]", Hex(&s0s[0]), Hex(&s0s[1]))));
+            assert!(cw_debug.contains(&format!("s: {},", s_hex)));
+        }
+    }
+
+    #[test]
+    fn test_serde_types_deserialize_owned() {
+        // `DeserializeOwned` is `for<'de> Deserialize<'de>`, which `Deserialize<'static>` alone does not satisfy
+        fn assert_deserialize_owned<T: serde::de::DeserializeOwned>() {}
+        assert_deserialize_owned::<Cw<16>>();
+        assert_deserialize_owned::<Share<16>>();
+        assert_deserialize_owned::<PublicShare<16>>();
+    }
+
+    #[test]
+    fn test_share_deserialize_bad_lengths_err() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        // The fields in the order of the serialized struct, with the arrays as vectors of any length
+        type Parts = (
+            Vec<Vec<u8>>,
+            Vec<(Vec<u8>, Vec<u8>, bool, bool)>,
+            Vec<u8>,
+            u64,
+            Option<Vec<u8>>,
+        );
+        let parts: Parts = (
+            k.s0s.iter().map(|s0| s0.to_vec()).collect(),
+            k.cws
+                .iter()
+                .map(|cw| (cw.s.to_vec(), cw.v.to_vec(), cw.tl, cw.tr))
+                .collect(),
+            k.cw_np1.to_vec(),
+            k.n_bytes as u64,
+            k.prg_check.map(|check| check.to_vec()),
+        );
+        let to_json = |parts: &Parts| {
+            let cws: Vec<_> = parts
+                .1
+                .iter()
+                .map(|(s, v, tl, tr)| serde_json::json!({"s": s, "v": v, "tl": tl, "tr": tr}))
+                .collect();
+            serde_json::json!({
+                "s0s": parts.0,
+                "cws": cws,
+                "cw_np1": parts.2,
+                "n_bytes": parts.3,
+                "prg_check": parts.4,
+            })
+        };
+        let k_seq: Share<16> = bincode::deserialize(&bincode::serialize(&parts).unwrap()).unwrap();
+        let k_map: Share<16> = serde_json::from_value(to_json(&parts)).unwrap();
+        assert!(k_seq == k && k_map == k);
+        // Truncated and oversized
+        [15, 17].into_iter().for_each(|len| {
+            let bad = vec![0xab; len];
+            (0..5).for_each(|field| {
+                let mut parts = parts.clone();
+                match field {
+                    0 => parts.0[1] = bad.clone(),
+                    1 => parts.1[3].0 = bad.clone(),
+                    2 => parts.1[5].1 = bad.clone(),
+                    3 => parts.2 = bad.clone(),
+                    _ => parts.4 = Some(bad.clone()),
+                }
+                let expected = format!("invalid length {}, expected 16 bytes", len);
+                let err_seq =
+                    bincode::deserialize::<Share<16>>(&bincode::serialize(&parts).unwrap())
+                        .err()
+                        .unwrap();
+                assert!(err_seq.to_string().contains(&expected), "{}", err_seq);
+                let err_map = serde_json::from_value::<Share<16>>(to_json(&parts))
+                    .err()
+                    .unwrap();
+                assert!(err_map.to_string().contains(&expected), "{}", err_map);
+            });
+            // The public part, which is what is sent to the parties
+            let mut public_parts = (parts.1.clone(), bad.clone());
+            let expected = format!("invalid length {}, expected 16 bytes", len);
+            let err = bincode::deserialize::<PublicShare<16>>(
+                &bincode::serialize(&public_parts).unwrap(),
+            )
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains(&expected), "{}", err);
+            public_parts = (parts.1.clone(), parts.2.clone());
+            public_parts.0[0].1 = bad.clone();
+            let err = bincode::deserialize::<PublicShare<16>>(
+                &bincode::serialize(&public_parts).unwrap(),
+            )
+            .err()
+            .unwrap();
+            assert!(err.to_string().contains(&expected), "{}", err);
+        });
+    }
+
+    #[test]
+    fn test_share_serde_json_from_short_lived_slice_ok() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let (k_de, public_de) = {
+            // Dropped before the deserialized values
+            let json = serde_json::to_vec(&k).unwrap();
+            let public_json =
+                serde_json::to_vec(k.clone().into_party_keys()[0].public.as_ref()).unwrap();
+            let k_de: Share<16> = serde_json::from_slice(&json[..]).unwrap();
+            let public_de: PublicShare<16> = serde_json::from_slice(&public_json[..]).unwrap();
+            (k_de, public_de)
+        };
+        assert!(k_de == k);
+        assert!(public_de.cws == k.cws);
+        assert_eq!(public_de.cw_np1, k.cw_np1);
+
+        let json = serde_json::to_string(&k).unwrap();
+        let json_dup = json.replacen("{", r#"{"n_bytes":2,"#, 1);
+        assert!(serde_json::from_str::<Share<16>>(&json_dup)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("duplicate field `n_bytes`"));
+        let json_unknown = json.replacen("{", r#"{"alpha":2,"#, 1);
+        assert!(serde_json::from_str::<Share<16>>(&json_unknown)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("unknown field `alpha`"));
+        let json_missing = json.replace(r#","n_bytes":2"#, "");
+        assert!(serde_json::from_str::<Share<16>>(&json_missing)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("missing field `n_bytes`"));
+    }
+
+    #[test]
+    fn test_share_from_bytes_other_n_err() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0x12, 0x34],
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let bytes = k.to_bytes();
+        assert_eq!(
+            Share::<16>::from_bytes::<4>(&bytes).err(),
+            Some(DcfError::DomainSizeMismatch {
+                expected: 4,
+                got: 2
+            })
+        );
+        let k_de = Share::<16>::from_bytes::<2>(&bytes).unwrap();
+        assert_eq!(k_de.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_share_is_canonical_encoding() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0x12, 0x34],
+            beta: BETA.to_owned(),
+        };
+        let bytes = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .to_bytes();
+        assert!(Share::<16>::is_canonical_encoding(&bytes));
+        // Trailing bytes
+        let mut bytes_trailing = bytes.clone();
+        bytes_trailing.push(0);
+        assert!(!Share::<16>::is_canonical_encoding(&bytes_trailing));
+        // Truncated
+        assert!(!Share::<16>::is_canonical_encoding(
+            &bytes[..bytes.len() - 1]
+        ));
+        // The length prefix of the first seed is changed, which must not panic
+        let mut bytes_len = bytes.clone();
+        bytes_len[1 + 8] = 15;
+        assert!(!Share::<16>::is_canonical_encoding(&bytes_len));
+        // A bool other than 0 and 1
+        let tl_pos = 1 + 8 + 2 * (8 + 16) + 8 + 2 * (8 + 16);
+        assert!(bytes[tl_pos] <= 1);
+        let mut bytes_bool = bytes.clone();
+        bytes_bool[tl_pos] = 2;
+        assert!(!Share::<16>::is_canonical_encoding(&bytes_bool));
+    }
+
+    /// Random share of any `LAMBDA`, since the layout does not depend on the shares being from gen
+    fn rand_share<const LAMBDA: usize>(seed_count: usize, n: usize) -> Share<LAMBDA> {
+        let rand_array = || std::array::from_fn(|_| thread_rng().gen());
+        Share {
+            s0s: (0..seed_count).map(|_| rand_array()).collect(),
+            cws: (0..n)
+                .map(|_| Cw {
+                    s: rand_array(),
+                    v: rand_array(),
+                    tl: thread_rng().gen(),
+                    tr: thread_rng().gen(),
+                })
+                .collect(),
+            cw_np1: rand_array(),
+            n_bytes: n.div_ceil(8),
+            prg_check: Some(rand_array()),
+        }
+    }
+
+    fn assert_compact_round_trip<const LAMBDA: usize, const N: usize>(k: &Share<LAMBDA>) {
+        let bytes = k.to_bytes_compact();
+        let n = k.cws.len();
+        assert_eq!(
+            bytes.len(),
+            10 + (k.s0s.len() + 2 * n + 1 + k.prg_check.is_some() as usize) * LAMBDA
+                + (2 * n).div_ceil(8)
+        );
+        assert!(bytes.len() < k.to_bytes().len());
+        let k_de = Share::<LAMBDA>::from_bytes::<N>(&bytes).unwrap();
+        assert!(&k_de == k);
+        assert!(Share::<LAMBDA>::is_canonical_encoding(&bytes));
+    }
+
+    #[test]
+    fn test_share_to_bytes_compact_then_from_ok() {
+        assert_compact_round_trip::<1, 1>(&rand_share(2, 8));
+        assert_compact_round_trip::<16, 2>(&rand_share(1, 13));
+        assert_compact_round_trip::<24, 3>(&rand_share(2, 24));
+        assert_compact_round_trip::<64, 16>(&rand_share(2, 128));
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0x12, 0x34],
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        assert_compact_round_trip::<16, 2>(&k);
+        // Without `prg_check`, which the length tells apart
+        let mut k_unchecked = k.clone();
+        k_unchecked.prg_check = None;
+        assert_compact_round_trip::<16, 2>(&k_unchecked);
+        let bytes = k.to_bytes_compact();
+        assert_eq!(
+            Share::<16>::from_bytes::<4>(&bytes).err(),
+            Some(DcfError::DomainSizeMismatch {
+                expected: 4,
+                got: 2
+            })
+        );
+        // Truncated, trailing, and a count larger than the bytes
+        let mut bytes_count = bytes.clone();
+        bytes_count[6..10].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut bytes_trailing = bytes.clone();
+        bytes_trailing.push(0);
+        // An unused high bit of the packed control bits, which are before `cw_np1` and `prg_check`
+        let mut k_odd = k.clone();
+        k_odd.cws.pop();
+        let mut bytes_odd = k_odd.to_bytes_compact();
+        let t_last = bytes_odd.len() - 2 * 16 - 1;
+        bytes_odd[t_last] |= 0x80;
+        [
+            &bytes[..bytes.len() - 1],
+            &bytes[..5],
+            &bytes_count,
+            &bytes_trailing,
+            &bytes_odd,
+        ]
+        .iter()
+        .for_each(|bytes| {
+            assert_eq!(
+                Share::<16>::from_bytes::<2>(bytes).err(),
+                Some(DcfError::MalformedBytes)
+            );
+            assert!(!Share::<16>::is_canonical_encoding(bytes));
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "too many seeds for the compact layout")]
+    fn test_share_to_bytes_compact_too_many_seeds_panics() {
+        let _ = rand_share::<1>(256, 1).to_bytes_compact();
+    }
+
+    #[test]
+    fn test_share_check_invariants() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0x00, 0x34],
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        assert_eq!(k.check_invariants::<2>(), Ok(()));
+        let mut k0 = k.clone();
+        k0.s0s.truncate(1);
+        assert_eq!(k0.check_invariants::<2>(), Ok(()));
+        let k_fixed = dcf
+            .gen_fixed_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 8)
+            .unwrap();
+        assert_eq!(k_fixed.check_invariants::<2>(), Ok(()));
+
+        let mut k_no_seed = k.clone();
+        k_no_seed.s0s.clear();
+        assert_eq!(
+            k_no_seed.check_invariants::<2>(),
+            Err(DcfError::InvalidSeedCount { got: 0 })
+        );
+        let mut k_3_seeds = k.clone();
+        k_3_seeds.s0s.push(s0s[0]);
+        assert_eq!(
+            k_3_seeds.check_invariants::<2>(),
+            Err(DcfError::InvalidSeedCount { got: 3 })
+        );
+        assert_eq!(
+            k.check_invariants::<4>(),
+            Err(DcfError::DomainSizeMismatch {
+                expected: 4,
+                got: 2
+            })
+        );
+        let mut k_more_cws = k.clone();
+        k_more_cws.cws.push(k.cws[0].clone());
+        assert_eq!(
+            k_more_cws.check_invariants::<2>(),
+            Err(DcfError::CwCountOutOfRange { got: 17, max: 16 })
+        );
+        let mut k_no_cws = k.clone();
+        k_no_cws.cws.clear();
+        assert_eq!(
+            k_no_cws.check_invariants::<2>(),
+            Err(DcfError::CwCountOutOfRange { got: 0, max: 16 })
+        );
+    }
+
+    #[test]
+    fn test_share_to_bytes_crc32_then_from_ok() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0x12, 0x34],
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let bytes = k.to_bytes_crc32();
+        assert_eq!(bytes.len(), k.to_bytes().len() + 4);
+        let k_de = Share::<16>::from_bytes::<2>(&bytes).unwrap();
+        assert_eq!(k_de.to_bytes(), k.to_bytes());
+        assert!(Share::<16>::is_canonical_encoding(&bytes));
+        // The ones without the CRC32 still parse
+        assert!(Share::<16>::from_bytes::<2>(&k.to_bytes()).is_ok());
+        // Every flipped payload byte is detected
+        (1..bytes.len() - 4).for_each(|i| {
+            let mut bytes_corrupt = bytes.clone();
+            bytes_corrupt[i] ^= 0x01;
+            assert_eq!(
+                Share::<16>::from_bytes::<2>(&bytes_corrupt).err(),
+                Some(DcfError::CorruptData)
+            );
+        });
+        assert_eq!(
+            Share::<16>::from_bytes::<2>(&[2]).err(),
+            Some(DcfError::MalformedBytes)
+        );
+    }
+
+    #[test]
+    fn test_share_from_bytes_without_flag_ok() {
+        // Written by `to_bytes` before the format flags, i.e., bincode of 2 seeds, 1 correlation word, and `n_bytes = 1`
+        let bytes: Vec<u8> = [
+            &[2, 0, 0, 0, 0, 0, 0, 0][..],
+            &[16, 0, 0, 0, 0, 0, 0, 0],
+            &[0x11; 16],
+            &[16, 0, 0, 0, 0, 0, 0, 0],
+            &[0x22; 16],
+            &[1, 0, 0, 0, 0, 0, 0, 0],
+            &[16, 0, 0, 0, 0, 0, 0, 0],
+            &[0x33; 16],
+            &[16, 0, 0, 0, 0, 0, 0, 0],
+            &[0x44; 16],
+            &[1, 0],
+            &[16, 0, 0, 0, 0, 0, 0, 0],
+            &[0x55; 16],
+            &[1, 0, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+        let k = Share {
+            s0s: vec![[0x11; 16], [0x22; 16]],
+            cws: vec![Cw {
+                s: [0x33; 16],
+                v: [0x44; 16],
+                tl: true,
+                tr: false,
+            }],
+            cw_np1: [0x55; 16],
+            n_bytes: 1,
+            prg_check: None,
+        };
+        assert!(Share::<16>::from_bytes::<1>(&bytes).unwrap() == k);
+        assert!(Share::<16>::is_canonical_encoding(&bytes));
+        // With 1 seed, whose first byte was the CRC32 flag
+        let [k0, _] = k.clone().split();
+        let bytes0 = [&[1][..], &bytes[1..32], &bytes[56..]].concat();
+        assert!(Share::<16>::from_bytes::<1>(&bytes0).unwrap() == k0);
+        // The flagged formats still parse as themselves
+        [k.to_bytes(), k.to_bytes_crc32(), k.to_bytes_compact()]
+            .iter()
+            .for_each(|bytes| assert!(Share::<16>::from_bytes::<1>(bytes).unwrap() == k));
+    }
+
+    #[test]
+    fn test_share_to_bytes_padded_then_from_ok() {
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let k16 = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS))
+            .gen(
+                &CmpFn {
+                    alpha: ALPHAS[2].to_owned(),
+                    beta: BETA.to_owned(),
+                },
+                [&s0s[0], &s0s[1]],
+                BoundState::LtBeta,
+            )
+            .unwrap();
+        // A smaller domain, which is hidden by the same padded size
+        let k4 = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS))
+            .gen(
+                &CmpFn {
+                    alpha: [1, 2, 3, 4],
+                    beta: BETA.to_owned(),
+                },
+                [&s0s[0], &s0s[1]],
+                BoundState::LtBeta,
+            )
+            .unwrap();
+        let bytes16 = k16.to_bytes_padded(8192).unwrap();
+        let bytes4 = k4.to_bytes_padded(8192).unwrap();
+        assert_eq!((bytes16.len(), bytes4.len()), (8192, 8192));
+        let k16_de = Share::<16>::from_bytes_padded::<16>(&bytes16).unwrap();
+        assert_eq!(k16_de.to_bytes(), k16.to_bytes());
+        let k4_de = Share::<16>::from_bytes_padded::<4>(&bytes4).unwrap();
+        assert_eq!(k4_de.to_bytes(), k4.to_bytes());
+
+        let len = 8 + k16.to_bytes().len();
+        assert_eq!(
+            k16.to_bytes_padded(len - 1).unwrap_err(),
+            DcfError::ShareTooLong {
+                len,
+                target_len: len - 1
+            }
+        );
+        let mut bytes_dirty = bytes4.clone();
+        *bytes_dirty.last_mut().unwrap() = 1;
+        assert_eq!(
+            Share::<16>::from_bytes_padded::<4>(&bytes_dirty).err(),
+            Some(DcfError::MalformedBytes)
+        );
+        assert_eq!(
+            Share::<16>::from_bytes_padded::<4>(&bytes4[..16]).err(),
+            Some(DcfError::MalformedBytes)
+        );
+    }
+
+    #[test]
+    fn test_share_write_framed_then_read_framed_over_pipe_ok() {
+        let dcf = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let ks: Vec<Share<16>> = ALPHAS[..3]
+            .iter()
+            .map(|alpha| {
+                let s0s: [[u8; 16]; 2] = thread_rng().gen();
+                let f = CmpFn {
+                    alpha: **alpha,
+                    beta: BETA.to_owned(),
+                };
+                dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap()
+            })
+            .collect();
+        let (mut reader, writer) = io::pipe().unwrap();
+        let ks_sent = ks.clone();
+        let sender = std::thread::spawn(move || {
+            let mut writer = writer;
+            ks_sent
+                .iter()
+                .for_each(|k| k.write_framed(&mut writer).unwrap());
+        });
+        ks.iter().for_each(|k| {
+            let k_de = Share::<16>::read_framed::<_, 16>(&mut reader)
+                .unwrap()
+                .unwrap();
+            assert_eq!(k_de.to_bytes(), k.to_bytes());
+        });
+        sender.join().unwrap();
+        // The writer is dropped, so the stream ends at a frame boundary
+        assert!(Share::<16>::read_framed::<_, 16>(&mut reader)
+            .unwrap()
+            .is_none());
+
+        let mut bytes = vec![];
+        ks[0].write_framed(&mut bytes).unwrap();
+        [1, 3, bytes.len() - 1].iter().for_each(|&len| {
+            assert_eq!(
+                Share::<16>::read_framed::<_, 16>(&bytes[..len]).err(),
+                Some(DcfError::TruncatedFrame)
+            );
+        });
+        let mut bytes_bad_magic = bytes.clone();
+        bytes_bad_magic[0] = 0;
+        assert_eq!(
+            Share::<16>::read_framed::<_, 16>(&bytes_bad_magic[..]).err(),
+            Some(DcfError::MalformedBytes)
+        );
+        assert_eq!(
+            Share::<16>::read_framed::<_, 4>(&bytes[..]).err(),
+            Some(DcfError::DomainSizeMismatch {
+                expected: 4,
+                got: 16
+            })
+        );
+    }
+
+    #[test]
+    fn test_dcf_gen_then_eval_party_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let pks = k.into_party_keys();
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval_party(
+            false,
+            &pks[0],
+            ALPHAS,
+            &mut ys0.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        dcf.eval_party(
+            true,
+            &pks[1],
+            ALPHAS,
+            &mut ys1.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
         ys0.iter_mut()
             .zip(ys1.iter())
             .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
@@ -394,8 +3567,507 @@ mod tests {
         assert_eq!(ys0, ys1);
     }
 
+    /// Same as [`BoundState::LtBeta`]
+    struct LtStrategy;
+
+    impl CorrectionStrategy<16, 16> for LtStrategy {
+        fn v_cw_correction(&self, f: &CmpFn<16, 16>, _i: usize, alpha_i: bool) -> Option<[u8; 16]> {
+            alpha_i.then_some(f.beta)
+        }
+    }
+
+    /// Correct both sides, which gives `$f(x) = \beta$` iff. `$x \neq \alpha$`
+    struct NeStrategy;
+
+    impl CorrectionStrategy<16, 16> for NeStrategy {
+        fn v_cw_correction(
+            &self,
+            f: &CmpFn<16, 16>,
+            _i: usize,
+            _alpha_i: bool,
+        ) -> Option<[u8; 16]> {
+            Some(f.beta)
+        }
+    }
+
+    #[test]
+    fn test_dcf_gen_with_strategy_same_as_bound() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let k_strategy = dcf
+            .gen_with_strategy(&f, [&s0s[0], &s0s[1]], &LtStrategy)
+            .unwrap();
+        assert_eq!(
+            bincode::serialize(&k).unwrap(),
+            bincode::serialize(&k_strategy).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dcf_gen_with_custom_strategy_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let strategy: &dyn CorrectionStrategy<16, 16> = &NeStrategy;
+        let k = dcf
+            .gen_with_strategy(&f, [&s0s[0], &s0s[1]], strategy)
+            .unwrap();
+        let [k0, k1] = k.split();
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        ys1 = vec![
+            BETA.to_owned(),
+            BETA.to_owned(),
+            [0; 16],
+            BETA.to_owned(),
+            BETA.to_owned(),
+        ];
+        assert_eq!(ys0, ys1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_dcf_prg_call_count() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        // 2 parties for each of the 128 levels
+        assert_eq!(dcf.prg_call_count(), 2 * 128);
+        dcf.reset_prg_call_count();
+        assert_eq!(dcf.prg_call_count(), 0);
+        let mut ys = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k, ALPHAS, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        // The root expansion is shared by the batch
+        assert_eq!(dcf.prg_call_count(), 1 + ALPHAS.len() as u64 * 127);
+    }
+
+    #[test]
+    fn test_dcf_eval_adaptive_same_as_eval() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 16]> = (0..100).map(|_| thread_rng().gen()).collect();
+        // 128 levels and 16 bytes make 2048 per input
+        [1, 100].iter().for_each(|&batch_size| {
+            let xs: Vec<&[u8; 16]> = xs[..batch_size].iter().collect();
+            let mut ys_expected = vec![[0; 16]; batch_size];
+            dcf.eval(
+                false,
+                &k,
+                &xs,
+                &mut ys_expected.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+            let mut ys = vec![[0; 16]; batch_size];
+            dcf.eval_adaptive(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            assert_eq!(ys, ys_expected);
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_and_eval_err_instead_of_panic() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<4, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0, 0x12, 0x34, 0x56],
+            beta: BETA.to_owned(),
+        };
+        let k = dcf
+            .gen_fixed_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 0)
+            .unwrap();
+        assert!(k == dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap());
+        assert!(dcf
+            .gen_fixed_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 11)
+            .is_ok());
+        // The first 1 of `alpha` is the bit 11
+        [12, 32, 100].iter().for_each(|&leading_fixed_bits| {
+            assert_eq!(
+                dcf.gen_fixed_bits(
+                    &f,
+                    [&s0s[0], &s0s[1]],
+                    BoundState::LtBeta,
+                    leading_fixed_bits
+                )
+                .err(),
+                Some(DcfError::InvalidFixedBits { leading_fixed_bits })
+            );
+        });
+
+        let xs = [[0, 0x12, 0x34, 0x55], [0, 0x12, 0x34, 0x56]];
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let mut ys = vec![[0; 16]; 2];
+        let [k0, _] = k.clone().split();
+        dcf.eval(false, &k0, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        let mut ys_expected = vec![[0; 16]; 2];
+        dcf.eval(
+            false,
+            &k0,
+            &xs,
+            &mut ys_expected.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(ys, ys_expected);
+
+        let mut k_other_n = k0.clone();
+        k_other_n.n_bytes = 16;
+        let mut k_no_seed = k0.clone();
+        k_no_seed.s0s.clear();
+        let mut k_no_cws = k0.clone();
+        k_no_cws.cws.clear();
+        [
+            (
+                &k_other_n,
+                DcfError::DomainSizeMismatch {
+                    expected: 4,
+                    got: 16,
+                },
+            ),
+            (&k_no_seed, DcfError::InvalidSeedCount { got: 0 }),
+            (&k_no_cws, DcfError::CwCountOutOfRange { got: 0, max: 32 }),
+        ]
+        .iter()
+        .for_each(|(kb, e)| {
+            let mut ys = vec![[0; 16]; 2];
+            assert_eq!(
+                dcf.eval(false, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                    .err()
+                    .as_ref(),
+                Some(e)
+            );
+            assert_eq!(ys, vec![[0; 16]; 2]);
+        });
+        let mut ys = [[0; 16]; 1];
+        assert_eq!(
+            dcf.eval(false, &k0, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .err(),
+            Some(DcfError::MismatchedOutputBuffer {
+                expected: 2,
+                got: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_dcf_eval_party_err() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let k_party = k.clone().into_party_keys().remove(0);
+        let xs: Vec<[u8; 4]> = (0..3).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let mut ys = vec![[0; 16]; 3];
+        dcf.eval_party(false, &k_party, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        let [k0, _] = k.split();
+        let mut ys_expected = vec![[0; 16]; 3];
+        dcf.eval(
+            false,
+            &k0,
+            &xs,
+            &mut ys_expected.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(ys, ys_expected);
+
+        let mut ys = [[0; 16]; 2];
+        assert_eq!(
+            dcf.eval_party(false, &k_party, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .err(),
+            Some(DcfError::MismatchedOutputBuffer {
+                expected: 3,
+                got: 2
+            })
+        );
+        let mut public = (*k_party.public).clone();
+        public.cws.extend(public.cws.clone());
+        let mut k_long = k_party.clone();
+        k_long.public = Arc::new(public);
+        let mut ys = vec![[0; 16]; 3];
+        assert_eq!(
+            dcf.eval_party(false, &k_long, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .err(),
+            Some(DcfError::CwCountOutOfRange { got: 64, max: 32 })
+        );
+        assert_eq!(ys, vec![[0; 16]; 3]);
+    }
+
+    /// Only the required methods of [`Dcf`], forwarded to [`DcfImpl`]
+    struct MinimalDcf(DcfImpl<4, 16, Aes256HirosePrg<16, 2>>);
+
+    impl Dcf<4, 16> for MinimalDcf {
+        fn gen(
+            &self,
+            f: &CmpFn<4, 16>,
+            s0s: [&[u8; 16]; 2],
+            bound: BoundState,
+        ) -> Result<Share<16>, DcfError> {
+            self.0.gen(f, s0s, bound)
+        }
+
+        fn eval(
+            &self,
+            b: bool,
+            k: &Share<16>,
+            xs: &[&[u8; 4]],
+            ys: &mut [&mut [u8; 16]],
+        ) -> Result<(), DcfError> {
+            self.0.eval(b, k, xs, ys)
+        }
+    }
+
+    #[test]
+    fn test_dcf_minimal_impl_as_dyn_ok() {
+        let minimal = MinimalDcf(DcfImpl::new(Aes256HirosePrg::new(KEYS)));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        assert_eq!(
+            minimal
+                .gen_with_strategy(&f, [&s0s[0], &s0s[1]], &BoundState::LtBeta)
+                .err(),
+            Some(DcfError::UnsupportedStrategy)
+        );
+        let f_fixed = CmpFn {
+            alpha: [0, 0, 0x12, 0x34],
+            beta: BETA.to_owned(),
+        };
+        assert_eq!(
+            minimal
+                .gen_fixed_bits(&f_fixed, [&s0s[0], &s0s[1]], BoundState::LtBeta, 20)
+                .err(),
+            Some(DcfError::InvalidFixedBits {
+                leading_fixed_bits: 20
+            })
+        );
+        let [k0_fixed, _] = minimal
+            .gen_fixed_bits(&f_fixed, [&s0s[0], &s0s[1]], BoundState::LtBeta, 16)
+            .unwrap()
+            .split();
+        assert_eq!(k0_fixed.cws.len(), 32);
+        let dcfs: [&dyn Dcf<4, 16>; 2] = [&minimal, &minimal.0];
+        let ks = dcfs.map(|dcf| dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap());
+        assert!(ks[0] == ks[1]);
+        let k_party = ks[0].clone().into_party_keys().remove(0);
+        let [k0, _] = ks[0].clone().split();
+        let x = thread_rng().gen();
+        assert_eq!(
+            dcfs[0].eval_at(false, &ks[0], &x),
+            dcfs[0].eval_at(false, &k0, &x)
+        );
+        let mut y_party = [0; 16];
+        dcfs[0]
+            .eval_party(false, &k_party, &[&x], &mut [&mut y_party])
+            .unwrap();
+        assert_eq!(y_party, dcfs[0].eval_at(false, &k0, &x).unwrap());
+        assert_eq!(y_party, dcfs[1].eval_at(false, &k0, &x).unwrap());
+    }
+
+    #[test]
+    fn test_dcf_eval_unsplit_share_same_as_party_keys() {
+        let dcf = DcfImpl::<1, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: [0x12],
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let ks = k.clone().split();
+        let xs: Vec<[u8; 1]> = (0..=u8::MAX).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        for (b, kb) in [false, true].into_iter().zip(ks.iter()) {
+            let mut ys = vec![[0; 16]; xs.len()];
+            let mut ys_party = vec![[0; 16]; xs.len()];
+            dcf.eval(b, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            dcf.eval(b, kb, &xs, &mut ys_party.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            assert_eq!(ys, ys_party);
+            let mut ys_visit = vec![];
+            dcf.eval_full_visit(b, &k, |_, y| ys_visit.push(*y));
+            assert_eq!(ys_visit, ys_party);
+        }
+    }
+
+    #[test]
+    fn test_dcf_eval_malformed_share_err() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let mut k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        // Truncated over the wire
+        k.cws.clear();
+        assert_eq!(
+            dcf.eval(false, &k, &[ALPHAS[0]], &mut [&mut [0; 16]]),
+            Err(DcfError::CwCountOutOfRange { got: 0, max: 128 })
+        );
+    }
+
+    #[test]
+    fn test_dcf_eval_at_same_as_eval() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let ks = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 16]> = (0..20)
+            .map(|_| thread_rng().gen())
+            .chain(ALPHAS.iter().map(|alpha| **alpha))
+            .collect();
+        xs.iter().for_each(|x| {
+            let [y0, y1] = [false, true].map(|b| {
+                let kb = &ks[b as usize];
+                let mut y = [0; 16];
+                dcf.eval(b, kb, &[x], &mut [&mut y]).unwrap();
+                assert_eq!(dcf.eval_at(b, kb, x).unwrap(), y);
+                y
+            });
+            assert_eq!(xor(&[&y0, &y1]), f.eval_plain(x, BoundState::LtBeta));
+        });
+    }
+
+    #[test]
+    fn test_dcf_eval_same_as_full_path_walk() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let k_fixed = dcf
+            .gen_fixed_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 0)
+            .unwrap();
+        let xs: Vec<[u8; 16]> = (0..30)
+            .map(|_| thread_rng().gen())
+            .chain(ALPHAS.iter().map(|alpha| **alpha))
+            .collect();
+        let xs: Vec<&[u8; 16]> = xs.iter().collect();
+        [k, k_fixed].into_iter().for_each(|k| {
+            let ks = k.split();
+            [false, true].iter().for_each(|&b| {
+                let kb = &ks[b as usize];
+                let mut ys = vec![[0; 16]; xs.len()];
+                dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                // The walk before eval kept only the state of the last level, with the whole path in vectors
+                let n = kb.cws.len();
+                let fixed = 128 - n;
+                xs.iter().zip(ys.iter()).for_each(|(x, y)| {
+                    let mut ss = vec![kb.s0s[0]];
+                    let mut ts = vec![b];
+                    let mut v = [0; 16];
+                    for i in 1..n + 1 {
+                        let x_i = x.view_bits::<Msb0>()[fixed + i - 1];
+                        let (s, t) =
+                            dcf.eval_level(&ss[i - 1], ts[i - 1], &kb.cws[i - 1], x_i, &mut v);
+                        ss.push(s);
+                        ts.push(t);
+                    }
+                    dcf.eval_leaf(b, &ss[n], ts[n], &kb.cw_np1, &mut v);
+                    assert_eq!(y, &v);
+                });
+            });
+        });
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_dcf_eval_adaptive_serial_below_threshold() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let mut dcf = DcfImpl::<16, 16, _>::new(prg);
+        dcf.set_parallel_threshold(DEFAULT_PARALLEL_THRESHOLD);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 16]> = (0..100).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 16]> = xs.iter().collect();
+        let mut ys = vec![[0; 16]; xs.len()];
+        // 2048 per input, so 32 inputs reach the default threshold
+        dcf.eval_adaptive(
+            false,
+            &k,
+            &xs[..31],
+            &mut ys[..31].iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(dcf.serial_eval_count(), 1);
+        dcf.eval_adaptive(
+            false,
+            &k,
+            &xs[..32],
+            &mut ys[..32].iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(dcf.serial_eval_count(), 1);
+        dcf.set_parallel_threshold(usize::MAX);
+        dcf.eval_adaptive(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        assert_eq!(dcf.serial_eval_count(), 2);
+    }
+
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_dcf_gen_gt_beta_then_eval_ok() {
+    fn test_share_eval_cost_prg_calls_same_as_counted() {
         let prg = Aes256HirosePrg::new(KEYS);
         let dcf = DcfImpl::<16, 16, _>::new(prg);
         let s0s: [[u8; 16]; 2] = thread_rng().gen();
@@ -403,22 +4075,850 @@ mod tests {
             alpha: ALPHAS[2].to_owned(),
             beta: BETA.to_owned(),
         };
-        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta);
-        let mut k0 = k.clone();
-        k0.s0s = vec![k0.s0s[0]];
-        let mut k1 = k.clone();
-        k1.s0s = vec![k1.s0s[1]];
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: Vec<[u8; 16]> = (0..64).map(|_| thread_rng().gen()).collect();
+        [0, 1, 5, 64].iter().for_each(|&batch_size| {
+            let xs: Vec<&[u8; 16]> = xs[..batch_size].iter().collect();
+            let mut ys = vec![[0; 16]; batch_size];
+            dcf.reset_prg_call_count();
+            dcf.eval(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            assert_eq!(k.eval_cost(batch_size).prg_calls, dcf.prg_call_count());
+        });
+    }
+
+    #[test]
+    fn test_dcf_arith_gen_shared_beta_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _, ArithGroup>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let beta_shares: [u128; 2] = thread_rng().gen();
+        let k = dcf.gen_shared_beta(
+            ALPHAS[2],
+            &beta_shares[0].to_be_bytes(),
+            &beta_shares[1].to_be_bytes(),
+            [&s0s[0], &s0s[1]],
+            BoundState::LtBeta,
+        );
+        let [k0, k1] = k.split();
         let mut ys0 = vec![[0; 16]; ALPHAS.len()];
         let mut ys1 = vec![[0; 16]; ALPHAS.len()];
-        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>());
-        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>());
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        let ys: Vec<u128> = ys0
+            .iter()
+            .zip(ys1.iter())
+            .map(|(y0, y1)| u128::from_be_bytes(*y0).wrapping_add(u128::from_be_bytes(*y1)))
+            .collect();
+        let beta = beta_shares[0].wrapping_add(beta_shares[1]);
+        assert_eq!(ys, vec![beta, beta, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_dcf_arith_gen_gt_beta_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _, ArithGroup>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta).unwrap();
+        let [k0, k1] = k.split();
+        let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+        let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
         ys0.iter_mut()
             .zip(ys1.iter())
-            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+            .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
         ys1 = vec![[0; 16], [0; 16], [0; 16], BETA.to_owned(), BETA.to_owned()];
         assert_eq!(ys0, ys1);
     }
 
+    #[test]
+    fn test_dcf_gen_derived_seeds_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let masters: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let ks = [0, 1, u64::MAX].map(|key_index| {
+            let k =
+                dcf.gen_derived_seeds(&f, &masters[0], &masters[1], key_index, BoundState::LtBeta);
+            // The parties can derive their seeds without the dealer
+            assert_eq!(k.s0s[0], dcf.derive_seed(&masters[0], key_index));
+            assert_eq!(k.s0s[1], dcf.derive_seed(&masters[1], key_index));
+            let [k0, k1] = k.clone().split();
+            let mut ys0 = vec![[0; 16]; ALPHAS.len()];
+            let mut ys1 = vec![[0; 16]; ALPHAS.len()];
+            dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            for i in 0..ys0.len() {
+                xor_inplace(&mut ys0[i], &[&ys1[i]]);
+            }
+            assert_eq!(
+                ys0,
+                vec![BETA.to_owned(), BETA.to_owned(), [0; 16], [0; 16], [0; 16]]
+            );
+            k
+        });
+        for i in 0..ks.len() {
+            for j in i + 1..ks.len() {
+                assert_ne!(ks[i].s0s, ks[j].s0s);
+                assert_ne!(ks[i].cw_np1, ks[j].cw_np1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dcf_eval_be_ints_same_as_zero_extended() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<4, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: 0x1234u32.to_be_bytes(),
+            beta: BETA.to_owned(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let xs: [&[u8]; 4] = [&[], &[0x12, 0x33], &[0x12, 0x34], &[0, 0x01, 0x12, 0x34]];
+        let xs_full = [0u32, 0x1233, 0x1234, 0x011234].map(|x| x.to_be_bytes());
+        let mut ys = vec![[0; 16]; xs.len()];
+        let mut ys_full = vec![[0; 16]; xs.len()];
+        dcf.eval_be_ints(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(
+            false,
+            &k,
+            &xs_full.iter().collect::<Vec<_>>(),
+            &mut ys_full.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(ys, ys_full);
+        let err = dcf
+            .eval_be_ints(
+                false,
+                &k,
+                &[&[0; 4], &[0; 5]],
+                &mut [&mut [0; 16], &mut [0; 16]],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            DcfError::InputTooLong {
+                index: 1,
+                len: 5,
+                max: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_dcf_arith_eval_affine_then_reconstruct_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _, ArithGroup>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: 7u128.to_be_bytes(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let c = 3u128.to_be_bytes();
+        let d = 100u128;
+        let d0: u128 = thread_rng().gen();
+        let d_shares = [d0, d.wrapping_sub(d0)].map(|x| x.to_be_bytes());
+        let ks = k.split();
+        let ys = [0, 1].map(|i| {
+            let k = &ks[i];
+            let mut ys = vec![[0; 16]; ALPHAS.len()];
+            dcf.eval_affine(
+                i == 1,
+                k,
+                ALPHAS,
+                &c,
+                &d_shares[i],
+                &mut ys.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+            ys
+        });
+        let ys: Vec<u128> = ys[0]
+            .iter()
+            .zip(ys[1].iter())
+            .map(|(y0, y1)| u128::from_be_bytes(*y0).wrapping_add(u128::from_be_bytes(*y1)))
+            .collect();
+        assert_eq!(ys, vec![7 * 3 + 100, 7 * 3 + 100, 100, 100, 100]);
+    }
+
+    #[test]
+    fn test_dcf_eval_checked_with_other_prg_keys_err() {
+        let dcf = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let prg_check = dcf.prg_check();
+        let mut ys = vec![[0; 16]; ALPHAS.len()];
+        let mut ys_unchecked = vec![[0; 16]; ALPHAS.len()];
+        dcf.eval_checked(
+            false,
+            &k,
+            &prg_check,
+            ALPHAS,
+            &mut ys.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        dcf.eval(
+            false,
+            &k,
+            ALPHAS,
+            &mut ys_unchecked.iter_mut().collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(ys, ys_unchecked);
+
+        let other_keys: [[u8; 32]; 2] = thread_rng().gen();
+        let dcf_other =
+            DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new([&other_keys[0], &other_keys[1]]));
+        let res = dcf_other.eval_checked(
+            false,
+            &k,
+            &prg_check,
+            ALPHAS,
+            &mut ys.iter_mut().collect::<Vec<_>>(),
+        );
+        assert_eq!(res, Err(DcfError::PrgMismatch));
+    }
+
+    #[test]
+    fn test_dcf_eval_with_other_prg_keys_err() {
+        let dcf = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        assert_eq!(k.prg_check, Some(dcf.prg_check()));
+        let [k0, _] = k.clone().split();
+        let other_key = [0x5a; 32];
+        // Either key alone, or both swapped
+        [
+            [&other_key, KEYS[1]],
+            [KEYS[0], &other_key],
+            [KEYS[1], KEYS[0]],
+        ]
+        .into_iter()
+        .for_each(|keys| {
+            let dcf_other = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(keys));
+            assert_ne!(dcf_other.prg_check(), dcf.prg_check());
+            let mut ys = vec![[0; 16]; ALPHAS.len()];
+            assert_eq!(
+                dcf_other.eval(false, &k0, ALPHAS, &mut ys.iter_mut().collect::<Vec<_>>()),
+                Err(DcfError::PrgMismatch)
+            );
+            assert_eq!(
+                dcf_other.eval_at(false, &k0, ALPHAS[0]),
+                Err(DcfError::PrgMismatch)
+            );
+            // The key serialized by the dealer carries the fingerprint
+            let k0_de = Share::from_bytes::<16>(&k0.to_bytes()).unwrap();
+            assert_eq!(
+                dcf_other.eval_at(false, &k0_de, ALPHAS[0]),
+                Err(DcfError::PrgMismatch)
+            );
+        });
+
+        // Every key of a larger `LAMBDA`, which has 2 blocks for each output
+        let keys: [[u8; 32]; 4] = thread_rng().gen();
+        let prg_check = DcfImpl::<2, 32, _>::new(Aes256HirosePrg::new(keys.each_ref())).prg_check();
+        (0..4).for_each(|i| {
+            let mut keys_other = keys;
+            keys_other[i][0] ^= 1;
+            let dcf_other = DcfImpl::<2, 32, _>::new(Aes256HirosePrg::new(keys_other.each_ref()));
+            assert_ne!(dcf_other.prg_check(), prg_check);
+        });
+    }
+
+    #[test]
+    fn test_share_without_prg_check_from_bytes_then_eval_ok() {
+        let dcf = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [mut k0, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let y0 = dcf.eval_at(false, &k0, ALPHAS[0]).unwrap();
+        k0.prg_check = None;
+        // In the serde layout from before `prg_check`
+        let mut bytes = vec![FORMAT_PLAIN];
+        bytes.extend(bincode::serialize(&LegacyShareRef(&k0)).unwrap());
+        assert!(bytes.len() < k0.to_bytes().len());
+        let k0_de = Share::<16>::from_bytes::<16>(&bytes).unwrap();
+        assert!(k0_de == k0);
+        assert!(Share::<16>::is_canonical_encoding(&bytes));
+        assert_eq!(dcf.eval_at(false, &k0_de, ALPHAS[0]).unwrap(), y0);
+        // Not checked
+        let dcf_other = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new([KEYS[1], KEYS[0]]));
+        assert!(dcf_other.eval_at(false, &k0_de, ALPHAS[0]).is_ok());
+        // Both the layout with `None` and the one from before `prg_check` round-trip
+        assert!(Share::<16>::from_bytes::<16>(&k0.to_bytes()).unwrap() == k0);
+        assert!(Share::<16>::from_bytes::<16>(&k0.to_bytes_crc32()).unwrap() == k0);
+    }
+
+    #[test]
+    fn test_dcf_eval_scaled_gf256_then_combine_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _, Gf256LaneGroup>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        // 2 evaluation points of a Reed-Solomon code, scaled and combined by XOR
+        let scales: [[u8; 16]; 2] = [[0x02; 16], std::array::from_fn(|i| i as u8)];
+        let ks = k.split();
+        let ys = [0, 1].map(|i| {
+            let k = &ks[i];
+            scales.map(|scale| {
+                let mut ys = vec![[0; 16]; ALPHAS.len()];
+                dcf.eval_scaled_gf256(
+                    i == 1,
+                    k,
+                    ALPHAS,
+                    &scale,
+                    &mut ys.iter_mut().collect::<Vec<_>>(),
+                )
+                .unwrap();
+                ys
+            })
+        });
+        (0..ALPHAS.len()).for_each(|j| {
+            let mut combined = [0; 16];
+            let mut expected = [0; 16];
+            for (scale_i, scale) in scales.iter().enumerate() {
+                Gf256LaneGroup::add(&mut combined, &ys[0][scale_i][j]);
+                Gf256LaneGroup::add(&mut combined, &ys[1][scale_i][j]);
+                let mut beta_scaled = if j < 2 { BETA.to_owned() } else { [0; 16] };
+                Gf256LaneGroup::mul(&mut beta_scaled, scale);
+                Gf256LaneGroup::add(&mut expected, &beta_scaled);
+            }
+            assert_eq!(combined, expected);
+        });
+    }
+
+    #[test]
+    fn test_dcf_reseed_then_regen_ok() {
+        const NEW_KEYS: [&[u8; 32]; 2] = [&[0x5a; 32], &[0xa5; 32]];
+        let prg = Aes256HirosePrg::new(KEYS);
+        let mut dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let expected = vec![BETA.to_owned(), BETA.to_owned(), [0; 16], [0; 16], [0; 16]];
+        let eval_both = |dcf: &DcfImpl<16, 16, Aes256HirosePrg<16, 2>>, k: &Share<16>| {
+            let ks = k.clone().split();
+            let [mut ys0, ys1] = [false, true].map(|b| {
+                let kb = &ks[b as usize];
+                let mut ys = vec![[0; 16]; ALPHAS.len()];
+                dcf.eval(b, kb, ALPHAS, &mut ys.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                ys
+            });
+            ys0.iter_mut()
+                .zip(ys1.iter())
+                .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+            ys0
+        };
+        let k_old = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        assert_eq!(eval_both(&dcf, &k_old), expected);
+        let check_old = dcf.prg_check();
+
+        dcf.prg_mut().reseed(NEW_KEYS);
+        assert_ne!(dcf.prg_check(), check_old);
+        // The old key is invalidated, which eval detects by its fingerprint
+        let [k0_old, _] = k_old.clone().split();
+        assert_eq!(
+            dcf.eval_at(false, &k0_old, ALPHAS[0]),
+            Err(DcfError::PrgMismatch)
+        );
+        let mut k_old_unchecked = k_old.clone();
+        k_old_unchecked.prg_check = None;
+        assert_ne!(eval_both(&dcf, &k_old_unchecked), expected);
+        let k_new = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        assert_ne!(k_new.cw_np1, k_old.cw_np1);
+        assert_eq!(eval_both(&dcf, &k_new), expected);
+        // The same as a PRG created with the new keys
+        let dcf_new = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(NEW_KEYS));
+        assert_eq!(dcf_new.prg_check(), dcf.prg_check());
+    }
+
+    #[test]
+    fn test_dcf_arith_eval_masked_output_then_reconstruct_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _, ArithGroup>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let masks0: Vec<[u8; 16]> = ALPHAS.iter().map(|_| thread_rng().gen()).collect();
+        let masks1: Vec<[u8; 16]> = masks0
+            .iter()
+            .map(|mask| {
+                let mut mask_neg = *mask;
+                ArithGroup::neg(&mut mask_neg);
+                mask_neg
+            })
+            .collect();
+        let ks = k.split();
+        let [(mut ys0, ys0_plain), (ys1, _)] =
+            [(false, &masks0), (true, &masks1)].map(|(b, masks)| {
+                let kb = &ks[b as usize];
+                let mut ys = vec![[0; 16]; ALPHAS.len()];
+                let masks: Vec<&[u8; 16]> = masks.iter().collect();
+                dcf.eval_masked_output(
+                    b,
+                    kb,
+                    ALPHAS,
+                    &masks,
+                    &mut ys.iter_mut().collect::<Vec<_>>(),
+                )
+                .unwrap();
+                let mut ys_plain = vec![[0; 16]; ALPHAS.len()];
+                dcf.eval(b, kb, ALPHAS, &mut ys_plain.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                (ys, ys_plain)
+            });
+        // The output of each party alone is blinded
+        ys0.iter()
+            .zip(ys0_plain.iter())
+            .for_each(|(y, y_plain)| assert_ne!(y, y_plain));
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+        let zero = [0; 16];
+        assert_eq!(ys0, vec![*BETA, *BETA, zero, zero, zero]);
+    }
+
+    #[test]
+    fn test_dcf_eval_broadcast_same_as_eval() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [_, k] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        ALPHAS.iter().for_each(|x| {
+            let xs = vec![*x; 10];
+            let mut ys_expected = vec![[0; 16]; 10];
+            dcf.eval(
+                true,
+                &k,
+                &xs,
+                &mut ys_expected.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+            let mut ys = vec![[0; 16]; 10];
+            dcf.eval_broadcast(true, &k, x, 10, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            assert_eq!(ys, ys_expected);
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_serialized_then_eval_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let (bytes0, bytes1) = dcf.gen_serialized(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+        // The same as serializing the split keys
+        let [k0, k1] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        assert_eq!(bytes0, k0.to_bytes());
+        assert_eq!(bytes1, k1.to_bytes());
+        let [ys0, ys1] = [(false, &bytes0), (true, &bytes1)].map(|(b, bytes)| {
+            let kb = Share::from_bytes::<16>(bytes).unwrap();
+            assert_eq!(kb.s0s, vec![s0s[b as usize]]);
+            let mut ys = vec![[0; 16]; ALPHAS.len()];
+            dcf.eval(b, &kb, ALPHAS, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        ALPHAS.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                xor(&[&ys0[i], &ys1[i]]),
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+    }
+
+    #[test]
+    fn test_share_split_each_party_only_own_seed() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.clone().split();
+        assert_eq!((k0.s0s.len(), k1.s0s.len()), (1, 1));
+        assert_ne!(k0.s0s[0], k1.s0s[0]);
+        assert_eq!([k0.s0s[0], k1.s0s[0]], s0s);
+        assert!(!k0.s0s.contains(&s0s[1]));
+        assert!(!k1.s0s.contains(&s0s[0]));
+        // The public part is untouched
+        let mut k_party = k.clone();
+        [&k0, &k1].iter().enumerate().for_each(|(b, kb)| {
+            k_party.s0s = vec![k.s0s[b]];
+            assert_eq!(kb.to_bytes(), k_party.to_bytes());
+        });
+        assert!(Share::unsplit(k0, k1) == k);
+    }
+
+    #[test]
+    #[should_panic(expected = "the keys must be of the same gen")]
+    fn test_share_unsplit_other_gen_panics() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [[k0, _], [_, k1]] = [(); 2].map(|_| {
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+                .unwrap()
+                .split()
+        });
+        let _ = Share::unsplit(k0, k1);
+    }
+
+    #[test]
+    #[should_panic(expected = "the 2 seeds must differ")]
+    fn test_share_split_same_seeds_panics() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0: [u8; 16] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let _ = dcf.gen(&f, [&s0, &s0], BoundState::LtBeta).unwrap().split();
+    }
+
+    #[test]
+    fn test_dcf_reconstruct_same_as_eval_plain() {
+        let dcf = DcfImpl::<16, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let [k0, k1] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        ALPHAS.iter().for_each(|x| {
+            let y0 = dcf.eval_at(false, &k0, x).unwrap();
+            let y1 = dcf.eval_at(true, &k1, x).unwrap();
+            assert_eq!(
+                dcf.reconstruct(&y0, &y1),
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_deterministic_given_s0s() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        [BoundState::LtBeta, BoundState::GtBeta]
+            .into_iter()
+            .for_each(|bound| {
+                let k0 = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+                let k1 = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap();
+                assert_eq!(k0.to_bytes(), k1.to_bytes());
+                assert!(k0 == k1);
+                // Only the PRG keys matter, not the instance
+                let dcf_other = DcfImpl::<16, 16, _>::new(Aes256HirosePrg::new(KEYS));
+                assert!(dcf_other.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap() == k0);
+                let mut s0s_other = s0s;
+                s0s_other[0][0] ^= 1;
+                assert!(dcf.gen(&f, [&s0s_other[0], &s0s_other[1]], bound).unwrap() != k0);
+            });
+    }
+
+    /// [`Aes256HirosePrg`] counting how many times it is created, including by clone
+    #[cfg(feature = "multithread")]
+    struct CountingPrg {
+        inner: Aes256HirosePrg<16, 2>,
+        constructions: Arc<std::sync::atomic::AtomicUsize>,
+        threads: Arc<std::sync::Mutex<std::collections::HashSet<std::thread::ThreadId>>>,
+    }
+
+    #[cfg(feature = "multithread")]
+    impl Clone for CountingPrg {
+        fn clone(&self) -> Self {
+            self.constructions
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Self {
+                inner: Aes256HirosePrg::new(KEYS),
+                constructions: self.constructions.clone(),
+                threads: self.threads.clone(),
+            }
+        }
+    }
+
+    #[cfg(feature = "multithread")]
+    impl Prg<16> for CountingPrg {
+        fn gen(&self, seed: &[u8; 16]) -> [([u8; 16], [u8; 16], bool); 2] {
+            self.threads
+                .lock()
+                .unwrap()
+                .insert(std::thread::current().id());
+            self.inner.gen(seed)
+        }
+    }
+
+    #[cfg(feature = "multithread")]
+    #[test]
+    fn test_dcf_eval_multithread_shares_prg() {
+        let constructions = Arc::new(std::sync::atomic::AtomicUsize::new(1));
+        let threads = Arc::new(std::sync::Mutex::new(std::collections::HashSet::new()));
+        let dcf = DcfImpl::<16, 16, _>::new(CountingPrg {
+            inner: Aes256HirosePrg::new(KEYS),
+            constructions: constructions.clone(),
+            threads: threads.clone(),
+        });
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<[u8; 16]> = (0..500).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 16]> = xs.iter().collect();
+        let ks = k.split();
+        let [ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                xor(&[&ys0[i], &ys1[i]]),
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+        // Only the one passed to `new`, however many workers used it
+        assert_eq!(constructions.load(std::sync::atomic::Ordering::Relaxed), 1);
+        if rayon::current_num_threads() > 1 {
+            assert!(threads.lock().unwrap().len() > 1);
+        }
+    }
+
+    #[test]
+    fn test_dcf_eval_full_same_as_eval_over_domain() {
+        let dcf = DcfImpl::<1, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta).unwrap();
+        let xs: Vec<[u8; 1]> = (0..=255).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        let keys = k.split();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let kb = &keys[b as usize];
+            let ys = dcf.eval_full(b, kb).unwrap();
+            let mut ys_expected = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys_expected.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            assert_eq!(ys, ys_expected);
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        xs.iter().zip(ys0.iter()).for_each(|(x, y)| {
+            assert_eq!(y, &f.eval_plain(x, BoundState::GtBeta));
+        });
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let [k, _] = dcf
+            .gen(
+                &CmpFn {
+                    alpha: [0; 4],
+                    beta: BETA.to_owned(),
+                },
+                [&s0s[0], &s0s[1]],
+                BoundState::LtBeta,
+            )
+            .unwrap()
+            .split();
+        assert_eq!(
+            dcf.eval_full(false, &k).err(),
+            Some(DcfError::DomainTooLarge {
+                bits: 32,
+                max: MAX_FULL_DOMAIN_BITS
+            })
+        );
+    }
+
+    #[test]
+    fn test_dcf_eval_full_visit_same_as_eval_over_domain() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<1, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<[u8; 1]> = (0..=255u8).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        let ks = k.split();
+        [false, true].iter().for_each(|&b| {
+            let kb = &ks[b as usize];
+            let mut ys_expected = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys_expected.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            let mut visited = vec![];
+            dcf.eval_full_visit(b, kb, |i, y| visited.push((i, *y)));
+            assert_eq!(
+                visited,
+                ys_expected
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, y)| (i as u128, y))
+                    .collect::<Vec<_>>()
+            );
+        });
+    }
+
+    #[test]
+    fn test_dcf_eval_all_same_as_eval() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<1, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        // Within the 4 low bits, so that the 4 leading bits can be fixed
+        let f = CmpFn {
+            alpha: [thread_rng().gen::<u8>() >> 4],
+            beta: BETA.to_owned(),
+        };
+        let xs: Vec<[u8; 1]> = (0..=255u8).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        [
+            dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap(),
+            dcf.gen_fixed_bits(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta, 4)
+                .unwrap(),
+        ]
+        .into_iter()
+        .for_each(|k| {
+            let ks = k.split();
+            [false, true].iter().for_each(|&b| {
+                let kb = &ks[b as usize];
+                let mut ys_expected = vec![[0; 16]; xs.len()];
+                dcf.eval(b, kb, &xs, &mut ys_expected.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                let mut ys = vec![[0; 16]; xs.len()];
+                dcf.eval_all(b, kb, &mut ys).unwrap();
+                assert_eq!(ys, ys_expected);
+            });
+        });
+    }
+
+    #[test]
+    fn test_dcf_eval_all_n2_ok() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<2, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: BETA.to_owned(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta).unwrap();
+        let ks = k.split();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; 1 << 16];
+            dcf.eval_all(b, kb, &mut ys).unwrap();
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| xor_inplace(y0, &[y1]));
+        ys0.iter().enumerate().for_each(|(x, y)| {
+            assert_eq!(
+                y,
+                &f.eval_plain(&(x as u16).to_be_bytes(), BoundState::GtBeta)
+            );
+        });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "assertion `left == right` failed")]
+    fn test_dcf_gen_corrupted_state_debug_assert_fires() {
+        let prg = Aes256HirosePrg::new(KEYS);
+        let dcf = DcfImpl::<16, 16, _>::new(prg);
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: ALPHAS[2].to_owned(),
+            beta: BETA.to_owned(),
+        };
+        crate::test_fixtures::CORRUPT_GEN_STATE.set(true);
+        dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+    }
+
     #[test]
     fn test_dcf_gen_then_eval_not_zeros() {
         let prg = Aes256HirosePrg::new(KEYS);
@@ -428,15 +4928,17 @@ mod tests {
             alpha: ALPHAS[2].to_owned(),
             beta: BETA.to_owned(),
         };
-        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta);
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
         let mut k0 = k.clone();
         k0.s0s = vec![k0.s0s[0]];
         let mut k1 = k.clone();
         k1.s0s = vec![k1.s0s[1]];
         let mut ys0 = vec![[0; 16]; ALPHAS.len()];
         let mut ys1 = vec![[0; 16]; ALPHAS.len()];
-        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>());
-        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>());
+        dcf.eval(false, &k0, ALPHAS, &mut ys0.iter_mut().collect::<Vec<_>>())
+            .unwrap();
+        dcf.eval(true, &k1, ALPHAS, &mut ys1.iter_mut().collect::<Vec<_>>())
+            .unwrap();
         assert_ne!(ys0[2], [0; 16]);
         assert_ne!(ys1[2], [0; 16]);
     }
@@ -3,33 +3,89 @@
 
 //! [`crate::Prg`] implementations
 
+#[cfg(feature = "prg")]
 use aes::cipher::generic_array::GenericArray;
+#[cfg(feature = "prg")]
 use aes::cipher::{BlockEncrypt, KeyInit};
+#[cfg(feature = "prg")]
 use aes::Aes256;
+#[cfg(feature = "prg")]
 use bitvec::prelude::*;
+#[cfg(feature = "chacha")]
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+#[cfg(feature = "chacha")]
+use chacha20::ChaCha20;
+#[cfg(all(feature = "chacha", feature = "zeroize"))]
+use zeroize::Zeroize;
 
+#[cfg(feature = "prg")]
 use crate::utils::{xor, xor_inplace};
-use crate::Prg;
+use crate::{Prg, SupportedParams};
 
 /// Hirose double-block-length one-way compression function with AES256 and precreated keys
 /// as an implementation of [`Prg`].
 ///
-/// To avoid `#![feature(generic_const_exprs)]`, it is **your responsibility**
-/// to ensure `LAMBDA % 16 = 0` and `N = 2 * (LAMBDA / 16)`.
+/// `LAMBDA % 16 = 0` and `N = 2 * (LAMBDA / 16)` are required.
+/// To avoid `#![feature(generic_const_exprs)]`, they are checked at compile time
+/// when [`Aes256HirosePrg::new`] is instantiated rather than expressed in the type.
 ///
-/// It actually works for LAMBDA * 8 - 1 bits other than LAMBDA bytes.
-/// The last bit of the output `[u8; LAMBDA]` is always set to 0.
+/// The seeds actually work for LAMBDA * 8 - 1 bits other than LAMBDA bytes.
+/// The last bit of each output seed is taken as its control bit and then set to 0.
+/// The `v`s are left intact, so that all of their bits are pseudorandom.
+///
+/// The key schedules are computed once in [`Aes256HirosePrg::new`] and only read after that,
+/// so the rayon workers of the `multithread` feature share them by reference without contention,
+/// and no per-thread warm-up is needed.
+#[cfg(feature = "prg")]
 pub struct Aes256HirosePrg<const LAMBDA: usize, const N: usize> {
     ciphers: [Aes256; N],
 }
 
+#[cfg(feature = "prg")]
 impl<const LAMBDA: usize, const N: usize> Aes256HirosePrg<LAMBDA, N> {
+    /// Fails the build when `LAMBDA` and `N` do not match as documented in [`Aes256HirosePrg`]
+    const PARAMS_OK: () = {
+        assert!(
+            LAMBDA > 0 && LAMBDA.is_multiple_of(16),
+            "LAMBDA must be a positive multiple of 16, the AES block size"
+        );
+        assert!(N == 2 * (LAMBDA / 16), "N must be 2 * (LAMBDA / 16)");
+    };
+
     pub fn new(keys: [&[u8; 32]; N]) -> Self {
-        let ciphers = std::array::from_fn(|i| {
+        let () = Self::PARAMS_OK;
+        Self {
+            ciphers: Self::ciphers(keys),
+        }
+    }
+
+    /// Replaces the keys in place, e.g., for periodic key rotation,
+    /// so that the owner (like [`crate::DcfImpl`] via [`crate::DcfImpl::prg_mut`]) is kept.
+    ///
+    /// **All of the keys generated before are invalidated.**
+    /// Eval of them with the reseeded PRG silently outputs garbage,
+    /// so they must be regenerated with the new keys,
+    /// which both the dealer and the parties must switch to at the same time.
+    pub fn reseed(&mut self, keys: [&[u8; 32]; N]) {
+        self.ciphers = Self::ciphers(keys);
+    }
+
+    /// Key schedule of `keys`
+    fn ciphers(keys: [&[u8; 32]; N]) -> [Aes256; N] {
+        std::array::from_fn(|i| {
             let key_block = GenericArray::from_slice(keys[i]);
             Aes256::new(key_block)
-        });
-        Self { ciphers }
+        })
+    }
+
+    /// `(i, j)` of the blocks to encrypt, where `i` is the output and `j` is the block in it.
+    ///
+    /// Every block of both outputs is encrypted.
+    /// An output left unencrypted would be the seed XORed with itself or with `c`,
+    /// which reveals the seed to anyone with the output, e.g., the other party with the correction words.
+    fn blocks() -> impl Iterator<Item = (usize, usize)> {
+        let blocks_per_output = LAMBDA / 16;
+        (0..2).flat_map(move |i| (0..blocks_per_output).map(move |j| (i, j)))
     }
 
     /// Get the arbitrary non-zero constant c
@@ -38,6 +94,7 @@ impl<const LAMBDA: usize, const N: usize> Aes256HirosePrg<LAMBDA, N> {
     }
 }
 
+#[cfg(feature = "prg")]
 impl<const LAMBDA: usize, const N: usize> Prg<LAMBDA> for Aes256HirosePrg<LAMBDA, N> {
     fn gen(&self, seed: &[u8; LAMBDA]) -> [([u8; LAMBDA], [u8; LAMBDA], bool); 2] {
         // `$p(G_{i - 1})$`
@@ -45,10 +102,11 @@ impl<const LAMBDA: usize, const N: usize> Prg<LAMBDA> for Aes256HirosePrg<LAMBDA
         let mut result_buf0 = [[0; LAMBDA]; 2];
         let mut result_buf1 = [[0; LAMBDA]; 2];
         let mut out_blocks = [GenericArray::default(); 2];
-        (0..2usize).zip(0..LAMBDA / 16).for_each(|(i, j)| {
+        Self::blocks().for_each(|(i, j)| {
             let in_block0 = GenericArray::from_slice(&seed[j * 16..(j + 1) * 16]);
             let in_block1 = GenericArray::from_slice(&seed_p[j * 16..(j + 1) * 16]);
-            self.ciphers[i * 16 + j]
+            // Each output has its own `LAMBDA / 16` ciphers, so that the index stays in `N`
+            self.ciphers[i * (LAMBDA / 16) + j]
                 .encrypt_blocks_b2b(&[*in_block0, *in_block1], &mut out_blocks)
                 .unwrap();
             result_buf0[i][j * 16..(j + 1) * 16].copy_from_slice(out_blocks[0].as_ref());
@@ -60,29 +118,135 @@ impl<const LAMBDA: usize, const N: usize> Prg<LAMBDA> for Aes256HirosePrg<LAMBDA
         result_buf1
             .iter_mut()
             .for_each(|buf| xor_inplace(buf, &[&seed_p]));
-        let bit0 = result_buf0[0].view_bits::<Lsb0>()[0];
-        let bit1 = result_buf1[0].view_bits::<Lsb0>()[0];
-        result_buf0
-            .iter_mut()
-            .chain(result_buf1.iter_mut())
-            .for_each(|buf| buf[LAMBDA - 1].view_bits_mut::<Lsb0>().set(0, false));
+        // The control bits are taken from the seeds and cleared in them,
+        // so that every bit of the `v`s stays pseudorandom, e.g., the lowest bit of `$\beta$` in the correction words
+        let bits = result_buf0.each_mut().map(|buf| {
+            let bit = buf[LAMBDA - 1].view_bits::<Lsb0>()[0];
+            buf[LAMBDA - 1].view_bits_mut::<Lsb0>().set(0, false);
+            bit
+        });
         [
-            (result_buf0[0], result_buf1[0], bit0),
-            (result_buf0[1], result_buf1[1], bit1),
+            (result_buf0[0], result_buf1[0], bits[0]),
+            (result_buf0[1], result_buf1[1], bits[1]),
         ]
     }
+
+    /// Any multiple of the AES block size works given its `2 * (LAMBDA / 16)` keys, so there is no upper bound.
+    /// `LAMBDA = 16` is recommended for 128-bit security, which is the most the AES block gives.
+    fn supported_params(&self) -> Option<SupportedParams> {
+        Some(SupportedParams {
+            max_lambda: usize::MAX,
+            recommended_lambda: 16,
+            lambda_multiple: 16,
+        })
+    }
 }
 
-#[cfg(test)]
+/// ChaCha20 keyed by the seed as an implementation of [`Prg`],
+/// e.g., for targets without AES instructions, where ChaCha20 in software is faster than AES.
+///
+/// `0 < LAMBDA <= 32` is required, since the seed is zero-padded to the 32-byte ChaCha20 key.
+/// It is checked at compile time when [`ChaCha20Prg::new`] is instantiated.
+/// The nonce is all 0s, which is fine since every seed keys its own stream.
+///
+/// The first `4 * LAMBDA` bytes of the keystream are the left seed, the left `v`, the right seed, and the right `v` in order.
+/// The control bits are the 2 lowest bits of the next byte, bit 0 for the left and bit 1 for the right,
+/// so unlike `Aes256HirosePrg` no bit of the seeds or `v`s is taken for them, and all of the `LAMBDA` bytes are used.
+#[cfg(feature = "chacha")]
+#[derive(Clone, Copy)]
+pub struct ChaCha20Prg<const LAMBDA: usize>;
+
+#[cfg(feature = "chacha")]
+impl<const LAMBDA: usize> ChaCha20Prg<LAMBDA> {
+    /// Fails the build when `LAMBDA` does not fit the key as documented in [`ChaCha20Prg`]
+    const PARAMS_OK: () = assert!(
+        LAMBDA > 0 && LAMBDA <= 32,
+        "LAMBDA must be in 1..=32, the ChaCha20 key size"
+    );
+
+    pub fn new() -> Self {
+        let () = Self::PARAMS_OK;
+        Self
+    }
+}
+
+#[cfg(feature = "chacha")]
+impl<const LAMBDA: usize> Default for ChaCha20Prg<LAMBDA> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "chacha")]
+impl<const LAMBDA: usize> Prg<LAMBDA> for ChaCha20Prg<LAMBDA> {
+    fn gen(&self, seed: &[u8; LAMBDA]) -> [([u8; LAMBDA], [u8; LAMBDA], bool); 2] {
+        let mut key = [0; 32];
+        key[..LAMBDA].copy_from_slice(seed);
+        let mut cipher = ChaCha20::new(&key.into(), &[0; 12].into());
+        // Enough for the largest `LAMBDA` without `#![feature(generic_const_exprs)]`
+        let mut buf = [0; 4 * 32 + 1];
+        let stream = &mut buf[..4 * LAMBDA + 1];
+        cipher.apply_keystream(stream);
+        let block =
+            |i: usize| -> [u8; LAMBDA] { stream[i * LAMBDA..(i + 1) * LAMBDA].try_into().unwrap() };
+        let bits = stream[4 * LAMBDA];
+        let out = [
+            (block(0), block(1), bits & 1 != 0),
+            (block(2), block(3), bits & 2 != 0),
+        ];
+        #[cfg(feature = "zeroize")]
+        {
+            key.zeroize();
+            buf.zeroize();
+        }
+        out
+    }
+
+    /// The key covers at most 32 bytes of the seed.
+    /// `LAMBDA = 16` is still recommended for 128-bit security.
+    fn supported_params(&self) -> Option<SupportedParams> {
+        Some(SupportedParams {
+            max_lambda: 32,
+            recommended_lambda: 16,
+            lambda_multiple: 1,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
 mod tests {
     use super::*;
 
-    const KEYS: [&[u8; 32]; 2] = [
-        b"j9\x1b_\xb3X\xf33\xacW\x15\x1b\x0812K\xb3I\xb9\x90r\x1cN\xb5\xee9W\xd3\xbb@\xc6d",
-        b"\x9b\x15\xc8\x0f\xb7\xbc!q\x9e\x89\xb8\xf7\x0e\xa0S\x9dN\xfa\x0c;\x16\xe4\x98\x82b\xfcdy\xb5\x8c{\xc2",
-    ];
+    use crate::test_fixtures::KEYS;
+    use crate::{DcfError, DcfImpl};
+
     const SEED: &[u8; 16] = b"*L\x8f%y\x12Z\x94*E\x8f$+NH\x19";
 
+    #[test]
+    fn test_prg_supported_params() {
+        let prg = Aes256HirosePrg::<16, 2>::new(KEYS);
+        let params = prg.supported_params().unwrap();
+        assert_eq!(
+            params,
+            SupportedParams {
+                max_lambda: usize::MAX,
+                recommended_lambda: 16,
+                lambda_multiple: 16,
+            }
+        );
+        [16, 32, 64].iter().for_each(|&lambda| {
+            assert_eq!(params.check(lambda), Ok(()));
+        });
+        [0, 17, 40].iter().for_each(|&lambda| {
+            assert_eq!(
+                params.check(lambda),
+                Err(DcfError::UnsupportedLambda { lambda, params })
+            );
+        });
+        let dcf = DcfImpl::<4, 16, _>::new(prg);
+        assert_eq!(dcf.supported_params(), Some(params));
+    }
+
     #[test]
     fn test_prg_gen_not_zeros() {
         let prg = Aes256HirosePrg::<16, 2>::new(KEYS);
@@ -94,4 +258,78 @@ mod tests {
             assert_ne!(xor(&[&out[i].1, SEED]), [0; 16]);
         });
     }
+
+    /// A block not from AES would be the one of the seed, or of the seed XORed with `c`, XORed with itself, i.e., 0s.
+    /// The last byte of each block is skipped since the bit cleared in the output seeds can differ from the seed.
+    fn assert_every_block_from_aes<const LAMBDA: usize, const N: usize>(
+        prg: &Aes256HirosePrg<LAMBDA, N>,
+        seed: &[u8; LAMBDA],
+    ) {
+        let seed_p = xor(&[seed, &[0xff; LAMBDA]]);
+        let out = prg.gen(seed);
+        (0..2).for_each(|i| {
+            let buf0 = xor(&[&out[i].0, seed]);
+            let buf1 = xor(&[&out[i].1, &seed_p]);
+            buf0.chunks(16)
+                .chain(buf1.chunks(16))
+                .for_each(|block| assert!(block[..15].iter().any(|&byte| byte != 0)));
+        });
+    }
+
+    #[test]
+    fn test_prg_gen_every_block_from_aes() {
+        assert_every_block_from_aes(&Aes256HirosePrg::<16, 2>::new(KEYS), SEED);
+        let keys: [[u8; 32]; 8] = std::array::from_fn(|i| [i as u8 + 1; 32]);
+        let prg = Aes256HirosePrg::<64, 8>::new(std::array::from_fn(|i| &keys[i]));
+        assert_eq!(prg.supported_params().unwrap().check(64), Ok(()));
+        assert_every_block_from_aes(&prg, &std::array::from_fn(|i| (i * 2) as u8));
+    }
+}
+
+#[cfg(all(test, feature = "chacha"))]
+mod chacha_tests {
+    use super::*;
+
+    use crate::{ArithGroup, BoundState, CmpFn, Dcf, DcfImpl, Group};
+
+    #[test]
+    fn test_chacha20_prg_gen_from_keystream() {
+        // The keystream of the all-0 key and nonce, test vector #1 of RFC 8439, Appendix A.1
+        let keystream = b"\x76\xb8\xe0\xad\xa0\xf1\x3d\x90\x40\x5d\x6a\xe5\x53\x86\xbd\x28\xbd\xd2\x19\xb8\xa0\x8d\xed\x1a\xa8\x36\xef\xcc\x8b\x77\x0d\xc7\xda\x41\x59\x7c\x51\x57\x48\x8d\x77\x24\xe0\x3f\xb8\xd8\x4a\x37\x6a\x43\xb8\xf4\x15\x18\xa1\x1c\xc3\x87\xb6\x69\xb2\xee\x65\x86";
+        let prg = ChaCha20Prg::<16>::new();
+        let out = prg.gen(&[0; 16]);
+        assert_eq!(out[0].0, keystream[..16]);
+        assert_eq!(out[0].1, keystream[16..32]);
+        assert_eq!(out[1].0, keystream[32..48]);
+        assert_eq!(out[1].1, keystream[48..64]);
+        // The next byte, the first one of block 1, is 0x9f
+        assert!(out[0].2 && out[1].2);
+        assert_eq!(prg.supported_params().unwrap().check(32), Ok(()));
+    }
+
+    #[test]
+    fn test_dcf_with_chacha20_prg_full_domain_ok() {
+        let dcf = DcfImpl::<1, 32, _, ArithGroup>::new(ChaCha20Prg::new());
+        assert_eq!(dcf.supported_params().unwrap().check(32), Ok(()));
+        [
+            BoundState::LtBeta,
+            BoundState::GtBeta,
+            BoundState::Leq,
+            BoundState::Geq,
+        ]
+        .into_iter()
+        .for_each(|bound| {
+            let s0s: [[u8; 32]; 2] = std::array::from_fn(|i| [i as u8 + 1; 32]);
+            let f = CmpFn {
+                alpha: [100],
+                beta: [7; 32],
+            };
+            let [k0, k1] = dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap().split();
+            (0..=255u8).for_each(|x| {
+                let mut y = dcf.eval_at(false, &k0, &[x]).unwrap();
+                ArithGroup::add(&mut y, &dcf.eval_at(true, &k1, &[x]).unwrap());
+                assert_eq!(y, f.eval_plain(&[x], bound));
+            });
+        });
+    }
 }
@@ -0,0 +1,149 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ramps below a threshold, i.e., outputs that grow linearly with the input
+
+use crate::group::Ring;
+use crate::{BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// `k` of [`DcfImpl::gen_ramp`].
+///
+/// Like [`Share`], both keys are shared by the 2 parties and only their `s0s` are different.
+#[derive(Clone)]
+pub struct RampShare<const LAMBDA: usize> {
+    /// DCF of `$\beta_0 [x < \alpha]$`
+    pub constant: Share<LAMBDA>,
+    /// DCF of `$s [x < \alpha]$`, which eval multiplies by `x`
+    pub slope: Share<LAMBDA>,
+}
+
+impl<const LAMBDA: usize> RampShare<LAMBDA> {
+    /// Splits the output of gen into the keys of party 0 and party 1 like [`Share::split`]
+    #[must_use = "each of the 2 keys must be sent to its party"]
+    pub fn split(self) -> [Self; 2] {
+        let [constant0, constant1] = self.constant.split();
+        let [slope0, slope1] = self.slope.split();
+        [
+            RampShare {
+                constant: constant0,
+                slope: slope0,
+            },
+            RampShare {
+                constant: constant1,
+                slope: slope1,
+            },
+        ]
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Ring<LAMBDA>,
+{
+    /// Gen of the ramp `$f(x) = \beta_0 + x s$` if `$x < \alpha$`, or 0 otherwise, where `s` is `step`.
+    ///
+    /// A single tree can not fold an index-dependent value into its leaves,
+    /// but `x` is public to the evaluator, so `f` is the sum of a DCF with `$\beta_0$`
+    /// and `x` times a DCF with `s`, which is linear in the shares.
+    /// The ramp only makes sense with [`crate::ArithGroup`],
+    /// where `x` is the big-endian integer of the input modulo `$2^{8 \lambda}$`.
+    /// `s0s` are the seeds of the 2 DCFs, which should be randomly sampled.
+    pub fn gen_ramp(
+        &self,
+        alpha: &[u8; N],
+        beta0: &[u8; LAMBDA],
+        step: &[u8; LAMBDA],
+        s0s: &[[[u8; LAMBDA]; 2]; 2],
+    ) -> RampShare<LAMBDA> {
+        let [constant, slope] = [(beta0, &s0s[0]), (step, &s0s[1])].map(|(beta, seeds)| {
+            let f = CmpFn {
+                alpha: *alpha,
+                beta: *beta,
+            };
+            self.gen_parts(&f, [&seeds[0], &seeds[1]], &BoundState::LtBeta, 0)
+        });
+        RampShare { constant, slope }
+    }
+
+    /// Eval of a key from [`DcfImpl::gen_ramp`], failing the same as [`Dcf::eval`]
+    pub fn eval_ramp(
+        &self,
+        b: bool,
+        k: &RampShare<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        self.eval(b, &k.constant, xs, ys)?;
+        let mut buf = vec![[0; LAMBDA]; xs.len()];
+        self.eval(b, &k.slope, xs, &mut buf.iter_mut().collect::<Vec<_>>())?;
+        ys.iter_mut()
+            .zip(buf.iter_mut())
+            .zip(xs.iter())
+            .for_each(|((y, y_slope), x)| {
+                G::mul(y_slope, &to_ring(x));
+                G::add(y, y_slope);
+            });
+        Ok(())
+    }
+}
+
+/// `x` as a big-endian integer modulo `$2^{8 \lambda}$`
+fn to_ring<const N: usize, const LAMBDA: usize>(x: &[u8; N]) -> [u8; LAMBDA] {
+    let mut res = [0; LAMBDA];
+    let len = N.min(LAMBDA);
+    res[LAMBDA - len..].copy_from_slice(&x[N - len..]);
+    res
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::group::Group;
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::ArithGroup;
+
+    #[test]
+    fn test_dcf_gen_ramp_then_eval_ramp_ok() {
+        let dcf = DcfImpl::<2, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let alpha = 40000u16;
+        let beta0 = 7u128;
+        let step = 3u128;
+        let s0s: [[[u8; 16]; 2]; 2] = thread_rng().gen();
+        let k = dcf.gen_ramp(
+            &alpha.to_be_bytes(),
+            &beta0.to_be_bytes(),
+            &step.to_be_bytes(),
+            &s0s,
+        );
+        let xs: Vec<u16> = (0..50)
+            .map(|_| thread_rng().gen())
+            .chain([0, 1, 1000, alpha - 1, alpha, alpha + 1, u16::MAX])
+            .collect();
+        let x_bytes: Vec<[u8; 2]> = xs.iter().map(|x| x.to_be_bytes()).collect();
+        let x_bytes: Vec<&[u8; 2]> = x_bytes.iter().collect();
+        let ks = k.split();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_ramp(b, kb, &x_bytes, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+        xs.iter().zip(ys0.iter()).for_each(|(&x, y)| {
+            let expected = if x < alpha {
+                beta0 + x as u128 * step
+            } else {
+                0
+            };
+            assert_eq!(u128::from_be_bytes(*y), expected);
+        });
+    }
+}
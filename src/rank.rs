@@ -0,0 +1,242 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shared ranks of inputs among a set of values, e.g., as a building block of oblivious sorting
+
+use crate::group::{Group, Ring};
+use crate::{
+    check_output_count, check_party_share, BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share,
+};
+
+/// Keys of the 2 parties of the same DCF, each with only its own seed
+#[derive(Clone)]
+pub struct KeyPair<const LAMBDA: usize> {
+    pub k0: Share<LAMBDA>,
+    pub k1: Share<LAMBDA>,
+}
+
+impl<const LAMBDA: usize> KeyPair<LAMBDA> {
+    /// Splits the output of gen, whose `s0s` has the 2 seeds
+    pub fn split(k: Share<LAMBDA>) -> Self {
+        let [k0, k1] = k.split();
+        Self { k0, k1 }
+    }
+
+    /// The key of the party `b`
+    pub fn get(&self, b: bool) -> &Share<LAMBDA> {
+        if b {
+            &self.k1
+        } else {
+            &self.k0
+        }
+    }
+
+    /// The first at most `limit` inputs, as big-endian integers in order,
+    /// where the outputs of the 2 keys reconstruct in `G` to nonzero.
+    ///
+    /// **Only for the dealer or testing**, since it evaluates both keys, which no party alone can.
+    /// Walks the domain of the keys, i.e., without the leading fixed bits of [`Dcf::gen_fixed_bits`],
+    /// by [`DcfImpl::eval_range_filtered`] in windows doubling in size up to `$2^{16}$` inputs,
+    /// so each node is expanded about once, and it stops soon after `limit` inputs are found.
+    /// `bound` must be the one of gen.
+    /// With [`BoundState::LtBeta`] or [`BoundState::Leq`], the support is a prefix of the domain,
+    /// so it also stops at the first 0.
+    /// Fails with the errors of [`Dcf::eval`] for either key.
+    pub fn find_matches<const N: usize, PrgT, G>(
+        &self,
+        dcf: &DcfImpl<N, LAMBDA, PrgT, G>,
+        bound: BoundState,
+        limit: usize,
+    ) -> Result<Vec<u128>, DcfError>
+    where
+        PrgT: Prg<LAMBDA>,
+        G: Group<LAMBDA>,
+    {
+        check_party_share::<N, LAMBDA>(&self.k0, &dcf.prg_check())?;
+        check_party_share::<N, LAMBDA>(&self.k1, &dcf.prg_check())?;
+        let n = self.k0.cws.len();
+        assert!(n > 0 && n <= 128, "the domain indexes overflow u128");
+        // The end of the domain, exclusive, which misses the last index of a 128-bit domain,
+        // but that takes `$2^{128}$` inputs to reach
+        let end = (u128::MAX >> (128 - n)).saturating_add(1);
+        let mut matches = vec![];
+        let [mut ys0, mut ys1] = [vec![], vec![]];
+        let (mut lo, mut window) = (0, 64);
+        while lo < end {
+            let hi = lo.saturating_add(window).min(end);
+            ys0.clear();
+            ys1.clear();
+            dcf.eval_range_filtered(false, &self.k0, lo, hi, |_| true, &mut ys0);
+            dcf.eval_range_filtered(true, &self.k1, lo, hi, |_| true, &mut ys1);
+            for (i, (y0, y1)) in (lo..hi).zip(ys0.iter().zip(ys1.iter())) {
+                if matches.len() >= limit {
+                    return Ok(matches);
+                }
+                if dcf.reconstruct(y0, y1) != [0; LAMBDA] {
+                    matches.push(i);
+                } else if matches!(bound, BoundState::LtBeta | BoundState::Leq) {
+                    return Ok(matches);
+                }
+            }
+            lo = hi;
+            window = (window * 2).min(1 << 16);
+        }
+        Ok(matches)
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Ring<LAMBDA>,
+{
+    /// 1 key for each of `values`, of `$f_j(x) = 1$` iff. `$x > v_j$` with 1 of the ring `G`.
+    ///
+    /// Summing the outputs of all of the keys at `x` gives the shared rank of `x`,
+    /// i.e., the number of the values less than it, which [`DcfImpl::eval_rank`] does.
+    /// The sum only counts with [`crate::ArithGroup`].
+    /// `s0s_source` is called once for each key, and its outputs should be randomly sampled.
+    pub fn gen_rank_keys<F>(&self, values: &[[u8; N]], mut s0s_source: F) -> Vec<KeyPair<LAMBDA>>
+    where
+        F: FnMut() -> [[u8; LAMBDA]; 2],
+    {
+        values
+            .iter()
+            .map(|v| {
+                let s0s = s0s_source();
+                let f = CmpFn {
+                    alpha: *v,
+                    beta: G::one(),
+                };
+                KeyPair::split(self.gen_parts(&f, [&s0s[0], &s0s[1]], &BoundState::GtBeta, 0))
+            })
+            .collect()
+    }
+
+    /// The shares of the party `b` of the ranks of `xs` among the values of [`DcfImpl::gen_rank_keys`].
+    ///
+    /// `keys` are the keys of the party, 1 for each of the values.
+    /// Fails the same as [`Dcf::eval`].
+    pub fn eval_rank(
+        &self,
+        b: bool,
+        keys: &[&Share<LAMBDA>],
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_output_count(xs.len(), ys.len())?;
+        ys.iter_mut().for_each(|y| **y = [0; LAMBDA]);
+        let mut buf = vec![[0; LAMBDA]; xs.len()];
+        for k in keys.iter() {
+            self.eval(b, k, xs, &mut buf.iter_mut().collect::<Vec<_>>())?;
+            ys.iter_mut()
+                .zip(buf.iter())
+                .for_each(|(y, y_key)| G::add(y, y_key));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::ArithGroup;
+
+    #[test]
+    fn test_dcf_gen_rank_keys_then_eval_rank_ok() {
+        let dcf = DcfImpl::<2, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        // Including a tie, which gets the same rank
+        let values: Vec<[u8; 2]> = [300u16, 7, 65535, 0, 1024, 7, 42]
+            .iter()
+            .map(|v| v.to_be_bytes())
+            .collect();
+        let pairs = dcf.gen_rank_keys(&values, || thread_rng().gen());
+        assert_eq!(pairs.len(), values.len());
+        let xs: Vec<&[u8; 2]> = values.iter().collect();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let keys: Vec<&Share<16>> = pairs.iter().map(|pair| pair.get(b)).collect();
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_rank(b, &keys, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+        let ranks: Vec<u128> = ys0.iter().map(|y| u128::from_be_bytes(*y)).collect();
+        assert_eq!(ranks, vec![4, 1, 6, 0, 5, 1, 3]);
+        // Sorting by the ranks sorts the values
+        let mut by_rank: Vec<_> = ranks.iter().zip(values.iter()).collect();
+        by_rank.sort_by_key(|(rank, _)| **rank);
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(
+            by_rank.into_iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+            sorted
+        );
+    }
+
+    #[test]
+    fn test_key_pair_find_matches_ok() {
+        let dcf = DcfImpl::<1, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let f = CmpFn {
+            alpha: [37],
+            beta: thread_rng().gen(),
+        };
+        [
+            BoundState::LtBeta,
+            BoundState::GtBeta,
+            BoundState::Leq,
+            BoundState::Geq,
+        ]
+        .into_iter()
+        .for_each(|bound| {
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            let pair = KeyPair::split(dcf.gen(&f, [&s0s[0], &s0s[1]], bound).unwrap());
+            let expected: Vec<u128> = match bound {
+                BoundState::LtBeta => (0..37).collect(),
+                BoundState::GtBeta => (38..256).collect(),
+                BoundState::Leq => (0..=37).collect(),
+                BoundState::Geq => (37..256).collect(),
+            };
+            assert_eq!(
+                pair.find_matches(&dcf, bound, usize::MAX).unwrap(),
+                expected
+            );
+            assert_eq!(pair.find_matches(&dcf, bound, 5).unwrap(), expected[..5]);
+            assert_eq!(pair.find_matches(&dcf, bound, 0).unwrap(), vec![]);
+        });
+    }
+
+    #[test]
+    fn test_key_pair_find_matches_across_windows_ok() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let f = CmpFn {
+            alpha: 40000u16.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let pair = KeyPair::split(dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta).unwrap());
+        assert_eq!(
+            pair.find_matches(&dcf, BoundState::GtBeta, 3).unwrap(),
+            vec![40001, 40002, 40003]
+        );
+        assert_eq!(
+            pair.find_matches(&dcf, BoundState::GtBeta, usize::MAX)
+                .unwrap(),
+            (40001..65536).collect::<Vec<_>>()
+        );
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let pair = KeyPair::split(dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap());
+        assert_eq!(
+            pair.find_matches(&dcf, BoundState::LtBeta, usize::MAX)
+                .unwrap(),
+            (0..40000).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! 2D rectangle membership, i.e., `$\beta$` iff. the point `(x, y)` is in `$[x_0, x_1) \times [y_0, y_1)$`, and 0 otherwise.
+//!
+//! It is the product of the interval indicators of the 2 axes,
+//! each of which is a difference of 2 DCFs from [`DcfImpl::gen_difference`].
+//! The product of the 2 shared indicators requires a [`BeaverTriple`] from the dealer
+//! and 1 round of communication:
+//!
+//! 1. Each party calls [`DcfImpl::rectangle_share`] and sends [`RectangleProduct::msg`] to the other party.
+//! 2. Each party calls [`RectangleProduct::finish`] with the message received.
+//!
+//! A triple must only be used once, so a key only evaluates 1 point.
+
+use std::marker::PhantomData;
+
+use crate::compare_swap::BeaverTriple;
+use crate::group::Ring;
+use crate::step::StepShare;
+use crate::{BoundState, DcfError, DcfImpl, Prg};
+
+/// `k` of a rectangle.
+///
+/// Like [`StepShare`], `x` and `y` are shared by the 2 parties,
+/// and only `triples[0]` is different.
+#[derive(Clone)]
+pub struct RectangleShare<const LAMBDA: usize> {
+    /// Indicator of `$[x_0, x_1)$` with `$\beta$`
+    pub x: StepShare<LAMBDA>,
+    /// Indicator of `$[y_0, y_1)$` with [`Ring::one`]
+    pub y: StepShare<LAMBDA>,
+    /// For the output of gen, the shares of the triple of party 0 and party 1.
+    /// For the input of eval, the first one is used.
+    pub triples: Vec<BeaverTriple<LAMBDA>>,
+}
+
+impl<const LAMBDA: usize> RectangleShare<LAMBDA> {
+    /// Splits the output of gen into the keys of party 0 and party 1 like [`StepShare::split`].
+    ///
+    /// Panics unless `triples` has exactly 2 shares, or the same as [`StepShare::split`] for either axis.
+    #[must_use = "each of the 2 keys must be sent to its party"]
+    pub fn split(self) -> [Self; 2] {
+        assert_eq!(self.triples.len(), 2, "only the output of gen can be split");
+        let [x0, x1] = self.x.split();
+        let [y0, y1] = self.y.split();
+        let [triple0, triple1]: [BeaverTriple<LAMBDA>; 2] = self.triples.try_into().ok().unwrap();
+        [
+            RectangleShare {
+                x: x0,
+                y: y0,
+                triples: vec![triple0],
+            },
+            RectangleShare {
+                x: x1,
+                y: y1,
+                triples: vec![triple1],
+            },
+        ]
+    }
+}
+
+/// State of a party between the 2 steps of rectangle membership
+pub struct RectangleProduct<const LAMBDA: usize, G> {
+    b: bool,
+    triple: BeaverTriple<LAMBDA>,
+    /// The party's shares of `$c_x - a$` and `$c_y - b$` to send to the other party
+    pub msg: [[u8; LAMBDA]; 2],
+    group: PhantomData<fn() -> G>,
+}
+
+impl<const LAMBDA: usize, G> RectangleProduct<LAMBDA, G>
+where
+    G: Ring<LAMBDA>,
+{
+    /// Returns the party's share of the output at the point.
+    ///
+    /// `peer_msg` is [`RectangleProduct::msg`] of the other party.
+    pub fn finish(&self, peer_msg: &[[u8; LAMBDA]; 2]) -> [u8; LAMBDA] {
+        self.triple.product_share::<G>(self.b, &self.msg, peer_msg)
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Ring<LAMBDA>,
+{
+    /// Gen of the rectangle `$[x_0, x_1) \times [y_0, y_1)$` with `beta`.
+    ///
+    /// `x_range` is `$[x_0, x_1)$` and `y_range` is `$[y_0, y_1)$`.
+    /// `s0s` are the seeds of the 4 DCFs, 2 for each axis, which should be randomly sampled.
+    /// `triples` are from [`BeaverTriple::gen`] with a fresh triple.
+    ///
+    /// Fails with [`DcfError::InvalidInterval`] at index 0 for `x_range` or 1 for `y_range` if it is empty.
+    pub fn gen_rectangle(
+        &self,
+        x_range: [&[u8; N]; 2],
+        y_range: [&[u8; N]; 2],
+        beta: &[u8; LAMBDA],
+        s0s: &[[[u8; LAMBDA]; 2]; 4],
+        triples: [BeaverTriple<LAMBDA>; 2],
+    ) -> Result<RectangleShare<LAMBDA>, DcfError> {
+        if let Some(index) = [x_range, y_range].iter().position(|[lo, hi]| lo >= hi) {
+            return Err(DcfError::InvalidInterval { index });
+        }
+        let x = self.gen_difference(
+            x_range[0],
+            x_range[1],
+            beta,
+            &[s0s[0], s0s[1]],
+            BoundState::LtBeta,
+        )?;
+        let y = self.gen_difference(
+            y_range[0],
+            y_range[1],
+            &G::one(),
+            &[s0s[2], s0s[3]],
+            BoundState::LtBeta,
+        )?;
+        Ok(RectangleShare {
+            x,
+            y,
+            triples: triples.to_vec(),
+        })
+    }
+
+    /// First step of rectangle membership at `point`, i.e., `(x, y)`.
+    /// See [`crate::rectangle`] for the protocol.
+    ///
+    /// Fails the same as [`DcfImpl::eval_step`].
+    pub fn rectangle_share(
+        &self,
+        b: bool,
+        k: &RectangleShare<LAMBDA>,
+        point: [&[u8; N]; 2],
+    ) -> Result<RectangleProduct<LAMBDA, G>, DcfError> {
+        let triple = k.triples[0].clone();
+        let [mut d, mut e] = [[0; LAMBDA]; 2];
+        self.eval_step(b, &k.x, &[point[0]], &mut [&mut d])?;
+        self.eval_step(b, &k.y, &[point[1]], &mut [&mut e])?;
+        G::sub(&mut d, &triple.a);
+        G::sub(&mut e, &triple.b);
+        Ok(RectangleProduct {
+            b,
+            triple,
+            msg: [d, e],
+            group: PhantomData,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::group::{ArithGroup, Group};
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+
+    #[test]
+    fn test_dcf_gen_rectangle_then_eval_ok() {
+        let dcf = DcfImpl::<2, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let (x_range, y_range) = ([100u16, 200], [1000u16, 1500]);
+        let beta = 42u128.to_be_bytes();
+        let points = [
+            // Inside, including the lower corners
+            (150, 1200),
+            (100, 1000),
+            (199, 1499),
+            // Outside on 1 or both axes, including the upper bounds
+            (200, 1200),
+            (150, 1500),
+            (99, 1200),
+            (150, 999),
+            (0, 0),
+            (65535, 65535),
+        ];
+        points.iter().for_each(|&(px, py): &(u16, u16)| {
+            let s0s: [[[u8; 16]; 2]; 4] = thread_rng().gen();
+            let masks: [[u8; 16]; 3] = thread_rng().gen();
+            let triples = BeaverTriple::gen::<ArithGroup>(
+                &thread_rng().gen(),
+                &thread_rng().gen(),
+                [&masks[0], &masks[1], &masks[2]],
+            );
+            let [x_range, y_range] = [x_range, y_range].map(|r| r.map(|v| v.to_be_bytes()));
+            let k = dcf
+                .gen_rectangle(
+                    [&x_range[0], &x_range[1]],
+                    [&y_range[0], &y_range[1]],
+                    &beta,
+                    &s0s,
+                    triples,
+                )
+                .unwrap();
+            let point = [px.to_be_bytes(), py.to_be_bytes()];
+            let ks = k.split();
+            let states = [0, 1].map(|i| {
+                let kb = &ks[i];
+                dcf.rectangle_share(i == 1, kb, [&point[0], &point[1]])
+                    .unwrap()
+            });
+            let mut y = states[0].finish(&states[1].msg);
+            ArithGroup::add(&mut y, &states[1].finish(&states[0].msg));
+            let inside = (100..200).contains(&px) && (1000..1500).contains(&py);
+            assert_eq!(y, if inside { beta } else { [0; 16] }, "({px}, {py})");
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_rectangle_empty_range_err() {
+        let dcf = DcfImpl::<2, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[[u8; 16]; 2]; 4] = thread_rng().gen();
+        let masks: [[u8; 16]; 3] = thread_rng().gen();
+        let [lo, hi] = [100u16, 200].map(|v| v.to_be_bytes());
+        [([&lo, &hi], [&hi, &lo], 1), ([&hi, &hi], [&lo, &hi], 0)]
+            .into_iter()
+            .for_each(|(x_range, y_range, index)| {
+                let triples = BeaverTriple::gen::<ArithGroup>(
+                    &thread_rng().gen(),
+                    &thread_rng().gen(),
+                    [&masks[0], &masks[1], &masks[2]],
+                );
+                assert_eq!(
+                    dcf.gen_rectangle(x_range, y_range, &[1; 16], &s0s, triples)
+                        .err(),
+                    Some(DcfError::InvalidInterval { index })
+                );
+            });
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Secret seeds in locked memory with the `secure-mem` feature, as a defense in depth
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+/// `[u8; LAMBDA]` in its own allocation that is `mlock`ed, i.e., never swapped to disk,
+/// and guarded by inaccessible pages around it, which [`memsec::malloc`] provides.
+///
+/// It derefs to `[u8; LAMBDA]`, so it can be used wherever a seed is read.
+/// On drop, it is zeroed, unlocked, and freed.
+/// Locking is best-effort: it silently fails if the process exceeds its limit of locked memory,
+/// e.g., `RLIMIT_MEMLOCK` on Linux, in which case the zeroing on drop still applies.
+pub struct SecureSeed<const LAMBDA: usize> {
+    ptr: NonNull<[u8; LAMBDA]>,
+}
+
+// Owns the allocation exclusively like `Box`
+unsafe impl<const LAMBDA: usize> Send for SecureSeed<LAMBDA> {}
+unsafe impl<const LAMBDA: usize> Sync for SecureSeed<LAMBDA> {}
+
+impl<const LAMBDA: usize> SecureSeed<LAMBDA> {
+    /// Copies `seed` into a new locked allocation.
+    /// The caller should zero its own copy afterwards.
+    pub fn new(seed: &[u8; LAMBDA]) -> Self {
+        let ptr =
+            unsafe { memsec::malloc::<[u8; LAMBDA]>() }.expect("failed to allocate secure memory");
+        // The memory is initialized here before any read
+        unsafe { ptr.as_ptr().write(*seed) };
+        Self { ptr }
+    }
+}
+
+impl<const LAMBDA: usize> From<[u8; LAMBDA]> for SecureSeed<LAMBDA> {
+    fn from(seed: [u8; LAMBDA]) -> Self {
+        Self::new(&seed)
+    }
+}
+
+impl<const LAMBDA: usize> Deref for SecureSeed<LAMBDA> {
+    type Target = [u8; LAMBDA];
+
+    fn deref(&self) -> &[u8; LAMBDA] {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<const LAMBDA: usize> DerefMut for SecureSeed<LAMBDA> {
+    fn deref_mut(&mut self) -> &mut [u8; LAMBDA] {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<const LAMBDA: usize> Clone for SecureSeed<LAMBDA> {
+    fn clone(&self) -> Self {
+        Self::new(self)
+    }
+}
+
+/// Never prints the seed
+impl<const LAMBDA: usize> fmt::Debug for SecureSeed<LAMBDA> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecureSeed(..)")
+    }
+}
+
+impl<const LAMBDA: usize> SecureSeed<LAMBDA> {
+    /// Zeroes the seed in place, which [`Drop`] does right before freeing.
+    ///
+    /// `memsec::free` also zeroes when unlocking, but zeroing first does not depend on that.
+    fn wipe(&mut self) {
+        unsafe { memsec::memzero(self.ptr.as_ptr() as *mut u8, LAMBDA) };
+    }
+}
+
+impl<const LAMBDA: usize> Drop for SecureSeed<LAMBDA> {
+    fn drop(&mut self) {
+        self.wipe();
+        unsafe { memsec::free(self.ptr) };
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::utils::xor;
+    use crate::{BoundState, CmpFn, Dcf, DcfImpl};
+
+    #[test]
+    fn test_dcf_secure_seed_eval_party_ok() {
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<[u8; 4]> = (0..100).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let pks = k.into_party_keys();
+        assert_eq!(*pks[0].seed, s0s[0]);
+        let [ys0, ys1] = [false, true].map(|b| {
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_party(
+                b,
+                &pks[b as usize],
+                &xs,
+                &mut ys.iter_mut().collect::<Vec<_>>(),
+            )
+            .unwrap();
+            ys
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                xor(&[&ys0[i], &ys1[i]]),
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+    }
+
+    /// The memory is inaccessible once freed, so the zeroing of [`Drop`] is inspected right before it
+    #[test]
+    fn test_secure_seed_wipe_then_zeros() {
+        let seed_in: [u8; 16] = thread_rng().gen();
+        let mut seed = SecureSeed::new(&seed_in);
+        assert_eq!(*seed, seed_in);
+        let ptr = seed.ptr;
+        seed.wipe();
+        // Still the same allocation, which is zeroed in place
+        assert_eq!(seed.ptr, ptr);
+        assert_eq!(*seed, [0; 16]);
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! End-to-end flow of the dealer and the 2 parties, as a reference of how the APIs fit together.
+//!
+//! [`TwoPartyDcf::setup`] is the dealer: gen, split the key, and serialize each party's key.
+//! [`Party::from_bytes`] is each party receiving its key,
+//! [`Party::eval`] is each party evaluating on its own,
+//! and [`TwoPartyDcf::reconstruct`] is whoever is allowed to learn the outputs combining them.
+//! In a real deployment the bytes go over the network and each step runs on its own machine.
+
+use std::sync::Arc;
+
+use crate::group::{Combine, Group, XorGroup};
+use crate::{BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// The 2-party flow over a shared [`DcfImpl`].
+///
+/// Both parties must use the same PRG as the dealer, which sharing the [`DcfImpl`] guarantees here.
+pub struct TwoPartyDcf<const N: usize, const LAMBDA: usize, PrgT, G = XorGroup>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    dcf: Arc<DcfImpl<N, LAMBDA, PrgT, G>>,
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> TwoPartyDcf<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    pub fn new(prg: PrgT) -> Self {
+        Self::from_dcf(Arc::new(DcfImpl::new(prg)))
+    }
+
+    pub fn from_dcf(dcf: Arc<DcfImpl<N, LAMBDA, PrgT, G>>) -> Self {
+        Self { dcf }
+    }
+
+    /// The dealer, which returns party 0 and party 1.
+    ///
+    /// Gens the key of `f` with `bound`, gives each party only its own seed,
+    /// and sends the keys as bytes, which the parties check with [`Share::from_bytes`].
+    /// `s0s` should be randomly sampled like the one of [`Dcf::gen`].
+    pub fn setup(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        bound: BoundState,
+        s0s: [&[u8; LAMBDA]; 2],
+    ) -> Result<[Party<N, LAMBDA, PrgT, G>; 2], DcfError> {
+        let [bytes0, bytes1] = self.dcf.gen(f, s0s, bound)?.split().map(|kb| kb.to_bytes());
+        let party0 = Party::from_bytes(self.dcf.clone(), false, &bytes0)?;
+        let party1 = Party::from_bytes(self.dcf.clone(), true, &bytes1)?;
+        Ok([party0, party1])
+    }
+
+    /// Combines the outputs of party 0 and party 1 at the same inputs in the same order.
+    ///
+    /// Fails with [`DcfError::OutputLengthMismatch`] if they have different lengths,
+    /// which means they are not evaluated at the same inputs.
+    pub fn reconstruct(
+        &self,
+        ys0: &[[u8; LAMBDA]],
+        ys1: &[[u8; LAMBDA]],
+    ) -> Result<Vec<[u8; LAMBDA]>, DcfError> {
+        self.reconstruct_with::<G>(ys0, ys1)
+    }
+
+    /// [`TwoPartyDcf::reconstruct`] with the custom rule `C`, which must be valid for `G`.
+    ///
+    /// See [`Combine`] for which rules are valid.
+    pub fn reconstruct_with<C>(
+        &self,
+        ys0: &[[u8; LAMBDA]],
+        ys1: &[[u8; LAMBDA]],
+    ) -> Result<Vec<[u8; LAMBDA]>, DcfError>
+    where
+        C: Combine<LAMBDA>,
+    {
+        if ys0.len() != ys1.len() {
+            return Err(DcfError::OutputLengthMismatch {
+                len0: ys0.len(),
+                len1: ys1.len(),
+            });
+        }
+        Ok(ys0
+            .iter()
+            .zip(ys1.iter())
+            .map(|(y0, y1)| C::combine(y0, y1))
+            .collect())
+    }
+}
+
+/// One of the 2 parties, holding only its own key
+pub struct Party<const N: usize, const LAMBDA: usize, PrgT, G = XorGroup>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    dcf: Arc<DcfImpl<N, LAMBDA, PrgT, G>>,
+    b: bool,
+    k: Share<LAMBDA>,
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> Party<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Receives the key of the party `b` serialized by [`Share::to_bytes`].
+    ///
+    /// Fails if the bytes can not be deserialized, the key is for another `N`,
+    /// or it has other than exactly 1 seed, e.g., the dealer sends both seeds by mistake.
+    pub fn from_bytes(
+        dcf: Arc<DcfImpl<N, LAMBDA, PrgT, G>>,
+        b: bool,
+        bytes: &[u8],
+    ) -> Result<Self, DcfError> {
+        let k = Share::from_bytes::<N>(bytes)?;
+        if k.s0s.len() != 1 {
+            return Err(DcfError::SeedCountMismatch {
+                expected: 1,
+                got: k.s0s.len(),
+            });
+        }
+        Ok(Self { dcf, b, k })
+    }
+
+    /// `false` is party 0 and `true` is party 1
+    pub fn b(&self) -> bool {
+        self.b
+    }
+
+    /// The outputs of the party at `xs`, which alone reveal nothing about `$f(x)$`.
+    ///
+    /// Fails the same as [`Dcf::eval`].
+    pub fn eval(&self, xs: &[&[u8; N]]) -> Result<Vec<[u8; LAMBDA]>, DcfError> {
+        let mut ys = vec![[0; LAMBDA]; xs.len()];
+        self.dcf
+            .eval(self.b, &self.k, xs, &mut ys.iter_mut().collect::<Vec<_>>())?;
+        Ok(ys)
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::ArithGroup;
+
+    fn run<G: Group<16>>(bound: BoundState) {
+        let sim = TwoPartyDcf::<4, 16, _, G>::new(Aes256HirosePrg::new(KEYS));
+        let alpha: u32 = thread_rng().gen();
+        let f = CmpFn {
+            alpha: alpha.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let [party0, party1] = sim.setup(&f, bound, [&s0s[0], &s0s[1]]).unwrap();
+        assert_eq!((party0.b(), party1.b()), (false, true));
+        let xs: Vec<[u8; 4]> = (0..200)
+            .map(|_| thread_rng().gen::<u32>())
+            .chain([alpha, alpha.wrapping_add(1), alpha.wrapping_sub(1)])
+            .map(|x| x.to_be_bytes())
+            .collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let ys0 = party0.eval(&xs).unwrap();
+        let ys1 = party1.eval(&xs).unwrap();
+        let ys = sim.reconstruct(&ys0, &ys1).unwrap();
+        xs.iter()
+            .zip(ys.iter())
+            .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x, bound)));
+    }
+
+    #[test]
+    fn test_two_party_dcf_setup_then_eval_then_reconstruct_ok() {
+        run::<XorGroup>(BoundState::LtBeta);
+        run::<XorGroup>(BoundState::GtBeta);
+        run::<ArithGroup>(BoundState::LtBeta);
+        run::<ArithGroup>(BoundState::GtBeta);
+    }
+
+    /// XOR written by hand, which is the same rule as [`XorGroup`]
+    struct ByteXor;
+
+    impl Combine<16> for ByteXor {
+        fn combine(y0: &[u8; 16], y1: &[u8; 16]) -> [u8; 16] {
+            std::array::from_fn(|i| y0[i] ^ y1[i])
+        }
+    }
+
+    #[test]
+    fn test_two_party_dcf_reconstruct_with_custom_combine_ok() {
+        let sim = TwoPartyDcf::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let [party0, party1] = sim
+            .setup(&f, BoundState::LtBeta, [&s0s[0], &s0s[1]])
+            .unwrap();
+        let xs: Vec<[u8; 4]> = (0..100).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let ys0 = party0.eval(&xs).unwrap();
+        let ys1 = party1.eval(&xs).unwrap();
+        let ys = sim.reconstruct_with::<ByteXor>(&ys0, &ys1).unwrap();
+        assert_eq!(ys, sim.reconstruct(&ys0, &ys1).unwrap());
+        xs.iter()
+            .zip(ys.iter())
+            .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x, BoundState::LtBeta)));
+    }
+
+    #[test]
+    fn test_two_party_dcf_errs() {
+        let dcf = Arc::new(DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS)));
+        let sim = TwoPartyDcf::from_dcf(dcf.clone());
+        assert_eq!(
+            sim.reconstruct(&[[0; 16]; 2], &[[0; 16]; 3]).err(),
+            Some(DcfError::OutputLengthMismatch { len0: 2, len1: 3 })
+        );
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        // Both seeds are sent
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        assert_eq!(
+            Party::from_bytes(dcf, false, &k.to_bytes()).err(),
+            Some(DcfError::SeedCountMismatch {
+                expected: 1,
+                got: 2
+            })
+        );
+    }
+}
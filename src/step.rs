@@ -0,0 +1,704 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Step functions composed of multiple DCFs, e.g., for bucketized comparisons
+
+use crate::group::Group;
+use crate::{check_output_count, BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// Step function.
+///
+/// `$f(x) = \beta_i$` where `$t_i$` is the largest threshold `$\le x$`,
+/// and `$f(x) = 0$` if `x` is less than all of the thresholds.
+/// Like [`CmpFn`], `[u8; N]` is compared in the big-endian order.
+pub struct StepFn<const N: usize, const LAMBDA: usize> {
+    steps: Vec<([u8; N], [u8; LAMBDA])>,
+}
+
+impl<const N: usize, const LAMBDA: usize> StepFn<N, LAMBDA> {
+    /// `steps` are the `$(t_i, \beta_i)$` pairs, whose thresholds must be strictly increasing.
+    ///
+    /// Fails with [`DcfError::DuplicateThreshold`] or [`DcfError::UnsortedThresholds`]
+    /// at the first index `i` whose threshold is equal to or less than the one of `i - 1`.
+    pub fn new(steps: Vec<([u8; N], [u8; LAMBDA])>) -> Result<Self, DcfError> {
+        for (index, pair) in steps.windows(2).enumerate() {
+            let index = index + 1;
+            match pair[0].0.cmp(&pair[1].0) {
+                std::cmp::Ordering::Less => (),
+                std::cmp::Ordering::Equal => return Err(DcfError::DuplicateThreshold { index }),
+                std::cmp::Ordering::Greater => return Err(DcfError::UnsortedThresholds { index }),
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    /// `$f(x) = \beta$` iff. `$|x - c| < r$` in the band `$[c - r, c + r)$` for [`BoundState::LtBeta`],
+    /// or iff. `x` is outside of it for [`BoundState::GtBeta`].
+    /// [`BoundState::Leq`] and [`BoundState::Geq`] are the same but with the closed band `$[c - r, c + r]$`.
+    ///
+    /// `center` and `radius` are big-endian, and the band wraps around the domain,
+    /// e.g., `$c < r$` makes it `$[0, c + r) \cup [2^{8N} + c - r, 2^{8N})$`.
+    /// `$r \ge 2^{8N - 1}$` makes it the whole domain.
+    pub fn abs_threshold(
+        center: &[u8; N],
+        radius: &[u8; N],
+        beta: &[u8; LAMBDA],
+        bound: BoundState,
+    ) -> Self {
+        let (inside, outside) = (*beta, [0; LAMBDA]);
+        let (inside, outside) = match bound {
+            BoundState::LtBeta | BoundState::Leq => (inside, outside),
+            BoundState::GtBeta | BoundState::Geq => (outside, inside),
+        };
+        let closed = matches!(bound, BoundState::Leq | BoundState::Geq);
+        let steps = if radius.iter().all(|&byte| byte == 0) && !closed {
+            vec![([0; N], outside)]
+        } else if radius[0] & 0x80 != 0 {
+            vec![([0; N], inside)]
+        } else {
+            // `$0 < 2r + [closed] < 2^{8N}$`, so `lo != hi`
+            let lo = be_wrapping_sub(center, radius);
+            let mut hi = be_wrapping_add(center, radius);
+            if closed {
+                let mut one = [0; N];
+                one[N - 1] = 1;
+                hi = be_wrapping_add(&hi, &one);
+            }
+            if lo < hi {
+                vec![([0; N], outside), (lo, inside), (hi, outside)]
+            } else {
+                vec![([0; N], inside), (hi, outside), (lo, inside)]
+            }
+        };
+        // `lo` or `hi` can be 0, where the later step replaces the earlier one.
+        // Steps not changing the output need no keys.
+        let mut merged: Vec<([u8; N], [u8; LAMBDA])> = Vec::with_capacity(steps.len());
+        for (t, y) in steps {
+            if merged.last().is_some_and(|last| last.0 == t) {
+                merged.pop();
+            }
+            if merged.last().map_or([0; LAMBDA], |last| last.1) != y {
+                merged.push((t, y));
+            }
+        }
+        Self::new(merged).unwrap()
+    }
+
+    /// `$f(x) = \beta$` iff. `x` is in any of the intervals `$[a_j, b_j)$`, i.e., the indicator of their union.
+    ///
+    /// The intervals can be in any order, and overlapping or adjacent ones are merged first,
+    /// so there are at most 2 thresholds for each of them.
+    ///
+    /// Fails with [`DcfError::InvalidInterval`] at the first `j` without `$a_j < b_j$`.
+    pub fn interval_set(
+        intervals: &[([u8; N], [u8; N])],
+        beta: &[u8; LAMBDA],
+    ) -> Result<Self, DcfError> {
+        if let Some(index) = intervals.iter().position(|(a, b)| a >= b) {
+            return Err(DcfError::InvalidInterval { index });
+        }
+        let mut sorted = intervals.to_vec();
+        sorted.sort();
+        let mut merged: Vec<([u8; N], [u8; N])> = Vec::with_capacity(sorted.len());
+        for (a, b) in sorted {
+            match merged.last_mut() {
+                Some(last) if a <= last.1 => last.1 = last.1.max(b),
+                _ => merged.push((a, b)),
+            }
+        }
+        let steps = merged
+            .into_iter()
+            .flat_map(|(a, b)| [(a, *beta), (b, [0; LAMBDA])])
+            .collect();
+        Ok(Self::new(steps).unwrap())
+    }
+
+    pub fn steps(&self) -> &[([u8; N], [u8; LAMBDA])] {
+        &self.steps
+    }
+
+    /// Plaintext oracle. What the 2 parties' outputs should be reconstructed to.
+    pub fn eval_plain(&self, x: &[u8; N]) -> [u8; LAMBDA] {
+        self.steps
+            .iter()
+            .rev()
+            .find(|(t, _)| t <= x)
+            .map_or([0; LAMBDA], |(_, beta)| *beta)
+    }
+}
+
+/// `k` of a [`StepFn`], or of the other functions composed as a sum of DCFs like [`DcfImpl::gen_difference`].
+///
+/// Like [`Share`], `keys` are shared by the 2 parties and only `offsets[0]` is different.
+#[derive(Clone)]
+pub struct StepShare<const LAMBDA: usize> {
+    /// The DCFs whose outputs are summed.
+    /// For [`DcfImpl::gen_step`], 1 for each of the thresholds other than 0, in the same order.
+    pub keys: Vec<Share<LAMBDA>>,
+    /// Shares of the output of the threshold 0, which every input is `$\ge$`.
+    /// For the output of `gen_step`, its length is 2.
+    /// For the input of `eval_step`, the first one is used.
+    pub offsets: Vec<[u8; LAMBDA]>,
+}
+
+impl<const LAMBDA: usize> StepShare<LAMBDA> {
+    /// Splits the output of gen into the keys of party 0 and party 1 like [`Share::split`].
+    ///
+    /// Panics unless `offsets` has exactly 2 shares, or the same as [`Share::split`] for any of the DCFs.
+    #[must_use = "each of the 2 keys must be sent to its party"]
+    pub fn split(self) -> [Self; 2] {
+        assert_eq!(self.offsets.len(), 2, "only the output of gen can be split");
+        let (keys0, keys1) = self
+            .keys
+            .into_iter()
+            .map(|k| {
+                let [k0, k1] = k.split();
+                (k0, k1)
+            })
+            .unzip();
+        [
+            StepShare {
+                keys: keys0,
+                offsets: vec![self.offsets[0]],
+            },
+            StepShare {
+                keys: keys1,
+                offsets: vec![self.offsets[1]],
+            },
+        ]
+    }
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Gen of a [`StepFn`].
+    ///
+    /// `$f$` is the sum of the DCFs of `$[x \ge t_i]$` with `$\beta_i - \beta_{i - 1}$`,
+    /// each of which is a [`BoundState::GtBeta`] DCF at `$t_i - 1$`.
+    /// `s0s` are the seeds of them, 1 pair for each threshold, which should be randomly sampled.
+    /// The threshold 0 needs no DCF since it covers the whole domain,
+    /// and its seed of party 0 masks the output shared in [`StepShare::offsets`] instead.
+    ///
+    /// Fails with [`DcfError::SeedCountMismatch`] if the number of the seed pairs is not the one of the thresholds.
+    pub fn gen_step(
+        &self,
+        f: &StepFn<N, LAMBDA>,
+        s0s: &[[[u8; LAMBDA]; 2]],
+    ) -> Result<StepShare<LAMBDA>, DcfError> {
+        if s0s.len() != f.steps.len() {
+            return Err(DcfError::SeedCountMismatch {
+                expected: f.steps.len(),
+                got: s0s.len(),
+            });
+        }
+        let mut beta_prev = [0; LAMBDA];
+        let mut offsets = vec![[0; LAMBDA]; 2];
+        let mut keys = Vec::with_capacity(f.steps.len());
+        for ((t, beta), seeds) in f.steps.iter().zip(s0s.iter()) {
+            let mut delta = *beta;
+            G::sub(&mut delta, &beta_prev);
+            beta_prev = *beta;
+            if t.iter().all(|&byte| byte == 0) {
+                offsets[0] = seeds[0];
+                offsets[1] = delta;
+                G::sub(&mut offsets[1], &seeds[0]);
+                continue;
+            }
+            // No underflow since `t > 0`
+            let mut one = [0; N];
+            one[N - 1] = 1;
+            let alpha = be_wrapping_sub(t, &one);
+            let cmp_f = CmpFn { alpha, beta: delta };
+            keys.push(self.gen(&cmp_f, [&seeds[0], &seeds[1]], BoundState::GtBeta)?);
+        }
+        Ok(StepShare { keys, offsets })
+    }
+
+    /// Gen of [`StepFn::abs_threshold`], evaluated by [`DcfImpl::eval_step`].
+    ///
+    /// The step function has at most 3 thresholds,
+    /// and only the first as many pairs of `s0s` are used, which should be randomly sampled.
+    pub fn gen_abs_threshold(
+        &self,
+        center: &[u8; N],
+        radius: &[u8; N],
+        beta: &[u8; LAMBDA],
+        bound: BoundState,
+        s0s: &[[[u8; LAMBDA]; 2]; 3],
+    ) -> StepShare<LAMBDA> {
+        let f = StepFn::abs_threshold(center, radius, beta, bound);
+        self.gen_step(&f, &s0s[..f.steps.len()]).unwrap()
+    }
+
+    /// Gen of [`StepFn::interval_set`], evaluated by [`DcfImpl::eval_step`].
+    ///
+    /// Only the first as many pairs of `s0s` as the thresholds are used, which should be randomly sampled.
+    /// 2 pairs for each of `intervals` are always enough.
+    ///
+    /// Fails with the errors of [`StepFn::interval_set`]
+    /// or [`DcfError::SeedCountMismatch`] if there are fewer pairs than the thresholds.
+    pub fn gen_interval_set(
+        &self,
+        intervals: &[([u8; N], [u8; N])],
+        beta: &[u8; LAMBDA],
+        s0s: &[[[u8; LAMBDA]; 2]],
+    ) -> Result<StepShare<LAMBDA>, DcfError> {
+        let f = StepFn::interval_set(intervals, beta)?;
+        if s0s.len() < f.steps.len() {
+            return Err(DcfError::SeedCountMismatch {
+                expected: f.steps.len(),
+                got: s0s.len(),
+            });
+        }
+        self.gen_step(&f, &s0s[..f.steps.len()])
+    }
+
+    /// Gen of the difference of the DCFs at `a` and `b` with `bound`,
+    /// i.e., `$\beta ([x < b] - [x < a])$` for [`BoundState::LtBeta`], which is `beta` iff. `x` is in `$[a, b)$`,
+    /// or `$\beta ([x > a] - [x > b])$` for [`BoundState::GtBeta`], which is `beta` iff. `x` is in `$(a, b]$`.
+    /// Likewise, it is `beta` iff. `x` is in `$(a, b]$` for [`BoundState::Leq`] and in `$[a, b)$` for [`BoundState::Geq`].
+    ///
+    /// The 2 trees, one with `beta` and the other with `$-\beta$`, are packed into the same [`StepShare`],
+    /// which [`DcfImpl::eval_step`] evaluates.
+    /// `s0s` are the seeds of them, which should be randomly sampled.
+    ///
+    /// Fails with [`DcfError::InvalidInterval`] unless `a < b`.
+    pub fn gen_difference(
+        &self,
+        a: &[u8; N],
+        b: &[u8; N],
+        beta: &[u8; LAMBDA],
+        s0s: &[[[u8; LAMBDA]; 2]; 2],
+        bound: BoundState,
+    ) -> Result<StepShare<LAMBDA>, DcfError> {
+        if a >= b {
+            return Err(DcfError::InvalidInterval { index: 0 });
+        }
+        let mut beta_neg = *beta;
+        G::neg(&mut beta_neg);
+        let thresholds = match bound {
+            BoundState::LtBeta | BoundState::Leq => [(b, beta), (a, &beta_neg)],
+            BoundState::GtBeta | BoundState::Geq => [(a, beta), (b, &beta_neg)],
+        };
+        let keys = thresholds
+            .iter()
+            .zip(s0s.iter())
+            .map(|((t, beta), seeds)| {
+                let f = CmpFn {
+                    alpha: **t,
+                    beta: **beta,
+                };
+                self.gen_parts(&f, [&seeds[0], &seeds[1]], &bound, 0)
+            })
+            .collect();
+        Ok(StepShare {
+            keys,
+            offsets: vec![[0; LAMBDA]; 2],
+        })
+    }
+
+    /// Gen of the complement of the interval `$[a, b)$`,
+    /// i.e., `$\beta ([x < a] + [x \ge b])$`, which is `beta` iff. `x` is not in `$[a, b)$`.
+    ///
+    /// Unlike [`DcfImpl::gen_difference`], both trees have `beta`:
+    /// a [`BoundState::LtBeta`] DCF at `a` and a [`BoundState::GtBeta`] DCF at `$b - 1$`.
+    /// They are packed into the same [`StepShare`], which [`DcfImpl::eval_step`] evaluates.
+    /// `s0s` are the seeds of them, which should be randomly sampled.
+    ///
+    /// Fails with [`DcfError::InvalidInterval`] unless `a < b`.
+    pub fn gen_outside_interval(
+        &self,
+        a: &[u8; N],
+        b: &[u8; N],
+        beta: &[u8; LAMBDA],
+        s0s: &[[[u8; LAMBDA]; 2]; 2],
+    ) -> Result<StepShare<LAMBDA>, DcfError> {
+        if a >= b {
+            return Err(DcfError::InvalidInterval { index: 0 });
+        }
+        // No underflow since `b > a`
+        let mut one = [0; N];
+        one[N - 1] = 1;
+        let b_last = be_wrapping_sub(b, &one);
+        let thresholds = [(a, BoundState::LtBeta), (&b_last, BoundState::GtBeta)];
+        let keys = thresholds
+            .into_iter()
+            .zip(s0s.iter())
+            .map(|((t, bound), seeds)| {
+                let f = CmpFn {
+                    alpha: *t,
+                    beta: *beta,
+                };
+                self.gen_parts(&f, [&seeds[0], &seeds[1]], &bound, 0)
+            })
+            .collect();
+        Ok(StepShare {
+            keys,
+            offsets: vec![[0; LAMBDA]; 2],
+        })
+    }
+
+    /// Eval of a key from [`DcfImpl::gen_step`], which sums the outputs of all of its DCFs.
+    ///
+    /// Fails the same as [`Dcf::eval`] for any of the DCFs.
+    pub fn eval_step(
+        &self,
+        b: bool,
+        k: &StepShare<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) -> Result<(), DcfError> {
+        check_output_count(xs.len(), ys.len())?;
+        ys.iter_mut().for_each(|y| **y = k.offsets[0]);
+        let mut buf = vec![[0; LAMBDA]; xs.len()];
+        for key in k.keys.iter() {
+            self.eval(b, key, xs, &mut buf.iter_mut().collect::<Vec<_>>())?;
+            ys.iter_mut()
+                .zip(buf.iter())
+                .for_each(|(y, y_key)| G::add(y, y_key));
+        }
+        Ok(())
+    }
+}
+
+/// `lhs + rhs` of big-endian integers modulo `$2^{8N}$`
+fn be_wrapping_add<const N: usize>(lhs: &[u8; N], rhs: &[u8; N]) -> [u8; N] {
+    let mut res = [0; N];
+    let mut carry = false;
+    for i in (0..N).rev() {
+        let (sum, carry0) = lhs[i].overflowing_add(rhs[i]);
+        let (sum, carry1) = sum.overflowing_add(carry as u8);
+        res[i] = sum;
+        carry = carry0 || carry1;
+    }
+    res
+}
+
+/// `lhs - rhs` of big-endian integers modulo `$2^{8N}$`
+fn be_wrapping_sub<const N: usize>(lhs: &[u8; N], rhs: &[u8; N]) -> [u8; N] {
+    let mut res = [0; N];
+    let mut borrow = false;
+    for i in (0..N).rev() {
+        let (diff, borrow0) = lhs[i].overflowing_sub(rhs[i]);
+        let (diff, borrow1) = diff.overflowing_sub(borrow as u8);
+        res[i] = diff;
+        borrow = borrow0 || borrow1;
+    }
+    res
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, XorGroup};
+
+    fn run<G: Group<16>>(thresholds: &[u16]) {
+        let dcf = DcfImpl::<2, 16, _, G>::new(Aes256HirosePrg::new(KEYS));
+        let f = StepFn::new(
+            thresholds
+                .iter()
+                .map(|t| (t.to_be_bytes(), thread_rng().gen()))
+                .collect(),
+        )
+        .unwrap();
+        let s0s: Vec<[[u8; 16]; 2]> = thresholds.iter().map(|_| thread_rng().gen()).collect();
+        let k = dcf.gen_step(&f, &s0s).unwrap();
+        let xs: Vec<[u8; 2]> = (0..100)
+            .map(|_| thread_rng().gen::<u16>())
+            .chain(
+                thresholds
+                    .iter()
+                    .flat_map(|t| [*t, t.wrapping_add(1), t.wrapping_sub(1)]),
+            )
+            .chain([0, u16::MAX])
+            .map(|x| x.to_be_bytes())
+            .collect();
+        let xs: Vec<&[u8; 2]> = xs.iter().collect();
+        let ks = k.split();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; 16]; xs.len()];
+            dcf.eval_step(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| G::add(y0, y1));
+        xs.iter()
+            .zip(ys0.iter())
+            .for_each(|(x, y)| assert_eq!(y, &f.eval_plain(x)));
+    }
+
+    #[test]
+    fn test_dcf_gen_step_then_eval_step_ok() {
+        run::<XorGroup>(&[100, 1000, 40000]);
+        run::<ArithGroup>(&[100, 1000, 40000]);
+        // Including the threshold 0 and the max
+        run::<XorGroup>(&[0, 5, u16::MAX]);
+        run::<ArithGroup>(&[0, 5, u16::MAX]);
+        run::<ArithGroup>(&[0]);
+        run::<ArithGroup>(&[]);
+    }
+
+    #[test]
+    fn test_dcf_gen_interval_set_then_eval_step_ok() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let beta: [u8; 16] = thread_rng().gen();
+        let xs: Vec<[u8; 1]> = (0..=255).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        let in_any =
+            |intervals: &[(u8, u8)], x: u8| intervals.iter().any(|&(a, b)| a <= x && x < b);
+        [
+            // Disjoint and unsorted, including the one from 0
+            vec![(200, 255), (10, 20), (0, 3), (50, 51)],
+            // Overlapping, adjacent, and contained
+            vec![(10, 30), (20, 40), (40, 45), (100, 110), (102, 105)],
+            vec![],
+        ]
+        .iter()
+        .for_each(|intervals| {
+            let intervals_be: Vec<([u8; 1], [u8; 1])> =
+                intervals.iter().map(|&(a, b)| ([a], [b])).collect();
+            let f = StepFn::interval_set(&intervals_be, &beta).unwrap();
+            let s0s: Vec<[[u8; 16]; 2]> = (0..2 * intervals.len())
+                .map(|_| thread_rng().gen())
+                .collect();
+            let k = dcf.gen_interval_set(&intervals_be, &beta, &s0s).unwrap();
+            assert_eq!(
+                k.keys.len(),
+                f.steps().iter().filter(|(t, _)| t != &[0]).count()
+            );
+            let ks = k.split();
+            let [mut ys0, ys1] = [false, true].map(|b| {
+                let kb = &ks[b as usize];
+                let mut ys = vec![[0; 16]; xs.len()];
+                dcf.eval_step(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                    .unwrap();
+                ys
+            });
+            ys0.iter_mut()
+                .zip(ys1.iter())
+                .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+            xs.iter().zip(ys0.iter()).for_each(|(x, y)| {
+                let expected = if in_any(intervals, x[0]) {
+                    beta
+                } else {
+                    [0; 16]
+                };
+                assert_eq!(y, &expected, "x = {}", x[0]);
+                assert_eq!(y, &f.eval_plain(x));
+            });
+        });
+        // The merged intervals are `$[10, 45)$` and `$[100, 110)$`
+        let f = StepFn::interval_set(&[([10], [30]), ([20], [45]), ([100], [110])], &beta).unwrap();
+        assert_eq!(
+            f.steps(),
+            [
+                ([10], beta),
+                ([45], [0; 16]),
+                ([100], beta),
+                ([110], [0; 16])
+            ]
+        );
+        assert_eq!(
+            dcf.gen_interval_set(&[([10], [30]), ([40], [45])], &beta, &[[[0; 16]; 2]; 3])
+                .err(),
+            Some(DcfError::SeedCountMismatch {
+                expected: 4,
+                got: 3
+            })
+        );
+        assert_eq!(
+            dcf.gen_interval_set(&[([10], [30]), ([40], [40])], &beta, &[[[0; 16]; 2]; 4])
+                .err(),
+            Some(DcfError::InvalidInterval { index: 1 })
+        );
+        assert_eq!(
+            StepFn::<1, 16>::interval_set(&[([30], [10])], &beta).err(),
+            Some(DcfError::InvalidInterval { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_dcf_gen_abs_threshold_then_eval_step_ok() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let beta: [u8; 16] = thread_rng().gen();
+        let xs: Vec<[u8; 1]> = (0..=255).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        // Including wrapping below 0 with `c < r`, above the max, `r = 0`, and the whole domain
+        [
+            (100, 10),
+            (3, 10),
+            (250, 10),
+            (0, 1),
+            (100, 0),
+            (100, 127),
+            (100, 128),
+        ]
+        .iter()
+        .for_each(|&(c, r): &(u8, u8)| {
+            [
+                BoundState::LtBeta,
+                BoundState::GtBeta,
+                BoundState::Leq,
+                BoundState::Geq,
+            ]
+            .iter()
+            .for_each(|&bound| {
+                let s0s: [[[u8; 16]; 2]; 3] = thread_rng().gen();
+                let k = dcf.gen_abs_threshold(&[c], &[r], &beta, bound, &s0s);
+                let ks = k.split();
+                let [mut ys0, ys1] = [false, true].map(|b| {
+                    let kb = &ks[b as usize];
+                    let mut ys = vec![[0; 16]; xs.len()];
+                    dcf.eval_step(b, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                        .unwrap();
+                    ys
+                });
+                ys0.iter_mut()
+                    .zip(ys1.iter())
+                    .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+                xs.iter().zip(ys0.iter()).for_each(|(x, y)| {
+                    // `x - (c - r) < 2r` modulo 256, or `<=` for the closed band
+                    let offset = x[0].wrapping_sub(c.wrapping_sub(r)) as u16;
+                    let hit = match bound {
+                        BoundState::LtBeta => offset < 2 * r as u16,
+                        BoundState::GtBeta => offset >= 2 * r as u16,
+                        BoundState::Leq => offset <= 2 * r as u16,
+                        BoundState::Geq => offset > 2 * r as u16,
+                    };
+                    let expected = if hit { beta } else { [0; 16] };
+                    assert_eq!(y, &expected, "c = {}, r = {}, x = {}", c, r, x[0]);
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_difference_then_eval_step_ok() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let beta: [u8; 16] = thread_rng().gen();
+        let xs: Vec<[u8; 1]> = (0..=255).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        [(10u8, 20u8), (0, 1), (0, 255), (254, 255)]
+            .iter()
+            .for_each(|&(a, b)| {
+                [
+                    BoundState::LtBeta,
+                    BoundState::GtBeta,
+                    BoundState::Leq,
+                    BoundState::Geq,
+                ]
+                .iter()
+                .for_each(|&bound| {
+                    let s0s: [[[u8; 16]; 2]; 2] = thread_rng().gen();
+                    let k = dcf.gen_difference(&[a], &[b], &beta, &s0s, bound).unwrap();
+                    let ks = k.split();
+                    let [mut ys0, ys1] = [false, true].map(|party| {
+                        let kb = &ks[party as usize];
+                        let mut ys = vec![[0; 16]; xs.len()];
+                        dcf.eval_step(party, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                            .unwrap();
+                        ys
+                    });
+                    ys0.iter_mut()
+                        .zip(ys1.iter())
+                        .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+                    xs.iter().zip(ys0.iter()).for_each(|(x, y)| {
+                        let hit = match bound {
+                            BoundState::LtBeta | BoundState::Geq => a <= x[0] && x[0] < b,
+                            BoundState::GtBeta | BoundState::Leq => a < x[0] && x[0] <= b,
+                        };
+                        let expected = if hit { beta } else { [0; 16] };
+                        assert_eq!(y, &expected, "a = {}, b = {}, x = {}", a, b, x[0]);
+                    });
+                });
+            });
+    }
+
+    #[test]
+    fn test_dcf_gen_difference_empty_interval_err() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[[u8; 16]; 2]; 2] = thread_rng().gen();
+        [(20u8, 10u8), (10, 10)].iter().for_each(|&(a, b)| {
+            assert_eq!(
+                dcf.gen_difference(&[a], &[b], &[1; 16], &s0s, BoundState::LtBeta)
+                    .err(),
+                Some(DcfError::InvalidInterval { index: 0 })
+            );
+        });
+    }
+
+    #[test]
+    fn test_dcf_gen_outside_interval_then_eval_step_ok() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let beta: [u8; 16] = thread_rng().gen();
+        let xs: Vec<[u8; 1]> = (0..=255).map(|x| [x]).collect();
+        let xs: Vec<&[u8; 1]> = xs.iter().collect();
+        [(10u8, 20u8), (0, 1), (0, 255), (254, 255)]
+            .iter()
+            .for_each(|&(a, b)| {
+                let s0s: [[[u8; 16]; 2]; 2] = thread_rng().gen();
+                let k = dcf.gen_outside_interval(&[a], &[b], &beta, &s0s).unwrap();
+                let ks = k.split();
+                let [mut ys0, ys1] = [false, true].map(|party| {
+                    let kb = &ks[party as usize];
+                    let mut ys = vec![[0; 16]; xs.len()];
+                    dcf.eval_step(party, kb, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+                        .unwrap();
+                    ys
+                });
+                ys0.iter_mut()
+                    .zip(ys1.iter())
+                    .for_each(|(y0, y1)| ArithGroup::add(y0, y1));
+                xs.iter().zip(ys0.iter()).for_each(|(x, y)| {
+                    let outside = x[0] < a || x[0] >= b;
+                    let expected = if outside { beta } else { [0; 16] };
+                    assert_eq!(y, &expected, "a = {}, b = {}, x = {}", a, b, x[0]);
+                });
+            });
+    }
+
+    #[test]
+    fn test_dcf_gen_outside_interval_empty_interval_err() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[[u8; 16]; 2]; 2] = thread_rng().gen();
+        [(20u8, 10u8), (10, 10)].iter().for_each(|&(a, b)| {
+            assert_eq!(
+                dcf.gen_outside_interval(&[a], &[b], &[1; 16], &s0s).err(),
+                Some(DcfError::InvalidInterval { index: 0 })
+            );
+        });
+    }
+
+    #[test]
+    fn test_step_fn_new_errs() {
+        let beta = [1; 16];
+        assert_eq!(
+            StepFn::new(vec![([0, 1], beta), ([0, 2], beta), ([0, 2], beta)]).err(),
+            Some(DcfError::DuplicateThreshold { index: 2 })
+        );
+        assert_eq!(
+            StepFn::new(vec![([0, 2], beta), ([0, 1], beta)]).err(),
+            Some(DcfError::UnsortedThresholds { index: 1 })
+        );
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let f = StepFn::new(vec![([0, 1], beta)]).unwrap();
+        assert_eq!(
+            dcf.gen_step(&f, &[]).err(),
+            Some(DcfError::SeedCountMismatch {
+                expected: 1,
+                got: 0
+            })
+        );
+    }
+}
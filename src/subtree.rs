@@ -0,0 +1,501 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keys restricted to a sub-range of the domain, e.g., for servers each evaluating a slice of it
+
+use bitvec::prelude::*;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::group::Group;
+use crate::utils::for_each_zip;
+#[cfg(feature = "zeroize")]
+use crate::utils::zeroize_expanded;
+use crate::{Cw, DcfImpl, Prg, Share};
+
+/// Key of a party pruned to the subtree containing a range.
+///
+/// Unlike [`Share`], it holds the state of the party at the subtree root,
+/// so it is party-specific and must be evaluated with the same `b` it was restricted with.
+#[derive(Clone)]
+pub struct SubtreeShare<const LAMBDA: usize> {
+    /// Path from the tree root to the subtree root, excluding the leading fixed bits.
+    /// Only inputs with the prefix can be evaluated.
+    pub prefix: BitVec<u8, Msb0>,
+    /// `$s$` at the subtree root
+    pub seed: [u8; LAMBDA],
+    /// `$t$` at the subtree root
+    pub t: bool,
+    /// Output accumulated along `prefix`, before the sign of the party is applied
+    pub v: [u8; LAMBDA],
+    /// Correlation words of the levels below the subtree root
+    pub cws: Vec<Cw<LAMBDA>>,
+    /// `$CW^{(n + 1)}$`
+    pub cw_np1: [u8; LAMBDA],
+}
+
+/// State of a party at the root of a subtree, from [`DcfImpl::eval_subtree_roots`].
+///
+/// Unlike a DPF, a DCF accumulates outputs along the path,
+/// so besides `$s$` and `$t$`, `v` is required to resume eval below the root.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize))]
+pub struct SubtreeRoot<const LAMBDA: usize> {
+    /// Number of the levels above the root, excluding the leading fixed bits
+    pub depth: usize,
+    /// `$s$` at the root
+    pub seed: [u8; LAMBDA],
+    /// `$t$` at the root
+    pub t: bool,
+    /// Output accumulated along the path to the root, before the sign of the party is applied
+    pub v: [u8; LAMBDA],
+}
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Restricts the key `k` of the party `b` to the smallest subtree containing `[lo, hi)`.
+    ///
+    /// Walks the common prefix of `lo` and `hi - 1` once,
+    /// so evaluating the result only walks the levels below it.
+    /// `lo < hi` is required.
+    pub fn restrict_to_range(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        lo: &[u8; N],
+        hi: &[u8; N],
+    ) -> SubtreeShare<LAMBDA> {
+        assert!(lo < hi);
+        let n = k.cws.len();
+        assert!(n > 0 && n <= N * 8);
+        let fixed = N * 8 - n;
+        let mut last = *hi;
+        // No underflow since `hi > lo`
+        for byte in last.iter_mut().rev() {
+            let (res, borrow) = byte.overflowing_sub(1);
+            *byte = res;
+            if !borrow {
+                break;
+            }
+        }
+        let lo_bits = &lo.view_bits::<Msb0>()[fixed..];
+        let last_bits = &last.view_bits::<Msb0>()[fixed..];
+        let depth = lo_bits
+            .iter()
+            .zip(last_bits.iter())
+            .take_while(|(l, h)| l == h)
+            .count();
+        let mut s = *self.party_seed(b, k);
+        let mut t = b;
+        let mut v = [0; LAMBDA];
+        for (i, x_i) in lo_bits[..depth].iter().by_vals().enumerate() {
+            (s, t) = self.eval_level(&s, t, &k.cws[i], x_i, &mut v);
+        }
+        SubtreeShare {
+            prefix: lo_bits[..depth].to_bitvec(),
+            seed: s,
+            t,
+            v,
+            cws: k.cws[depth..].to_vec(),
+            cw_np1: k.cw_np1,
+        }
+    }
+
+    /// Like [`crate::Dcf::eval`] but with a key from [`DcfImpl::restrict_to_range`].
+    ///
+    /// `b` must be the same as the one used to restrict the key.
+    /// Panics if any of `xs` is outside of the subtree.
+    pub fn eval_subtree(
+        &self,
+        b: bool,
+        k: &SubtreeShare<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) {
+        let depth = k.prefix.len();
+        let fixed = N * 8 - depth - k.cws.len();
+        let f = |x: &[u8; N], y: &mut [u8; LAMBDA]| {
+            let x_bits = &x.view_bits::<Msb0>()[fixed..];
+            assert_eq!(&x_bits[..depth], &k.prefix, "x is outside of the subtree");
+            let mut s = k.seed;
+            let mut t = k.t;
+            *y = k.v;
+            for (cw, x_i) in k.cws.iter().zip(x_bits[depth..].iter().by_vals()) {
+                (s, t) = self.eval_level(&s, t, cw, x_i, y);
+            }
+            self.eval_leaf(b, &s, t, &k.cw_np1, y);
+            #[cfg(feature = "zeroize")]
+            s.zeroize();
+        };
+        for_each_zip(xs, ys, |x, y| f(x, y));
+    }
+
+    /// The states of the party `b` at all of the `$2^{depth}$` nodes of the level `depth`, in the domain order,
+    /// e.g., for a coordinator to ship each subtree to a worker, which resumes by [`DcfImpl::eval_from_subtree`].
+    ///
+    /// `depth` excludes the leading fixed bits of [`crate::Dcf::gen_fixed_bits`],
+    /// and must be no larger than the number of the levels of `k`.
+    pub fn eval_subtree_roots(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        depth: usize,
+    ) -> Vec<SubtreeRoot<LAMBDA>> {
+        assert!(depth <= k.cws.len());
+        assert!(depth < usize::BITS as usize, "too many subtrees");
+        let mut roots = vec![SubtreeRoot {
+            depth: 0,
+            seed: *self.party_seed(b, k),
+            t: b,
+            v: [0; LAMBDA],
+        }];
+        for cw in k.cws[..depth].iter() {
+            #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+            let mut parents = std::mem::take(&mut roots);
+            roots = parents
+                .iter()
+                .flat_map(|root| {
+                    #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+                    let mut expanded = self.prg_gen(&root.seed);
+                    let children = [false, true].map(|x_i| {
+                        let mut v = root.v;
+                        let (seed, t) = self.eval_level_expanded(expanded, root.t, cw, x_i, &mut v);
+                        SubtreeRoot {
+                            depth: root.depth + 1,
+                            seed,
+                            t,
+                            v,
+                        }
+                    });
+                    #[cfg(feature = "zeroize")]
+                    zeroize_expanded(&mut expanded);
+                    children
+                })
+                .collect();
+            #[cfg(feature = "zeroize")]
+            parents.zeroize();
+        }
+        roots
+    }
+
+    /// Resumes eval of the key `k` of the party `b` from `root`,
+    /// which must be from [`DcfImpl::eval_subtree_roots`] with the same `b` and `k`.
+    ///
+    /// Only the bits of `xs` below the root are read,
+    /// so all of `xs` must be in the subtree, whose prefix is the index of `root` in the returned order.
+    /// Only the public part of `k` is used.
+    pub fn eval_from_subtree(
+        &self,
+        b: bool,
+        root: &SubtreeRoot<LAMBDA>,
+        k: &Share<LAMBDA>,
+        xs: &[&[u8; N]],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) {
+        let n = k.cws.len();
+        assert!(n > 0 && n <= N * 8);
+        assert!(root.depth <= n);
+        let fixed = N * 8 - n;
+        let f = |x: &[u8; N], y: &mut [u8; LAMBDA]| {
+            let suffix_bits = &x.view_bits::<Msb0>()[fixed + root.depth..];
+            let mut s = root.seed;
+            let mut t = root.t;
+            *y = root.v;
+            for (cw, x_i) in k.cws[root.depth..].iter().zip(suffix_bits.iter().by_vals()) {
+                (s, t) = self.eval_level(&s, t, cw, x_i, y);
+            }
+            self.eval_leaf(b, &s, t, &k.cw_np1, y);
+            #[cfg(feature = "zeroize")]
+            s.zeroize();
+        };
+        for_each_zip(xs, ys, |x, y| f(x, y));
+    }
+
+    /// Evaluates the key `k` of the party `b` at the indices in `[lo, hi)` for which `keep` is true,
+    /// and appends the outputs to `out` in order, e.g., for sparse queries with a stride.
+    ///
+    /// The indices are the ones of [`DcfImpl::eval_full_visit`].
+    /// Only the subtrees overlapping the range are walked.
+    /// `lo < hi` and `hi` no larger than the domain size are required.
+    pub fn eval_range_filtered<F>(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        lo: u128,
+        hi: u128,
+        keep: F,
+        out: &mut Vec<[u8; LAMBDA]>,
+    ) where
+        F: Fn(u128) -> bool,
+    {
+        let n = k.cws.len();
+        assert!(n > 0 && n <= N * 8);
+        assert!(n <= 128, "the domain indexes overflow u128");
+        assert!(lo < hi);
+        assert!(hi - 1 <= u128::MAX >> (128 - n), "hi is out of the domain");
+        self.visit_node(
+            b,
+            self.party_seed(b, k),
+            b,
+            &[0; LAMBDA],
+            &k.cws,
+            &k.cw_np1,
+            0,
+            &(lo..=hi - 1),
+            &mut |i, y| {
+                if keep(i) {
+                    out.push(*y);
+                }
+            },
+        );
+    }
+
+    /// Evaluates the key `k` of the party `b` at `indices` in any order, with the output of `indices[i]` at `ys[i]`.
+    ///
+    /// The indices are the ones of [`DcfImpl::eval_full_visit`].
+    /// They are walked in the sorted order, keeping the path of the last one,
+    /// so only the levels below the prefix shared with it are walked,
+    /// e.g., half of the leaves are reused for a stride-2 pattern.
+    /// Each index must be in the domain of the key.
+    pub fn eval_pattern(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        indices: &[u128],
+        ys: &mut [&mut [u8; LAMBDA]],
+    ) {
+        let n = k.cws.len();
+        assert!(n > 0 && n <= N * 8);
+        assert!(n <= 128, "the domain indexes overflow u128");
+        assert_eq!(indices.len(), ys.len());
+        let max = u128::MAX >> (128 - n);
+        let mut order: Vec<usize> = (0..indices.len()).collect();
+        order.sort_unstable_by_key(|&i| indices[i]);
+        // `path[d]` is `$(s, t, v)$` after the first `d` levels of the last index
+        let mut path = Vec::with_capacity(n + 1);
+        path.push((*self.party_seed(b, k), b, [0; LAMBDA]));
+        let mut last = None;
+        for i in order {
+            let index = indices[i];
+            assert!(index <= max, "index {} is out of the domain", index);
+            let shared = last.map_or(0, |last: u128| {
+                ((last ^ index) << (128 - n)).leading_zeros().min(n as u32) as usize
+            });
+            #[cfg(feature = "zeroize")]
+            path[shared + 1..].iter_mut().for_each(zeroize_path_node);
+            path.truncate(shared + 1);
+            for (d, cw) in k.cws.iter().enumerate().skip(shared) {
+                let (s, t, mut v) = path[d];
+                let x_d = (index >> (n - 1 - d)) & 1 == 1;
+                let (s, t) = self.eval_level(&s, t, cw, x_d, &mut v);
+                path.push((s, t, v));
+            }
+            let (s, t, v) = path[n];
+            *ys[i] = v;
+            self.eval_leaf(b, &s, t, &k.cw_np1, ys[i]);
+            last = Some(index);
+        }
+        #[cfg(feature = "zeroize")]
+        path.iter_mut().for_each(zeroize_path_node);
+    }
+}
+
+/// Zeroes `$(s, t, v)$` of a node on the path of [`DcfImpl::eval_pattern`]
+#[cfg(feature = "zeroize")]
+fn zeroize_path_node<const LAMBDA: usize>((s, t, v): &mut ([u8; LAMBDA], bool, [u8; LAMBDA])) {
+    s.zeroize();
+    t.zeroize();
+    v.zeroize();
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::utils::xor;
+    use crate::{BoundState, CmpFn, Dcf};
+
+    #[test]
+    fn test_restrict_to_range_then_eval_same_as_full() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: 0x1256u16.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let (lo, hi) = (0x1230u16, 0x1280u16);
+        let xs: Vec<[u8; 2]> = (lo..hi).map(|x| x.to_be_bytes()).collect();
+        let xs: Vec<&[u8; 2]> = xs.iter().collect();
+        let ks = k.split();
+        let ys = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let kr = dcf.restrict_to_range(b, kb, &lo.to_be_bytes(), &hi.to_be_bytes());
+            // 0x1230 and 0x127f share the leading 9 bits
+            assert_eq!(kr.prefix.len(), 9);
+            assert_eq!(kr.cws.len(), 16 - 9);
+            let mut ys_full = vec![[0; 16]; xs.len()];
+            let mut ys_restricted = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys_full.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            dcf.eval_subtree(
+                b,
+                &kr,
+                &xs,
+                &mut ys_restricted.iter_mut().collect::<Vec<_>>(),
+            );
+            assert_eq!(ys_full, ys_restricted);
+            ys_restricted
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                xor(&[&ys[0][i], &ys[1][i]]),
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of the subtree")]
+    fn test_eval_subtree_outside_panics() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: 0x1256u16.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let [k, _] = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let kr = dcf.restrict_to_range(false, &k, &[0x12, 0x30], &[0x12, 0x80]);
+        dcf.eval_subtree(false, &kr, &[&[0x13, 0x00]], &mut [&mut [0; 16]]);
+    }
+
+    #[test]
+    fn test_dcf_eval_range_filtered_same_as_filtering_eval() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: 0x1256u16.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let ks = dcf
+            .gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta)
+            .unwrap()
+            .split();
+        let (lo, hi) = (0x1203u16, 0x12a1u16);
+        let keep = |i: u128| i.is_multiple_of(3) || i & 0xf == 0x5;
+        let xs: Vec<[u8; 2]> = (lo..hi)
+            .filter(|&x| keep(x as u128))
+            .map(|x| x.to_be_bytes())
+            .collect();
+        let xs: Vec<&[u8; 2]> = xs.iter().collect();
+        [false, true].into_iter().for_each(|b| {
+            let kb = &ks[b as usize];
+            let mut ys_expected = vec![[0; 16]; xs.len()];
+            dcf.eval(b, kb, &xs, &mut ys_expected.iter_mut().collect::<Vec<_>>())
+                .unwrap();
+            let mut out = vec![];
+            dcf.eval_range_filtered(b, kb, lo as u128, hi as u128, keep, &mut out);
+            assert_eq!(out, ys_expected);
+        });
+        // The whole domain
+        let mut out = vec![];
+        dcf.eval_range_filtered(false, &ks[0], 0, 1 << 16, |_| true, &mut out);
+        assert_eq!(out.len(), 1 << 16);
+    }
+
+    #[test]
+    fn test_dcf_eval_pattern_same_as_eval() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: 0x1256u16.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        // Stride 2 in reverse, so that the sorting matters, with a duplicate and the ends of the domain
+        let indices: Vec<u128> = (0x1200..0x1300u32)
+            .step_by(2)
+            .rev()
+            .chain([0x1256, 0, 0xffff])
+            .map(|i| i as u128)
+            .collect();
+        let xs: Vec<[u8; 2]> = indices.iter().map(|&i| (i as u16).to_be_bytes()).collect();
+        let xs: Vec<&[u8; 2]> = xs.iter().collect();
+        let ks = k.split();
+        let ys = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let ys_expected: Vec<[u8; 16]> =
+                xs.iter().map(|x| dcf.eval_at(b, kb, x).unwrap()).collect();
+            let mut ys = vec![[0; 16]; indices.len()];
+            dcf.eval_pattern(b, kb, &indices, &mut ys.iter_mut().collect::<Vec<_>>());
+            assert_eq!(ys, ys_expected);
+            ys
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                xor(&[&ys[0][i], &ys[1][i]]),
+                f.eval_plain(x, BoundState::LtBeta)
+            );
+        });
+    }
+
+    #[test]
+    fn test_dcf_eval_subtree_roots_then_eval_from_subtree_same_as_eval() {
+        let dcf = DcfImpl::<2, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let alpha: u16 = thread_rng().gen();
+        let f = CmpFn {
+            alpha: alpha.to_be_bytes(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let xs: Vec<u16> = (0..500)
+            .map(|_| thread_rng().gen())
+            .chain([
+                0,
+                u16::MAX,
+                alpha,
+                alpha.wrapping_sub(1),
+                alpha.wrapping_add(1),
+            ])
+            .collect();
+        let ks = k.split();
+        let ys = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let roots = dcf.eval_subtree_roots(b, kb, 4);
+            assert_eq!(roots.len(), 16);
+            // The workers only need the public part
+            let mut public = kb.clone();
+            public.s0s.clear();
+            xs.iter()
+                .map(|x| {
+                    let x_bytes = x.to_be_bytes();
+                    let mut y_expected = [0; 16];
+                    dcf.eval(b, kb, &[&x_bytes], &mut [&mut y_expected])
+                        .unwrap();
+                    let mut y = [0; 16];
+                    let root = &roots[(x >> 12) as usize];
+                    dcf.eval_from_subtree(b, root, &public, &[&x_bytes], &mut [&mut y]);
+                    assert_eq!(y, y_expected);
+                    y
+                })
+                .collect::<Vec<_>>()
+        });
+        xs.iter().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                xor(&[&ys[0][i], &ys[1][i]]),
+                f.eval_plain(&x.to_be_bytes(), BoundState::LtBeta)
+            );
+        });
+    }
+}
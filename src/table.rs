@@ -0,0 +1,93 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Precomputed outputs of a key over the whole of a small domain, for repeated queries by lookup
+
+use crate::group::Group;
+use crate::{check_domain_indexable, check_party_share, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// Outputs of the party `b` of a key at every point of the domain, from [`Share::precompute_table`].
+///
+/// It holds `$2^{8N}$` outputs, i.e., `$2^{8N} \lambda$` bytes,
+/// e.g., 4 KiB for `N = 1` and 1 MiB for `N = 2` with `LAMBDA = 16`, but 256 MiB for `N = 3`,
+/// so it is only practical for small `N`.
+/// The outputs are shares like the ones of eval, so the table is as secret as the key.
+#[derive(Clone)]
+pub struct EvalTable<const N: usize, const LAMBDA: usize> {
+    ys: Vec<[u8; LAMBDA]>,
+}
+
+impl<const N: usize, const LAMBDA: usize> EvalTable<N, LAMBDA> {
+    /// The output at `x`, the same as eval with the key
+    pub fn get(&self, x: &[u8; N]) -> &[u8; LAMBDA] {
+        let i = x.iter().fold(0, |i, &byte| (i << 8) | byte as usize);
+        &self.ys[i]
+    }
+
+    /// All of the outputs, with the one of `x` at the big-endian integer of `x`
+    pub fn as_slice(&self) -> &[[u8; LAMBDA]] {
+        &self.ys
+    }
+}
+
+impl<const LAMBDA: usize> Share<LAMBDA> {
+    /// Evals the key as the party `b` at every point of the domain once by [`Dcf::eval_all`],
+    /// after which each query is a lookup by [`EvalTable::get`].
+    ///
+    /// See [`EvalTable`] for the memory cost.
+    /// Fails the same as [`Dcf::eval_all`] before anything is allocated.
+    pub fn precompute_table<const N: usize, PrgT, G>(
+        &self,
+        dcf: &DcfImpl<N, LAMBDA, PrgT, G>,
+        b: bool,
+    ) -> Result<EvalTable<N, LAMBDA>, DcfError>
+    where
+        PrgT: Prg<LAMBDA>,
+        G: Group<LAMBDA>,
+    {
+        check_domain_indexable::<N>()?;
+        check_party_share::<N, LAMBDA>(self, &dcf.prg_check())?;
+        let mut ys = vec![[0; LAMBDA]; 1 << (8 * N)];
+        dcf.eval_all(b, self, &mut ys)?;
+        Ok(EvalTable { ys })
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, BoundState, CmpFn};
+
+    #[test]
+    fn test_share_precompute_table_same_as_eval() {
+        let dcf = DcfImpl::<1, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.split();
+        let tables = [
+            k0.precompute_table(&dcf, false).unwrap(),
+            k1.precompute_table(&dcf, true).unwrap(),
+        ];
+        (0..=255u8).for_each(|x| {
+            [(&k0, false), (&k1, true)].iter().for_each(|(kb, b)| {
+                assert_eq!(
+                    tables[*b as usize].get(&[x]),
+                    &dcf.eval_at(*b, kb, &[x]).unwrap()
+                );
+            });
+            let mut y = *tables[0].get(&[x]);
+            ArithGroup::add(&mut y, tables[1].get(&[x]));
+            assert_eq!(y, f.eval_plain(&[x], BoundState::LtBeta));
+        });
+        assert_eq!(tables[0].as_slice().len(), 256);
+    }
+}
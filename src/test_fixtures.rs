@@ -0,0 +1,56 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shared by the tests of the modules
+
+use std::cell::Cell;
+
+use bitvec::prelude::*;
+use rand::{thread_rng, Rng};
+
+use crate::Share;
+
+thread_local! {
+    /// Test-only hook making gen drop the last correction word to check that the debug assertions fire
+    pub static CORRUPT_GEN_STATE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Keys of [`crate::prg::Aes256HirosePrg`] with `LAMBDA = 16`
+pub const KEYS: [&[u8; 32]; 2] = [
+    b"j9\x1b_\xb3X\xf33\xacW\x15\x1b\x0812K\xb3I\xb9\x90r\x1cN\xb5\xee9W\xd3\xbb@\xc6d",
+    b"\x9b\x15\xc8\x0f\xb7\xbc!q\x9e\x89\xb8\xf7\x0e\xa0S\x9dN\xfa\x0c;\x16\xe4\x98\x82b\xfcdy\xb5\x8c{\xc2",
+];
+
+/// Panics if any bit of the correction words, which are public, agrees with the bit of `$\alpha$` of its level
+/// far from half of the time over the keys from `gen` of random `$\alpha$`s and seeds,
+/// e.g., a bit of `$\beta$` not masked by the PRG
+pub fn assert_cws_hide_alpha<const N: usize, const LAMBDA: usize>(
+    gen: impl Fn(&[u8; N], [&[u8; LAMBDA]; 2]) -> Share<LAMBDA>,
+) {
+    const KEY_NUM: usize = 256;
+    let mut agrees = vec![vec![0usize; 2 * 8 * LAMBDA + 2]; 8 * N];
+    for _ in 0..KEY_NUM {
+        let mut alpha = [0; N];
+        let mut s0s = [[0; LAMBDA]; 2];
+        thread_rng().fill(&mut alpha[..]);
+        s0s.iter_mut().for_each(|s0| thread_rng().fill(&mut s0[..]));
+        let k = gen(&alpha, [&s0s[0], &s0s[1]]);
+        assert_eq!(k.cws.len(), 8 * N);
+        k.cws.iter().enumerate().for_each(|(i, cw)| {
+            let alpha_i = alpha.view_bits::<Msb0>()[i];
+            let bits = cw.s.view_bits::<Lsb0>().iter().by_vals();
+            let bits = bits.chain(cw.v.view_bits::<Lsb0>().iter().by_vals());
+            bits.chain([cw.tl, cw.tr])
+                .zip(agrees[i].iter_mut())
+                .for_each(|(bit, agree)| *agree += (bit == alpha_i) as usize);
+        });
+    }
+    agrees.iter().enumerate().for_each(|(i, level)| {
+        level.iter().enumerate().for_each(|(j, &agree)| {
+            assert!(
+                (KEY_NUM / 4..KEY_NUM * 3 / 4).contains(&agree),
+                "bit {j} of the cw of level {i} agrees with alpha {agree} of {KEY_NUM} times"
+            );
+        });
+    });
+}
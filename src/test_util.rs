@@ -0,0 +1,222 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to validate a [`crate::Dcf`] outside of the unit tests
+
+use rand::Rng;
+
+use crate::group::Group;
+use crate::{BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+impl<const N: usize, const LAMBDA: usize, PrgT, G> DcfImpl<N, LAMBDA, PrgT, G>
+where
+    PrgT: Prg<LAMBDA>,
+    G: Group<LAMBDA>,
+{
+    /// Runs both parties in this process: gen with seeds from `rng`, eval each party,
+    /// and returns the outputs reconstructed in `G`, i.e., what `f` with `bound` should be at `xs`.
+    ///
+    /// **Only for testing and simulation.**
+    /// Whoever calls it sees `f`, both keys, and both outputs,
+    /// which is the opposite of what a real deployment needs,
+    /// where the dealer and each party run on their own and the parties only see their key and output.
+    pub fn simulate<R>(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        bound: BoundState,
+        xs: &[&[u8; N]],
+        rng: &mut R,
+    ) -> Vec<[u8; LAMBDA]>
+    where
+        R: Rng + ?Sized,
+    {
+        let mut s0s = [[0; LAMBDA]; 2];
+        s0s.iter_mut().for_each(|s0| rng.fill_bytes(s0));
+        let ks = self.gen_parts(f, [&s0s[0], &s0s[1]], &bound, 0).split();
+        let [mut ys0, ys1] = [false, true].map(|b| {
+            let kb = &ks[b as usize];
+            let mut ys = vec![[0; LAMBDA]; xs.len()];
+            self.eval_parts(
+                b,
+                &kb.s0s[0],
+                &kb.cws,
+                &kb.cw_np1,
+                xs,
+                &mut ys.iter_mut().collect::<Vec<_>>(),
+            );
+            ys
+        });
+        ys0.iter_mut()
+            .zip(ys1.iter())
+            .for_each(|(y0, y1)| G::add(y0, y1));
+        ys0
+    }
+
+    /// Chi-square statistic of the bytes of the outputs of the party `b` at `sample_points`
+    /// against the uniform distribution, e.g., to detect a broken PRG leaking structure.
+    ///
+    /// A single party's outputs should be pseudorandom, so the statistic should follow the chi-square distribution
+    /// with 255 degrees of freedom, whose mean is 255 and standard deviation is about 22.6.
+    /// Much larger values mean the outputs are far from uniform.
+    /// `sample_points` should be distinct and give enough bytes,
+    /// e.g., `sample_points.len() * LAMBDA >= 1280` for at least 5 of each byte value expected.
+    ///
+    /// Fails with [`DcfError::NoSamplePoints`] if `sample_points` is empty, or the same as [`Dcf::eval`].
+    pub fn statistical_uniformity_test(
+        &self,
+        b: bool,
+        k: &Share<LAMBDA>,
+        sample_points: &[&[u8; N]],
+    ) -> Result<f64, DcfError> {
+        if sample_points.is_empty() {
+            return Err(DcfError::NoSamplePoints);
+        }
+        let mut ys = vec![[0; LAMBDA]; sample_points.len()];
+        self.eval(b, k, sample_points, &mut ys.iter_mut().collect::<Vec<_>>())?;
+        let mut counts = [0u64; 256];
+        ys.iter()
+            .flatten()
+            .for_each(|&byte| counts[byte as usize] += 1);
+        let expected = (ys.len() * LAMBDA) as f64 / 256.0;
+        Ok(counts
+            .iter()
+            .map(|&count| {
+                let diff = count as f64 - expected;
+                diff * diff / expected
+            })
+            .sum())
+    }
+
+    /// Statistical check of a key pair over a domain too large to enumerate.
+    ///
+    /// Draws `samples` random `x`, evaluates both parties, reconstructs by [`DcfImpl::reconstruct`],
+    /// and compares with [`CmpFn::eval_plain`].
+    /// `k0` and `k1` are the keys of party 0 and 1 respectively, i.e., `s0s` only has the party's seed.
+    ///
+    /// Returns the number of mismatches, which should be 0.
+    /// Fails the same as [`Dcf::eval`] for either key.
+    pub fn sample_and_check<R>(
+        &self,
+        f: &CmpFn<N, LAMBDA>,
+        bound: BoundState,
+        k0: &Share<LAMBDA>,
+        k1: &Share<LAMBDA>,
+        samples: usize,
+        rng: &mut R,
+    ) -> Result<usize, DcfError>
+    where
+        R: Rng + ?Sized,
+    {
+        let xs: Vec<[u8; N]> = (0..samples)
+            .map(|_| {
+                let mut x = [0; N];
+                rng.fill_bytes(&mut x);
+                x
+            })
+            .collect();
+        let xs: Vec<&[u8; N]> = xs.iter().collect();
+        let mut ys0 = vec![[0; LAMBDA]; samples];
+        let mut ys1 = vec![[0; LAMBDA]; samples];
+        self.eval(false, k0, &xs, &mut ys0.iter_mut().collect::<Vec<_>>())?;
+        self.eval(true, k1, &xs, &mut ys1.iter_mut().collect::<Vec<_>>())?;
+        Ok(xs
+            .iter()
+            .zip(ys0.iter().zip(ys1.iter()))
+            .filter(|(x, (y0, y1))| self.reconstruct(y0, y1) != f.eval_plain(x, bound))
+            .count())
+    }
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+    use crate::{ArithGroup, XorGroup};
+
+    #[test]
+    fn test_sample_and_check_no_mismatches() {
+        fn run<G: Group<16>>() {
+            let dcf = DcfImpl::<16, 16, _, G>::new(Aes256HirosePrg::new(KEYS));
+            let s0s: [[u8; 16]; 2] = thread_rng().gen();
+            // Alpha in the middle of the domain so that both sides are sampled
+            let mut alpha: [u8; 16] = thread_rng().gen();
+            alpha[0] = 0x80;
+            let f = CmpFn {
+                alpha,
+                beta: thread_rng().gen(),
+            };
+            let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::GtBeta).unwrap();
+            let [k0, k1] = k.split();
+            let mismatches =
+                dcf.sample_and_check(&f, BoundState::GtBeta, &k0, &k1, 200, &mut thread_rng());
+            assert_eq!(mismatches, Ok(0));
+            // Checking against the other bound flags the mismatches
+            let mismatches =
+                dcf.sample_and_check(&f, BoundState::LtBeta, &k0, &k1, 200, &mut thread_rng());
+            assert_eq!(mismatches, Ok(200));
+        }
+        run::<XorGroup>();
+        run::<ArithGroup>();
+    }
+
+    /// Broken PRG whose outputs are all 0
+    struct ZeroPrg;
+
+    impl Prg<16> for ZeroPrg {
+        fn gen(&self, _seed: &[u8; 16]) -> [([u8; 16], [u8; 16], bool); 2] {
+            [([0; 16], [0; 16], false); 2]
+        }
+    }
+
+    #[test]
+    fn test_dcf_statistical_uniformity_test() {
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let xs: Vec<[u8; 4]> = (0..4096u32).map(|i| (i * 0x9e37).to_be_bytes()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().collect();
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, k1] = k.split();
+        // Beyond 6 standard deviations from the mean 255
+        [(&k0, false), (&k1, true)].iter().for_each(|(kb, b)| {
+            let stat = dcf.statistical_uniformity_test(*b, kb, &xs).unwrap();
+            assert!((100.0..400.0).contains(&stat), "{}", stat);
+        });
+        let dcf = DcfImpl::<4, 16, _>::new(ZeroPrg);
+        let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+        let [k0, _] = k.split();
+        let stat = dcf.statistical_uniformity_test(false, &k0, &xs).unwrap();
+        assert!(stat > 10000.0, "{}", stat);
+        assert_eq!(
+            dcf.statistical_uniformity_test(false, &k0, &[]),
+            Err(DcfError::NoSamplePoints)
+        );
+    }
+
+    #[test]
+    fn test_simulate_same_as_eval_plain() {
+        let f = CmpFn {
+            alpha: thread_rng().gen(),
+            beta: thread_rng().gen(),
+        };
+        let xs: Vec<[u8; 4]> = (0..100).map(|_| thread_rng().gen()).collect();
+        let xs: Vec<&[u8; 4]> = xs.iter().chain([&f.alpha]).collect();
+        let ys_plain: Vec<[u8; 16]> = xs
+            .iter()
+            .map(|x| f.eval_plain(x, BoundState::LtBeta))
+            .collect();
+        let dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+        let ys = dcf.simulate(&f, BoundState::LtBeta, &xs, &mut thread_rng());
+        assert_eq!(ys, ys_plain);
+        let dcf = DcfImpl::<4, 16, _, ArithGroup>::new(Aes256HirosePrg::new(KEYS));
+        let ys = dcf.simulate(&f, BoundState::LtBeta, &xs, &mut thread_rng());
+        assert_eq!(ys, ys_plain);
+    }
+}
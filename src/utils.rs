@@ -1,6 +1,12 @@
 // Copyright (C) myl7
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "multithread")]
+use rayon::prelude::*;
+use std::fmt;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
 pub fn xor<const LAMBDA: usize>(xs: &[&[u8; LAMBDA]]) -> [u8; LAMBDA] {
     let mut res = [0; LAMBDA];
     for i in 0..LAMBDA {
@@ -18,3 +24,92 @@ pub fn xor_inplace<const LAMBDA: usize>(lhs: &mut [u8; LAMBDA], rhss: &[&[u8; LA
         }
     }
 }
+
+/// Bytes printed as hex in `Debug`
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Debug for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Bytes printed as hex of only the first 4 of them in `Debug`, e.g., to tell values apart without printing them
+pub struct HexPrefix<'a>(pub &'a [u8]);
+
+impl fmt::Debug for HexPrefix<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&Hex(&self.0[..self.0.len().min(4)]), f)?;
+        if self.0.len() > 4 {
+            f.write_str("..")?;
+        }
+        Ok(())
+    }
+}
+
+/// Key material printed as [`HexPrefix`] in `Debug`, or as [`Hex`] with the `debug-secrets` feature
+pub struct Secret<'a>(pub &'a [u8]);
+
+impl fmt::Debug for Secret<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "debug-secrets")]
+        return fmt::Debug::fmt(&Hex(self.0), f);
+        #[cfg(not(feature = "debug-secrets"))]
+        return fmt::Debug::fmt(&HexPrefix(self.0), f);
+    }
+}
+
+/// CRC-32/ISO-HDLC, the one of zlib and Ethernet
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Zeroes the output of [`crate::Prg::gen`], which holds the seeds of the children, before it goes out of scope
+#[cfg(feature = "zeroize")]
+pub fn zeroize_expanded<const LAMBDA: usize>(
+    expanded: &mut [([u8; LAMBDA], [u8; LAMBDA], bool); 2],
+) {
+    expanded.iter_mut().for_each(|(s, v, t)| {
+        s.zeroize();
+        v.zeroize();
+        t.zeroize();
+    });
+}
+
+/// Runs `f` on each pair of `xs` and `ys`, in parallel with the `multithread` feature
+#[cfg(feature = "multithread")]
+pub fn for_each_zip<X, Y, F>(xs: &[X], ys: &mut [Y], f: F)
+where
+    X: Sync,
+    Y: Send,
+    F: Fn(&X, &mut Y) + Sync + Send,
+{
+    xs.par_iter()
+        .zip(ys.par_iter_mut())
+        .for_each(|(x, y)| f(x, y));
+}
+#[cfg(not(feature = "multithread"))]
+pub fn for_each_zip<X, Y, F>(xs: &[X], ys: &mut [Y], f: F)
+where
+    F: Fn(&X, &mut Y),
+{
+    xs.iter().zip(ys.iter_mut()).for_each(|(x, y)| f(x, y));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Outputs as additive shares over `$\mathbb{Z}_{2^{32}}$`, the common modulus of integer MPC
+
+use crate::group::ArithGroup;
+use crate::{BoundState, CmpFn, Dcf, DcfError, DcfImpl, Prg, Share};
+
+/// Byte size of seeds and outputs internally.
+/// Seeds must be kept at the security parameter, so outputs are reduced afterwards
+/// instead of shrinking `LAMBDA` to 4.
+const LAMBDA: usize = 16;
+
+/// DCF whose outputs are `u32` shares that sum (wrapping) to `$\beta$` where `$f(x) = \beta$`.
+///
+/// [`ArithGroup`] of `LAMBDA = 16` with the outputs reduced modulo `$2^{32}$`.
+pub struct DcfZ2_32<const N: usize, PrgT>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    dcf: DcfImpl<N, LAMBDA, PrgT, ArithGroup>,
+}
+
+impl<const N: usize, PrgT> DcfZ2_32<N, PrgT>
+where
+    PrgT: Prg<LAMBDA>,
+{
+    pub fn new(prg: PrgT) -> Self {
+        Self {
+            dcf: DcfImpl::new(prg),
+        }
+    }
+
+    /// See [`Dcf::gen`]
+    pub fn gen(
+        &self,
+        alpha: &[u8; N],
+        beta: u32,
+        s0s: [&[u8; LAMBDA]; 2],
+        bound: BoundState,
+    ) -> Result<Share<LAMBDA>, DcfError> {
+        let f = CmpFn {
+            alpha: *alpha,
+            beta: (beta as u128).to_be_bytes(),
+        };
+        self.dcf.gen(&f, s0s, bound)
+    }
+
+    /// See [`Dcf::eval`]. Returns the party's `u32` share for each of `xs`.
+    pub fn eval(&self, b: bool, k: &Share<LAMBDA>, xs: &[&[u8; N]]) -> Result<Vec<u32>, DcfError> {
+        let mut ys = vec![[0; LAMBDA]; xs.len()];
+        self.dcf
+            .eval(b, k, xs, &mut ys.iter_mut().collect::<Vec<_>>())?;
+        Ok(ys
+            .iter()
+            .map(|y| u32::from_be_bytes(y[LAMBDA - 4..].try_into().unwrap()))
+            .collect())
+    }
+}
+
+/// Reconstructs an output of [`DcfZ2_32::eval`] from the shares of the 2 parties
+pub fn reconstruct(y0: u32, y1: u32) -> u32 {
+    y0.wrapping_add(y1)
+}
+
+#[cfg(all(test, feature = "prg"))]
+mod tests {
+    use super::*;
+
+    use rand::{thread_rng, Rng};
+
+    use crate::prg::Aes256HirosePrg;
+    use crate::test_fixtures::KEYS;
+
+    #[test]
+    fn test_dcf_z2_32_gen_then_eval_reconstruct_ok() {
+        let dcf = DcfZ2_32::<4, _>::new(Aes256HirosePrg::new(KEYS));
+        let s0s: [[u8; 16]; 2] = thread_rng().gen();
+        let alpha: u32 = thread_rng().gen();
+        let beta: u32 = thread_rng().gen();
+        let [k0, k1] = dcf
+            .gen(
+                &alpha.to_be_bytes(),
+                beta,
+                [&s0s[0], &s0s[1]],
+                BoundState::GtBeta,
+            )
+            .unwrap()
+            .split();
+        let xs: Vec<u32> = (0..100)
+            .map(|_| thread_rng().gen())
+            .chain([alpha, alpha.wrapping_add(1), alpha.wrapping_sub(1)])
+            .collect();
+        let xs_bytes: Vec<[u8; 4]> = xs.iter().map(|x| x.to_be_bytes()).collect();
+        let xs_bytes: Vec<&[u8; 4]> = xs_bytes.iter().collect();
+        let ys0 = dcf.eval(false, &k0, &xs_bytes).unwrap();
+        let ys1 = dcf.eval(true, &k1, &xs_bytes).unwrap();
+        xs.iter().enumerate().for_each(|(i, x)| {
+            let expected = (*x > alpha) as u32 * beta;
+            assert_eq!(reconstruct(ys0[i], ys1[i]), expected);
+        });
+    }
+
+    #[test]
+    fn test_dcf_z2_32_odd_beta_cws_hide_alpha() {
+        let dcf = DcfZ2_32::<4, _>::new(Aes256HirosePrg::new(KEYS));
+        crate::test_fixtures::assert_cws_hide_alpha(|alpha, s0s| {
+            let beta = thread_rng().gen::<u32>() | 1;
+            dcf.gen(alpha, beta, s0s, BoundState::LtBeta).unwrap()
+        });
+    }
+}
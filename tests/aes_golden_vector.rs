@@ -0,0 +1,149 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Golden vector of [`Aes256HirosePrg`].
+//!
+//! The `aes` crate picks AES-NI or the software implementation at runtime,
+//! and both must produce the same keys and outputs.
+//! CI runs this test once as is and once with `RUSTFLAGS="--cfg aes_force_soft"`,
+//! which forces the software implementation,
+//! so that a divergence between the backends fails one of the runs.
+
+#![cfg(feature = "prg")]
+
+use dcf::prg::Aes256HirosePrg;
+use dcf::{BoundState, CmpFn, Dcf, DcfImpl, Prg};
+
+const KEYS: [&[u8; 32]; 2] = [&[0x11; 32], &[0x22; 32]];
+const S0S: [[u8; 16]; 2] = [[0x33; 16], [0x44; 16]];
+const ALPHA: u8 = 0x5a;
+const BETA: u128 = 0x03119712438ae92381a8dea88f20c0bb;
+
+/// bincode of the [`dcf::Share`] of `KEYS`, `S0S`, `ALPHA`, `BETA`, and [`BoundState::LtBeta`]
+const EXPECTED_SHARE: &str = concat!(
+    "0200000000000000",
+    "1000000000000000",
+    "33333333333333333333333333333333",
+    "1000000000000000",
+    "44444444444444444444444444444444",
+    "0800000000000000",
+    "1000000000000000",
+    "b2b6c7554aabe55b13e496e3dab9562a",
+    "1000000000000000",
+    "0695232fe25921130c856c83ee03d9fa",
+    "0000",
+    "1000000000000000",
+    "57eba593d21d4028de4f2858731c2584",
+    "1000000000000000",
+    "43784a4c3b79a22cfccbbe216e8364a6",
+    "0101",
+    "1000000000000000",
+    "e4741fa11d1ba993f3cbcd24b87070d4",
+    "1000000000000000",
+    "9063e3bedb5320ee4ae0b83823ef53e8",
+    "0000",
+    "1000000000000000",
+    "77091dddc91ebafe1519f105d65debd0",
+    "1000000000000000",
+    "3d96fa231db593433ff326338ef948df",
+    "0100",
+    "1000000000000000",
+    "84a2c6d3f254ec0ed49dc16d6ea4a484",
+    "1000000000000000",
+    "c3924ca88ce1c96218aa1373f73c189a",
+    "0100",
+    "1000000000000000",
+    "0050252b0b55895c0247f57579f5373c",
+    "1000000000000000",
+    "94a6f01d6bb0dd36459dcce40043c40d",
+    "0001",
+    "1000000000000000",
+    "6243465c56023621eae48c38a0f04364",
+    "1000000000000000",
+    "3b94a7fbc264e2f53dd2bee5afbed360",
+    "0000",
+    "1000000000000000",
+    "444599e48a6a024db4a1b95203ff918e",
+    "1000000000000000",
+    "0ea21ea4a5cb9762560477d7b91b56f8",
+    "0001",
+    "1000000000000000",
+    "33e49127b5f5e7c86376002a325a5d6c",
+    // `n_bytes`
+    "0100000000000000",
+    // `prg_check`
+    "01",
+    "1000000000000000",
+    "22d08b824e5b3485858c134658b5f22e",
+);
+
+/// Outputs of party 0 at `EXPECTED_XS`
+const EXPECTED_XS: [u8; 4] = [0x00, 0x59, 0x5a, 0xff];
+const EXPECTED_YS0: [u128; 4] = [
+    0xd6e9688460d08e04fe75465746635bfd,
+    0xd2e408aadf1b095d5cdb8e0f2937e8a6,
+    0xe45b8d3a148d6a40a72bc85764dc57d2,
+    0x88423923f73391e3cf90ce9fcc6f673d,
+];
+
+fn from_hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_aes_gen_matches_golden_vector() {
+    let dcf = DcfImpl::<1, 16, _>::new(Aes256HirosePrg::new(KEYS));
+    let f = CmpFn {
+        alpha: [ALPHA],
+        beta: BETA.to_be_bytes(),
+    };
+    let k = dcf.gen(&f, [&S0S[0], &S0S[1]], BoundState::LtBeta).unwrap();
+    assert_eq!(bincode::serialize(&k).unwrap(), from_hex(EXPECTED_SHARE));
+}
+
+#[test]
+fn test_aes_eval_matches_golden_vector() {
+    let dcf = DcfImpl::<1, 16, _>::new(Aes256HirosePrg::new(KEYS));
+    let f = CmpFn {
+        alpha: [ALPHA],
+        beta: BETA.to_be_bytes(),
+    };
+    let mut k = dcf.gen(&f, [&S0S[0], &S0S[1]], BoundState::LtBeta).unwrap();
+    k.s0s = vec![k.s0s[0]];
+    let xs: Vec<[u8; 1]> = EXPECTED_XS.iter().map(|x| [*x]).collect();
+    let xs: Vec<&[u8; 1]> = xs.iter().collect();
+    let mut ys = vec![[0; 16]; xs.len()];
+    dcf.eval(false, &k, &xs, &mut ys.iter_mut().collect::<Vec<_>>())
+        .unwrap();
+    let ys: Vec<u128> = ys.into_iter().map(u128::from_be_bytes).collect();
+    assert_eq!(ys, EXPECTED_YS0);
+}
+
+/// An unencrypted PRG output is an input seed, or the seed XORed with the all-1 constant of Hirose, up to the last bit cleared in the output seeds.
+/// The golden vectors are only meaningful if none of them is.
+#[test]
+fn test_aes_outputs_not_input_seeds() {
+    let prg = Aes256HirosePrg::new(KEYS);
+    let inputs: Vec<[u8; 16]> = S0S.iter().flat_map(|s| [*s, s.map(|x| x ^ 0xff)]).collect();
+    let differs = |out: &[u8; 16], input: &[u8; 16]| out[..15] != input[..15];
+    S0S.iter().for_each(|seed| {
+        prg.gen(seed).iter().for_each(|(s, v, _)| {
+            inputs.iter().for_each(|input| {
+                assert!(differs(s, input));
+                assert!(differs(v, input));
+            });
+        });
+    });
+
+    let dcf = DcfImpl::<1, 16, _>::new(prg);
+    let f = CmpFn {
+        alpha: [ALPHA],
+        beta: BETA.to_be_bytes(),
+    };
+    let k = dcf.gen(&f, [&S0S[0], &S0S[1]], BoundState::LtBeta).unwrap();
+    let seeds_xor: [u8; 16] = std::array::from_fn(|i| S0S[0][i] ^ S0S[1][i]);
+    assert!(differs(&k.cws[0].s, &seeds_xor));
+}
@@ -0,0 +1,81 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serial eval must not allocate per input, so that loops evaluating inputs one at a time need no reusable context.
+//!
+//! The allocations are counted per thread, so that the other tests running in parallel are not counted.
+
+#![cfg(feature = "prg")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use dcf::prg::Aes256HirosePrg;
+use dcf::{BoundState, CmpFn, Dcf, DcfImpl};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|allocs| allocs.set(allocs.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocs_in<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCS.with(|allocs| allocs.get());
+    f();
+    ALLOCS.with(|allocs| allocs.get()) - before
+}
+
+const KEYS: [&[u8; 32]; 2] = [&[0x11; 32], &[0x22; 32]];
+const S0S: [[u8; 16]; 2] = [[0x33; 16], [0x44; 16]];
+
+#[test]
+fn test_dcf_serial_eval_no_alloc() {
+    let mut dcf = DcfImpl::<4, 16, _>::new(Aes256HirosePrg::new(KEYS));
+    let f = CmpFn {
+        alpha: 0x12345678u32.to_be_bytes(),
+        beta: [0x55; 16],
+    };
+    let [k0, _] = dcf
+        .gen(&f, [&S0S[0], &S0S[1]], BoundState::LtBeta)
+        .unwrap()
+        .split();
+    let xs: Vec<[u8; 4]> = (0..256u32)
+        .map(|i| (i * 0x01010101).to_be_bytes())
+        .collect();
+    let xs: Vec<&[u8; 4]> = xs.iter().collect();
+    let mut ys = vec![[0; 16]; xs.len()];
+    let mut ys: Vec<&mut [u8; 16]> = ys.iter_mut().collect();
+    dcf.set_parallel_threshold(usize::MAX);
+    // The counter itself works
+    assert_eq!(
+        allocs_in(|| {
+            std::hint::black_box(vec![0u8; 1]);
+        }),
+        1
+    );
+
+    assert_eq!(
+        allocs_in(|| xs.iter().for_each(|x| {
+            std::hint::black_box(dcf.eval_at(false, &k0, x).unwrap());
+        })),
+        0
+    );
+    assert_eq!(
+        allocs_in(|| dcf.eval_adaptive(false, &k0, &xs, &mut ys).unwrap()),
+        0
+    );
+}
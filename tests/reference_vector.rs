@@ -0,0 +1,224 @@
+// Copyright (C) myl7
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reference test vector of the DCF in Boyle et al., EUROCRYPT 2021.
+//!
+//! The PRG is a fixed toy one so that the vector does not depend on any cipher implementation.
+//! The reference implementation below is written from the paper independently of the crate,
+//! with `u128` as the 16-byte group of XOR, and `EXPECTED_*` are its committed outputs.
+
+use dcf::{BoundState, CmpFn, Cw, Dcf, DcfImpl, Prg};
+
+/// Toy PRG based on SplitMix64. **Not** secure. Only for fixed test vectors.
+struct ToyPrg;
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn toy_prg(seed: u128) -> [(u128, u128, bool); 2] {
+    let mut state = (seed >> 64) as u64 ^ (seed as u64).rotate_left(32);
+    let mut next = || ((splitmix64(&mut state) as u128) << 64) | splitmix64(&mut state) as u128;
+    let (sl, vl, sr, vr) = (next(), next(), next(), next());
+    let ts = splitmix64(&mut state);
+    [(sl, vl, ts & 1 == 1), (sr, vr, ts & 2 == 2)]
+}
+
+impl Prg<16> for ToyPrg {
+    fn gen(&self, seed: &[u8; 16]) -> [([u8; 16], [u8; 16], bool); 2] {
+        toy_prg(u128::from_be_bytes(*seed)).map(|(s, v, t)| (s.to_be_bytes(), v.to_be_bytes(), t))
+    }
+}
+
+/// `Gen` of the paper with `$x < \alpha$` and the XOR group
+fn ref_gen(alpha: u8, beta: u128, s0s: [u128; 2]) -> (Vec<(u128, u128, bool, bool)>, u128) {
+    let (mut s0, mut s1) = (s0s[0], s0s[1]);
+    let (mut t0, mut t1) = (false, true);
+    let mut v_alpha = 0u128;
+    let mut cws = vec![];
+    for i in 0..8 {
+        let alpha_i = (alpha >> (7 - i)) & 1 == 1;
+        let g0 = toy_prg(s0);
+        let g1 = toy_prg(s1);
+        let (keep, lose) = if alpha_i { (1, 0) } else { (0, 1) };
+        let s_cw = g0[lose].0 ^ g1[lose].0;
+        let mut v_cw = g0[lose].1 ^ g1[lose].1 ^ v_alpha;
+        if lose == 0 {
+            v_cw ^= beta;
+        }
+        v_alpha ^= g0[keep].1 ^ g1[keep].1 ^ v_cw;
+        let tl_cw = g0[0].2 ^ g1[0].2 ^ alpha_i ^ true;
+        let tr_cw = g0[1].2 ^ g1[1].2 ^ alpha_i;
+        let t_cw_keep = [tl_cw, tr_cw][keep];
+        cws.push((s_cw, v_cw, tl_cw, tr_cw));
+        s0 = g0[keep].0 ^ if t0 { s_cw } else { 0 };
+        s1 = g1[keep].0 ^ if t1 { s_cw } else { 0 };
+        t0 = g0[keep].2 ^ (t0 & t_cw_keep);
+        t1 = g1[keep].2 ^ (t1 & t_cw_keep);
+    }
+    (cws, s0 ^ s1 ^ v_alpha)
+}
+
+/// `Eval` of the paper with the XOR group
+fn ref_eval(b: bool, seed: u128, cws: &[(u128, u128, bool, bool)], cw_np1: u128, x: u8) -> u128 {
+    let (mut s, mut t) = (seed, b);
+    let mut v = 0u128;
+    for (i, &(s_cw, v_cw, tl_cw, tr_cw)) in cws.iter().enumerate() {
+        let g = toy_prg(s);
+        let side = ((x >> (7 - i)) & 1) as usize;
+        let t_cw = [tl_cw, tr_cw][side];
+        v ^= g[side].1 ^ if t { v_cw } else { 0 };
+        s = g[side].0 ^ if t { s_cw } else { 0 };
+        t = g[side].2 ^ (t & t_cw);
+    }
+    v ^ s ^ if t { cw_np1 } else { 0 }
+}
+
+const ALPHA: u8 = 0x5a;
+const BETA: u128 = 0x03119712438ae92381a8dea88f20c0bb;
+const S0S: [u128; 2] = [
+    0x2a4c8f2579125a942a458f242b4e4819,
+    0x9b15c80fb7bc21719e89b8f70ea0539d,
+];
+
+const EXPECTED_CWS: [(u128, u128, bool, bool); 8] = [
+    (
+        0xe2b9fcb847a785269f1e12abb77e4be9,
+        0xe3d2072e02bedbc7b9aec61c7f2e99f7,
+        true,
+        false,
+    ),
+    (
+        0xe5f88adb0db0cbe86fe109056d60d007,
+        0xf3b947c31315884703e402213ac1a832,
+        false,
+        true,
+    ),
+    (
+        0xd57f6d622fa9f296b6a87b91c1eb41cf,
+        0x6dfe5eb4a3e6e3ec250a1aa1c04a9403,
+        true,
+        false,
+    ),
+    (
+        0x6929f8857afb7fdd02a8b26ee8a6b2ab,
+        0x4ca3954c27dcca2efcef5df949d57da2,
+        true,
+        true,
+    ),
+    (
+        0xeb340d998faa88552280cc1b7f391311,
+        0x2fe256e73534c7eec0502e2b6aef86d2,
+        true,
+        false,
+    ),
+    (
+        0xe48b9e93e414fe29e696e55f451a9774,
+        0x4d39ec123ce7e238862bee8654638b5e,
+        false,
+        false,
+    ),
+    (
+        0xfacd5478065c366983aff501b45cf605,
+        0x9d3ca030a4202ab32af9dff1d158e69e,
+        false,
+        false,
+    ),
+    (
+        0x3446da0fd4765def2e17decd22e94c85,
+        0x2b4d35214e3f432ab48e5ef5e76687fc,
+        true,
+        false,
+    ),
+];
+const EXPECTED_CW_NP1: u128 = 0xa2476a2659bdd8322472d76ec290eec7;
+/// Outputs of party 0 at `x = 0x00, ALPHA - 1, ALPHA, 0xff`
+const EXPECTED_YS0: [u128; 4] = [
+    0xd1827b93a6f27789a6cdf12ce2b579eb,
+    0xd512a898c885159f34da9c86a6de7161,
+    0x65ace5502111b1b8369b05c6474c53ad,
+    0xdf3775eff4958b565eb1531ba7d7cf81,
+];
+
+fn cw_to_u128s(cw: &Cw<16>) -> (u128, u128, bool, bool) {
+    (
+        u128::from_be_bytes(cw.s),
+        u128::from_be_bytes(cw.v),
+        cw.tl,
+        cw.tr,
+    )
+}
+
+#[test]
+fn test_reference_impl_matches_committed_vector() {
+    let (cws, cw_np1) = ref_gen(ALPHA, BETA, S0S);
+    assert_eq!(cws, EXPECTED_CWS);
+    assert_eq!(cw_np1, EXPECTED_CW_NP1);
+    let ys0 = [0x00, ALPHA - 1, ALPHA, 0xff].map(|x| ref_eval(false, S0S[0], &cws, cw_np1, x));
+    assert_eq!(ys0, EXPECTED_YS0);
+    (0..=255u8).for_each(|x| {
+        let y = ref_eval(false, S0S[0], &cws, cw_np1, x) ^ ref_eval(true, S0S[1], &cws, cw_np1, x);
+        assert_eq!(y, if x < ALPHA { BETA } else { 0 });
+    });
+}
+
+#[test]
+fn test_dcf_gen_matches_reference_vector() {
+    let dcf = DcfImpl::<1, 16, _>::new(ToyPrg);
+    let f = CmpFn {
+        alpha: [ALPHA],
+        beta: BETA.to_be_bytes(),
+    };
+    let s0s = S0S.map(u128::to_be_bytes);
+    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+    assert_eq!(k.s0s, s0s);
+    assert_eq!(
+        k.cws.iter().map(cw_to_u128s).collect::<Vec<_>>(),
+        EXPECTED_CWS
+    );
+    assert_eq!(u128::from_be_bytes(k.cw_np1), EXPECTED_CW_NP1);
+}
+
+#[test]
+fn test_dcf_eval_matches_reference_vector() {
+    let dcf = DcfImpl::<1, 16, _>::new(ToyPrg);
+    let f = CmpFn {
+        alpha: [ALPHA],
+        beta: BETA.to_be_bytes(),
+    };
+    let s0s = S0S.map(u128::to_be_bytes);
+    let k = dcf.gen(&f, [&s0s[0], &s0s[1]], BoundState::LtBeta).unwrap();
+    let mut k0 = k.clone();
+    k0.s0s = vec![k0.s0s[0]];
+    let mut k1 = k.clone();
+    k1.s0s = vec![k1.s0s[1]];
+    let xs: Vec<[u8; 1]> = (0..=255u8).map(|x| [x]).collect();
+    let xs: Vec<&[u8; 1]> = xs.iter().collect();
+    let mut ys0 = vec![[0; 16]; 256];
+    let mut ys1 = vec![[0; 16]; 256];
+    dcf.eval(false, &k0, &xs, &mut ys0.iter_mut().collect::<Vec<_>>())
+        .unwrap();
+    dcf.eval(true, &k1, &xs, &mut ys1.iter_mut().collect::<Vec<_>>())
+        .unwrap();
+    let ys0: Vec<u128> = ys0.into_iter().map(u128::from_be_bytes).collect();
+    let ys1: Vec<u128> = ys1.into_iter().map(u128::from_be_bytes).collect();
+    assert_eq!(
+        [0x00, ALPHA - 1, ALPHA, 0xff].map(|x| ys0[x as usize]),
+        EXPECTED_YS0
+    );
+    (0..=255u8).for_each(|x| {
+        let i = x as usize;
+        assert_eq!(
+            ys0[i],
+            ref_eval(false, S0S[0], &EXPECTED_CWS, EXPECTED_CW_NP1, x)
+        );
+        assert_eq!(
+            ys1[i],
+            ref_eval(true, S0S[1], &EXPECTED_CWS, EXPECTED_CW_NP1, x)
+        );
+    });
+}